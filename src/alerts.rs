@@ -1,8 +1,18 @@
-use crate::config::AlertConfig;
+use std::collections::{HashMap, HashSet};
+
+use crate::config::{AlertConfig, AlertRule, ConditionPolicy, EffectiveThresholds};
+use crate::collectors::kmsg::{KernelEvent, KernelEventKind, KernelLog};
+use crate::collectors::nfs::NfsMountStats;
+use crate::collectors::pressure::SystemPressure;
 use crate::models::device::BlockDevice;
 use crate::models::filesystem::Filesystem;
-use crate::models::smart::SmartStatus;
+use crate::models::smart::{SelfTestResult, SmartStatus};
 use crate::models::volume::{RaidArray, ZfsPool};
+use crate::util::expr::{self, Context, Value};
+use crate::util::glob;
+use crate::util::health_score::health_score;
+use crate::util::human;
+use crate::util::smart_history::{self, SmartHistory};
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub enum Severity {
@@ -19,6 +29,14 @@ impl Severity {
             Severity::Critical => "CRIT",
         }
     }
+    /// Inverse of `label()`; unknown labels map to Info.
+    pub fn from_label(s: &str) -> Self {
+        match s {
+            "CRIT" => Severity::Critical,
+            "WARN" => Severity::Warning,
+            _      => Severity::Info,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -26,6 +44,11 @@ pub struct Alert {
     pub severity: Severity,
     pub device:   Option<String>,
     pub mount:    Option<String>,
+    /// Identifier of the check that produced this alert, e.g. "temperature",
+    /// "smart.attr.5", "fs.usage" or "rule.<id>" for user-defined rules.
+    pub rule:     String,
+    /// Measured value that tripped the check (°C, %, ms, raw count…), when numeric.
+    pub value:    Option<f64>,
     pub message:  String,
 }
 
@@ -36,19 +59,105 @@ impl Alert {
         else { String::new() }
     }
 
-    /// Stable string key identifying this alert condition (for ack/cooldown).
+    /// Stable string key identifying this alert condition at this severity (for ack/cooldown).
+    /// Built from the rule ID rather than the message so a changing reading
+    /// (e.g. 61°C → 62°C) does not look like a new alert.
     pub fn key(&self) -> String {
-        format!("{}{}{}", self.severity.label(), self.prefix(), self.message)
+        format!("{}{}{}", self.severity.label(), self.prefix(), self.rule)
+    }
+
+    /// Severity-independent identity of the condition: "[sda] temperature".
+    pub fn condition(&self) -> String {
+        format!("{}{}", self.prefix(), self.rule)
+    }
+}
+
+/// Condition identity for a target (device or mount) and rule, matching `Alert::condition`.
+fn cond_key(target: &str, rule: &str) -> String {
+    format!("[{}] {}", target, rule)
+}
+
+/// A reading found past one of its thresholds by `level`.
+struct Level {
+    severity: Severity,
+    /// The threshold it is past.
+    limit:    f64,
+    rising:   bool,
+    /// Only past the clear threshold: the alert is being held by hysteresis.
+    clearing: bool,
+}
+
+impl Level {
+    /// "≥ warning threshold 50°C", or "still above clear threshold 45°C" while
+    /// hysteresis holds the alert.
+    fn versus(&self, unit: &str) -> String {
+        if self.clearing {
+            let side = if self.rising { "above" } else { "below" };
+            format!("still {} clear threshold {}{}", side, self.limit, unit)
+        } else {
+            let name = if self.severity == Severity::Critical { "critical" } else { "warning" };
+            format!("{} {} threshold {}{}", if self.rising { "≥" } else { "≤" }, name, self.limit, unit)
+        }
+    }
+
+    /// " (still above clear threshold 80%)" while hysteresis holds the alert, else empty.
+    fn clearing_note(&self, unit: &str) -> String {
+        if self.clearing { format!(" ({})", self.versus(unit)) } else { String::new() }
     }
 }
 
+/// Severity level for a numeric reading against warn/crit thresholds (None = disabled).
+///
+/// `rising` is true when higher values are worse. While the condition is already
+/// firing (`held`), the policy's clear thresholds replace the trigger thresholds so
+/// a reading hovering around a threshold does not flap.
+fn level(
+    value:  f64,
+    warn:   Option<f64>,
+    crit:   Option<f64>,
+    rising: bool,
+    policy: Option<&ConditionPolicy>,
+    held:   Option<&Severity>,
+) -> Option<Level> {
+    let past = |t: f64| if rising { value >= t } else { value <= t };
+    let crit_t = match (held, policy.and_then(|p| p.clear_crit)) {
+        (Some(Severity::Critical), Some(c)) => crit.map(|_| c),
+        _                                   => crit,
+    };
+    let warn_t = match (held, policy.and_then(|p| p.clear_warn)) {
+        (Some(_), Some(c)) => warn.map(|_| c),
+        _                  => warn,
+    };
+    let hit = |severity, trigger: Option<f64>, limit: f64| {
+        let clearing = !trigger.is_some_and(past);
+        Level { severity, limit: if clearing { limit } else { trigger.unwrap_or(limit) }, rising, clearing }
+    };
+    crit_t.filter(|&t| past(t)).map(|t| hit(Severity::Critical, crit, t))
+        .or_else(|| warn_t.filter(|&t| past(t)).map(|t| hit(Severity::Warning, warn, t)))
+}
+
+/// Evaluate all alert conditions against current state, with no memory of
+/// earlier samples. Used by the one-shot CLI modes.
+pub fn evaluate(devices: &[BlockDevice], filesystems: &[Filesystem], alert_cfg: &AlertConfig) -> Vec<Alert> {
+    evaluate_with_state(devices, filesystems, alert_cfg, &HashMap::new())
+}
+
 /// Evaluate all alert conditions against current state.
+/// `held` maps `Alert::condition()` → severity for conditions currently firing,
+/// so clear thresholds (hysteresis) can be applied.
 /// Returns a freshly built list sorted Critical → Warning → Info.
-pub fn evaluate(devices: &[BlockDevice], filesystems: &[Filesystem], alert_cfg: &AlertConfig) -> Vec<Alert> {
-    let thr   = &alert_cfg.thresholds;
+pub fn evaluate_with_state(
+    devices:     &[BlockDevice],
+    filesystems: &[Filesystem],
+    alert_cfg:   &AlertConfig,
+    held:        &HashMap<String, Severity>,
+) -> Vec<Alert> {
     let mut alerts: Vec<Alert> = Vec::new();
 
     for dev in devices {
+        let eff = device_thresholds(dev, alert_cfg);
+        let thr = &eff.thresholds;
+
         // ── SMART / temperature ───────────────────────────────────────
         if let Some(smart) = &dev.smart {
             // Overall health
@@ -57,6 +166,8 @@ pub fn evaluate(devices: &[BlockDevice], filesystems: &[Filesystem], alert_cfg:
                     severity: Severity::Critical,
                     device:   Some(dev.name.clone()),
                     mount:    None,
+                    rule:     "smart.failed".into(),
+                    value:    None,
                     message:  "SMART health check FAILED".into(),
                 });
             }
@@ -68,19 +179,18 @@ pub fn evaluate(devices: &[BlockDevice], filesystems: &[Filesystem], alert_cfg:
                 } else {
                     (thr.temperature_warn_ssd, thr.temperature_crit_ssd)
                 };
-                if temp >= crit {
-                    alerts.push(Alert {
-                        severity: Severity::Critical,
-                        device:   Some(dev.name.clone()),
-                        mount:    None,
-                        message:  format!("Temperature {}°C ≥ critical threshold {}°C", temp, crit),
-                    });
-                } else if temp >= warn {
+                if let Some(hit) = level(
+                    temp as f64, Some(warn as f64), Some(crit as f64), true,
+                    alert_cfg.policy("temperature"),
+                    held.get(&cond_key(&dev.name, "temperature")),
+                ) {
                     alerts.push(Alert {
-                        severity: Severity::Warning,
+                        severity: hit.severity.clone(),
                         device:   Some(dev.name.clone()),
                         mount:    None,
-                        message:  format!("Temperature {}°C ≥ warning threshold {}°C", temp, warn),
+                        rule:     "temperature".into(),
+                        value:    Some(temp as f64),
+                        message:  format!("Temperature {}°C {}", temp, hit.versus("°C")),
                     });
                 }
             }
@@ -92,6 +202,8 @@ pub fn evaluate(devices: &[BlockDevice], filesystems: &[Filesystem], alert_cfg:
                         severity: Severity::Warning,
                         device:   Some(dev.name.clone()),
                         mount:    None,
+                        rule:     format!("smart.prefail.{}", attr.id),
+                        value:    Some(attr.value as f64),
                         message:  format!(
                             "Pre-fail attr {} value {} near threshold {}",
                             attr.name, attr.value, attr.thresh
@@ -116,6 +228,8 @@ pub fn evaluate(devices: &[BlockDevice], filesystems: &[Filesystem], alert_cfg:
                             severity,
                             device:  Some(dev.name.clone()),
                             mount:   None,
+                            rule:    format!("smart.attr.{}", rule.attr),
+                            value:   Some(attr.raw_value as f64),
                             message,
                         });
                     }
@@ -132,6 +246,8 @@ pub fn evaluate(devices: &[BlockDevice], filesystems: &[Filesystem], alert_cfg:
                                 severity: Severity::Warning,
                                 device:   Some(dev.name.clone()),
                                 mount:    None,
+                                rule:     format!("smart.degraded.{}", curr_attr.id),
+                                value:    Some(curr_attr.value as f64),
                                 message:  format!(
                                     "Pre-fail attr {} degraded {} → {} (↓{})",
                                     curr_attr.name, prev_attr.value, curr_attr.value,
@@ -143,6 +259,39 @@ pub fn evaluate(devices: &[BlockDevice], filesystems: &[Filesystem], alert_cfg:
                 }
             }
 
+            // Self-test log
+            if let Some(last) = smart.self_tests.first() {
+                if last.result == SelfTestResult::Failed {
+                    let lba = last.failing_lba.map(|l| format!(" at LBA {}", l)).unwrap_or_default();
+                    alerts.push(Alert {
+                        severity: Severity::Critical,
+                        device:   Some(dev.name.clone()),
+                        mount:    None,
+                        rule:     "smart.selftest.failed".into(),
+                        value:    last.failing_lba.map(|l| l as f64),
+                        message:  format!("{} self-test failed{} ({})", last.test_type, lba, last.status),
+                    });
+                }
+            }
+            if thr.selftest_max_age_days > 0.0 && smart.self_test_supported && smart.self_test_remaining.is_none() {
+                let max_hours = thr.selftest_max_age_days * 24.0;
+                // Never tested: count the drive's whole powered-on life
+                let last = smart.hours_since_long_test();
+                if let Some(hours) = last.or(smart.power_on_hours).filter(|&h| h as f64 > max_hours) {
+                    alerts.push(Alert {
+                        severity: Severity::Warning,
+                        device:   Some(dev.name.clone()),
+                        mount:    None,
+                        rule:     "smart.selftest.overdue".into(),
+                        value:    Some(hours as f64 / 24.0),
+                        message:  match last {
+                            Some(h) => format!("last extended self-test {} power-on days ago", h / 24),
+                            None    => "no extended self-test on record".to_string(),
+                        },
+                    });
+                }
+            }
+
             // NVMe-specific
             if let Some(nvme) = &smart.nvme {
                 if nvme.media_errors > 0 {
@@ -150,6 +299,8 @@ pub fn evaluate(devices: &[BlockDevice], filesystems: &[Filesystem], alert_cfg:
                         severity: Severity::Warning,
                         device:   Some(dev.name.clone()),
                         mount:    None,
+                        rule:     "nvme.media_errors".into(),
+                        value:    Some(nvme.media_errors as f64),
                         message:  format!("{} uncorrectable media error(s)", nvme.media_errors),
                     });
                 }
@@ -158,6 +309,8 @@ pub fn evaluate(devices: &[BlockDevice], filesystems: &[Filesystem], alert_cfg:
                         severity: Severity::Warning,
                         device:   Some(dev.name.clone()),
                         mount:    None,
+                        rule:     "nvme.spare".into(),
+                        value:    Some(nvme.available_spare_pct as f64),
                         message:  format!(
                             "NVMe spare {}% below threshold {}%",
                             nvme.available_spare_pct, nvme.available_spare_threshold
@@ -169,6 +322,8 @@ pub fn evaluate(devices: &[BlockDevice], filesystems: &[Filesystem], alert_cfg:
                         severity: Severity::Critical,
                         device:   Some(dev.name.clone()),
                         mount:    None,
+                        rule:     "nvme.critical_warning".into(),
+                        value:    Some(nvme.critical_warning as f64),
                         message:  format!("NVMe critical warning byte: 0x{:02X}", nvme.critical_warning),
                     });
                 }
@@ -176,91 +331,474 @@ pub fn evaluate(devices: &[BlockDevice], filesystems: &[Filesystem], alert_cfg:
         }
 
         // ── I/O utilisation sustained ─────────────────────────────────
-        if dev.io_util_pct >= thr.io_util_warn_pct {
+        if let Some(hit) = level(
+            dev.io_util_pct, Some(thr.io_util_warn_pct), None, true,
+            alert_cfg.policy("io.util"),
+            held.get(&cond_key(&dev.name, "io.util")),
+        ) {
             alerts.push(Alert {
                 severity: Severity::Warning,
                 device:   Some(dev.name.clone()),
                 mount:    None,
-                message:  format!("I/O utilisation {:.0}% (sustained)", dev.io_util_pct),
+                rule:     "io.util".into(),
+                value:    Some(dev.io_util_pct),
+                message:  match hit.clearing {
+                    true  => format!("I/O utilisation {:.0}% ({})", dev.io_util_pct, hit.versus("%")),
+                    false => format!("I/O utilisation {:.0}% (sustained)", dev.io_util_pct),
+                },
             });
         }
 
         // ── I/O latency ───────────────────────────────────────────────
         let lat = dev.avg_read_latency_ms.max(dev.avg_write_latency_ms);
-        if thr.latency_crit_ms > 0.0 && lat >= thr.latency_crit_ms {
-            alerts.push(Alert {
-                severity: Severity::Critical,
-                device:   Some(dev.name.clone()),
-                mount:    None,
-                message:  format!("I/O latency {:.0}ms ≥ critical threshold {:.0}ms", lat, thr.latency_crit_ms),
-            });
-        } else if thr.latency_warn_ms > 0.0 && lat >= thr.latency_warn_ms {
+        if let Some(hit) = level(
+            lat,
+            Some(thr.latency_warn_ms).filter(|t| *t > 0.0),
+            Some(thr.latency_crit_ms).filter(|t| *t > 0.0),
+            true,
+            alert_cfg.policy("io.latency"),
+            held.get(&cond_key(&dev.name, "io.latency")),
+        ) {
             alerts.push(Alert {
-                severity: Severity::Warning,
+                severity: hit.severity.clone(),
                 device:   Some(dev.name.clone()),
                 mount:    None,
-                message:  format!("I/O latency {:.0}ms ≥ warning threshold {:.0}ms", lat, thr.latency_warn_ms),
+                rule:     "io.latency".into(),
+                value:    Some(lat),
+                message:  format!("I/O latency {:.0}ms {}", lat, hit.versus("ms")),
             });
         }
     }
 
     // ── Filesystem thresholds ─────────────────────────────────────────
     for fs in filesystems {
+        let eff = alert_cfg.for_mount(&fs.mount);
+        let thr = &eff.thresholds;
         let pct = fs.use_pct();
-        if pct >= thr.filesystem_crit_pct {
-            alerts.push(Alert {
+        match level(
+            pct, Some(thr.filesystem_warn_pct), Some(thr.filesystem_crit_pct), true,
+            alert_cfg.policy("fs.usage"),
+            held.get(&cond_key(&fs.mount, "fs.usage")),
+        ) {
+            Some(hit) if hit.severity == Severity::Critical => alerts.push(Alert {
                 severity: Severity::Critical,
                 device:   None,
                 mount:    Some(fs.mount.clone()),
-                message:  format!("{:.0}% full — critically low space", pct),
-            });
-        } else if pct >= thr.filesystem_warn_pct {
-            alerts.push(Alert {
+                rule:     "fs.usage".into(),
+                value:    Some(pct),
+                message:  format!("{:.0}% full — critically low space{}", pct, hit.clearing_note("%")),
+            }),
+            Some(hit) => alerts.push(Alert {
                 severity: Severity::Warning,
                 device:   None,
                 mount:    Some(fs.mount.clone()),
-                message:  format!("{:.0}% full", pct),
-            });
+                rule:     "fs.usage".into(),
+                value:    Some(pct),
+                message:  format!("{:.0}% full{}", pct, hit.clearing_note("%")),
+            }),
+            None => {}
         }
 
         let ipct = fs.inode_pct();
-        if ipct >= thr.inode_crit_pct {
-            alerts.push(Alert {
+        match level(
+            ipct, Some(thr.inode_warn_pct), Some(thr.inode_crit_pct), true,
+            alert_cfg.policy("fs.inodes"),
+            held.get(&cond_key(&fs.mount, "fs.inodes")),
+        ) {
+            Some(hit) if hit.severity == Severity::Critical => alerts.push(Alert {
                 severity: Severity::Critical,
                 device:   None,
                 mount:    Some(fs.mount.clone()),
-                message:  format!("Inodes {:.0}% used — critically low", ipct),
-            });
-        } else if ipct >= thr.inode_warn_pct {
-            alerts.push(Alert {
+                rule:     "fs.inodes".into(),
+                value:    Some(ipct),
+                message:  format!("Inodes {:.0}% used — critically low{}", ipct, hit.clearing_note("%")),
+            }),
+            Some(hit) => alerts.push(Alert {
                 severity: Severity::Warning,
                 device:   None,
                 mount:    Some(fs.mount.clone()),
-                message:  format!("Inodes {:.0}% used", ipct),
-            });
+                rule:     "fs.inodes".into(),
+                value:    Some(ipct),
+                message:  format!("Inodes {:.0}% used{}", ipct, hit.clearing_note("%")),
+            }),
+            None => {}
         }
 
-        // Fill-rate projection
+        // Fill-rate projection (lower is worse)
         if let Some(days) = fs.days_until_full {
-            if thr.fill_days_crit > 0.0 && days <= thr.fill_days_crit {
+            if let Some(hit) = level(
+                days,
+                Some(thr.fill_days_warn).filter(|t| *t > 0.0),
+                Some(thr.fill_days_crit).filter(|t| *t > 0.0),
+                false,
+                alert_cfg.policy("fs.fill_rate"),
+                held.get(&cond_key(&fs.mount, "fs.fill_rate")),
+            ) {
                 alerts.push(Alert {
-                    severity: Severity::Critical,
+                    severity: hit.severity.clone(),
                     device:   None,
                     mount:    Some(fs.mount.clone()),
-                    message:  format!("Projected full in {:.1} days at current fill rate", days),
+                    rule:     "fs.fill_rate".into(),
+                    value:    Some(days),
+                    message:  format!("Projected full in {:.1} days at current fill rate{}", days, hit.clearing_note(" days")),
                 });
-            } else if thr.fill_days_warn > 0.0 && days <= thr.fill_days_warn {
-                alerts.push(Alert {
-                    severity: Severity::Warning,
+            }
+        }
+    }
+
+    // ── User-defined expression rules ─────────────────────────────────
+    for rule in &alert_cfg.rules {
+        alerts.extend(evaluate_rule(rule, devices, filesystems, held));
+    }
+
+    alerts.retain(|a| !disabled_by_override(a, devices, alert_cfg));
+
+    // Sort: Critical first, then Warning, then Info
+    alerts.sort_by(|a, b| b.severity.cmp(&a.severity));
+    alerts
+}
+
+/// Evaluate one `[[alerts.rules]]` entry. Rules whose expression fails to parse
+/// produce no alerts (`dtop --config` reports the parse error). A firing rule with
+/// a `clear` expression stays active until that expression becomes true.
+fn evaluate_rule(
+    rule:        &AlertRule,
+    devices:     &[BlockDevice],
+    filesystems: &[Filesystem],
+    held:        &HashMap<String, Severity>,
+) -> Vec<Alert> {
+    let (Some(expr), clear) = rule.parsed.get_or_init(|| {
+        (expr::parse(&rule.expr).ok(), rule.clear.as_deref().and_then(|c| expr::parse(c).ok()))
+    }) else { return Vec::new() };
+    let rule_id = format!("rule.{}", rule.id);
+    let active = |target: &str, ctx: &Context| {
+        if expr.eval(ctx).truthy() { return true; }
+        match &clear {
+            Some(c) => held.contains_key(&cond_key(target, &rule_id)) && !c.eval(ctx).truthy(),
+            None    => false,
+        }
+    };
+    let severity = match rule.severity.as_str() {
+        "crit" | "critical" => Severity::Critical,
+        _                   => Severity::Warning,
+    };
+    let render = |ctx: &Context| match &rule.message {
+        Some(t) => expr::render_template(t, ctx),
+        None    => format!("Rule '{}' matched: {}", rule.id, rule.expr),
+    };
+    let mut out = Vec::new();
+
+    if expr.references("fs.") {
+        for fs in filesystems {
+            if !glob::any_match(&rule.mounts, &[&fs.mount]) { continue; }
+            let backing = backing_device(fs, devices);
+            if !rule.devices.is_empty() {
+                let Some(dev) = backing else { continue };
+                if !glob::any_match(&rule.devices, &device_names(dev)) { continue; }
+            }
+            let mut ctx = fs_context(fs);
+            if let Some(dev) = backing { ctx.extend(device_context(dev)); }
+            if active(&fs.mount, &ctx) {
+                out.push(Alert {
+                    severity: severity.clone(),
                     device:   None,
                     mount:    Some(fs.mount.clone()),
-                    message:  format!("Projected full in {:.1} days at current fill rate", days),
+                    rule:     rule_id.clone(),
+                    value:    None,
+                    message:  render(&ctx),
+                });
+            }
+        }
+    } else {
+        for dev in devices {
+            if !glob::any_match(&rule.devices, &device_names(dev)) { continue; }
+            if !rule.mounts.is_empty() && !filesystems.iter().any(|fs| {
+                backing_device(fs, devices).is_some_and(|b| b.name == dev.name) && glob::any_match(&rule.mounts, &[&fs.mount])
+            }) {
+                continue;
+            }
+            let ctx = device_context(dev);
+            if active(&dev.name, &ctx) {
+                out.push(Alert {
+                    severity: severity.clone(),
+                    device:   Some(dev.name.clone()),
+                    mount:    None,
+                    rule:     rule_id.clone(),
+                    value:    None,
+                    message:  render(&ctx),
                 });
             }
         }
     }
+    out
+}
 
-    // Sort: Critical first, then Warning, then Info
+/// Global thresholds with any `[alerts.overrides.device/serial]` blocks applied.
+pub fn device_thresholds(dev: &BlockDevice, alert_cfg: &AlertConfig) -> EffectiveThresholds {
+    let mut names = vec![dev.name.as_str()];
+    if let Some(a) = &dev.alias { names.push(a); }
+    alert_cfg.for_device(&names, dev.serial.as_deref())
+}
+
+/// Whether an override block for the alert's device or mount disables its rule.
+fn disabled_by_override(alert: &Alert, devices: &[BlockDevice], alert_cfg: &AlertConfig) -> bool {
+    if let Some(m) = &alert.mount {
+        return alert_cfg.for_mount(m).is_disabled(&alert.rule);
+    }
+    match alert.device.as_ref().and_then(|d| devices.iter().find(|dev| &dev.name == d)) {
+        Some(dev) => device_thresholds(dev, alert_cfg).is_disabled(&alert.rule),
+        None      => false,
+    }
+}
+
+/// Names a device selector may match: kernel name, alias, serial.
+fn device_names(dev: &BlockDevice) -> Vec<&str> {
+    let mut names = vec![dev.name.as_str()];
+    if let Some(a) = &dev.alias  { names.push(a); }
+    if let Some(s) = &dev.serial { names.push(s); }
+    names
+}
+
+/// Find the whole-disk device a filesystem lives on ("/dev/sda1" → sda).
+pub fn backing_device<'a>(fs: &Filesystem, devices: &'a [BlockDevice]) -> Option<&'a BlockDevice> {
+    let short = fs.short_device();
+    devices.iter().find(|d| d.name == short || d.partitions.iter().any(|p| p.name == short))
+}
+
+/// Metric variables exposed to rule expressions for one block device.
+pub fn device_context(dev: &BlockDevice) -> Context {
+    let mut ctx = Context::new();
+    let num = |v: f64| Value::Num(v);
+    let s   = |v: &str| Value::Str(v.to_string());
+
+    ctx.insert("device".into(),                 s(&dev.name));
+    ctx.insert("device.name".into(),            s(&dev.name));
+    ctx.insert("device.type".into(),            s(dev.dev_type.label().trim()));
+    ctx.insert("device.rotational".into(),      Value::Bool(dev.rotational));
+    ctx.insert("device.capacity_bytes".into(),  num(dev.capacity_bytes as f64));
+    ctx.insert("device.read_bytes_per_sec".into(),  num(dev.read_bytes_per_sec));
+    ctx.insert("device.write_bytes_per_sec".into(), num(dev.write_bytes_per_sec));
+    ctx.insert("device.read_iops".into(),       num(dev.read_iops));
+    ctx.insert("device.write_iops".into(),      num(dev.write_iops));
+    ctx.insert("device.util_pct".into(),        num(dev.io_util_pct));
+    ctx.insert("device.read_latency_ms".into(), num(dev.avg_read_latency_ms));
+    ctx.insert("device.write_latency_ms".into(), num(dev.avg_write_latency_ms));
+    if let Some(v) = &dev.alias     { ctx.insert("device.alias".into(), s(v)); }
+    if let Some(v) = &dev.serial    { ctx.insert("device.serial".into(), s(v)); }
+    if let Some(v) = &dev.model     { ctx.insert("device.model".into(), s(v)); }
+    if let Some(v) = &dev.transport { ctx.insert("device.transport".into(), s(v)); }
+
+    if let Some(smart) = &dev.smart {
+        ctx.insert("device.smart_status".into(), s(smart.status.label().trim()));
+        ctx.insert("device.health_score".into(), num(health_score(dev) as f64));
+        if let Some(t) = smart.temperature    { ctx.insert("device.temperature".into(), num(t as f64)); }
+        if let Some(h) = smart.power_on_hours { ctx.insert("device.power_on_hours".into(), num(h as f64)); }
+        for attr in &smart.attributes {
+            ctx.insert(format!("device.attr.{}", attr.id), num(attr.raw_value as f64));
+            ctx.insert(format!("device.attr.{}.value", attr.id), num(attr.value as f64));
+        }
+        if let Some(nvme) = &smart.nvme {
+            ctx.insert("device.nvme.percentage_used".into(),  num(nvme.percentage_used as f64));
+            ctx.insert("device.nvme.available_spare".into(),  num(nvme.available_spare_pct as f64));
+            ctx.insert("device.nvme.media_errors".into(),     num(nvme.media_errors as f64));
+            ctx.insert("device.nvme.critical_warning".into(), num(nvme.critical_warning as f64));
+            ctx.insert("device.nvme.unsafe_shutdowns".into(), num(nvme.unsafe_shutdowns as f64));
+        }
+    }
+    ctx
+}
+
+/// Metric variables exposed to rule expressions for one mounted filesystem.
+pub fn fs_context(fs: &Filesystem) -> Context {
+    let mut ctx = Context::new();
+    ctx.insert("mount".into(),            Value::Str(fs.mount.clone()));
+    ctx.insert("fs.mount".into(),         Value::Str(fs.mount.clone()));
+    ctx.insert("fs.device".into(),        Value::Str(fs.device.clone()));
+    ctx.insert("fs.type".into(),          Value::Str(fs.fs_type.clone()));
+    ctx.insert("fs.total_bytes".into(),   Value::Num(fs.total_bytes as f64));
+    ctx.insert("fs.used_bytes".into(),    Value::Num(fs.used_bytes as f64));
+    ctx.insert("fs.avail_bytes".into(),   Value::Num(fs.avail_bytes as f64));
+    ctx.insert("fs.use_pct".into(),       Value::Num(fs.use_pct()));
+    ctx.insert("fs.inode_pct".into(),     Value::Num(fs.inode_pct()));
+    if let Some(r) = fs.fill_rate_bps   { ctx.insert("fs.fill_rate_bps".into(), Value::Num(r)); }
+    if let Some(d) = fs.days_until_full { ctx.insert("fs.days_until_full".into(), Value::Num(d)); }
+    ctx
+}
+
+/// Evaluate `[[alerts.rate_rules]]` against timestamped SMART history.
+/// At most one alert per device and attribute: the most severe matching rule.
+pub fn evaluate_rates(
+    devices:   &[BlockDevice],
+    history:   &SmartHistory,
+    alert_cfg: &AlertConfig,
+    now:       i64,
+) -> Vec<Alert> {
+    let mut alerts: Vec<Alert> = Vec::new();
+
+    for dev in devices {
+        let Some(hist) = smart_history::get(history, dev) else { continue };
+        for rule in &alert_cfg.rate_rules {
+            let Some(window) = rule.window_secs() else { continue };
+            let Some(grew) = smart_history::delta(hist, rule.attr, window, now) else { continue };
+            if grew == 0 || grew < rule.delta { continue; }
+
+            let severity = match rule.severity.as_str() {
+                "crit" | "critical" => Severity::Critical,
+                _                   => Severity::Warning,
+            };
+            let rule_id = format!("smart.rate.{}", rule.attr);
+            if let Some(i) = alerts.iter().position(|a| a.device.as_deref() == Some(&dev.name) && a.rule == rule_id) {
+                if alerts[i].severity >= severity { continue; }
+                alerts.remove(i);
+            }
+
+            let name = if rule.attr == smart_history::NVME_MEDIA_ERRORS {
+                "NVMe media errors".to_string()
+            } else {
+                dev.smart.as_ref()
+                    .and_then(|s| s.attributes.iter().find(|a| a.id == rule.attr))
+                    .map(|a| a.name.clone())
+                    .unwrap_or_else(|| format!("attr {}", rule.attr))
+            };
+            let current = hist.series.get(&rule.attr).and_then(|s| s.last()).map(|&(_, v)| v).unwrap_or(0);
+            let message = match &rule.message {
+                Some(m) => m.clone(),
+                None    => format!(
+                    "{} grew by {} in {} (now {})",
+                    name, grew, rule.window, current
+                ),
+            };
+            alerts.push(Alert {
+                severity,
+                device:  Some(dev.name.clone()),
+                mount:   None,
+                rule:    rule_id,
+                value:   Some(grew as f64),
+                message,
+            });
+        }
+    }
+
+    alerts.retain(|a| !disabled_by_override(a, devices, alert_cfg));
+
+    alerts.sort_by(|a, b| b.severity.cmp(&a.severity));
+    alerts
+}
+
+/// Raise one alert per device and kind of storage error seen in the kernel log
+/// within `alerts.kernel_window`. Alerts clear once the window has passed.
+pub fn evaluate_kernel(
+    devices:   &[BlockDevice],
+    log:       &KernelLog,
+    alert_cfg: &AlertConfig,
+    now:       i64,
+) -> Vec<Alert> {
+    let mut alerts: Vec<Alert> = Vec::new();
+    let window = human::parse_duration(&alert_cfg.kernel_window).unwrap_or(3600) as i64;
+
+    for dev in devices {
+        let Some(events) = log.for_device(&dev.name) else { continue };
+        for kind in KernelEventKind::ALL {
+            let recent: Vec<&KernelEvent> = events.iter()
+                .filter(|e| e.kind == kind && now - e.ts <= window)
+                .collect();
+            let Some(last) = recent.last() else { continue };
+            let severity = match kind {
+                KernelEventKind::LinkReset | KernelEventKind::Timeout => Severity::Warning,
+                _ => Severity::Critical,
+            };
+            alerts.push(Alert {
+                severity,
+                device:  Some(dev.name.clone()),
+                mount:   None,
+                rule:    format!("kernel.{}", kind.id()),
+                value:   Some(recent.len() as f64),
+                message: format!(
+                    "{} kernel {}{} in {}: {}",
+                    recent.len(), kind.label(), if recent.len() == 1 { "" } else { "s" },
+                    alert_cfg.kernel_window, last.message,
+                ),
+            });
+        }
+    }
+
+    alerts.retain(|a| !disabled_by_override(a, devices, alert_cfg));
+    alerts.sort_by(|a, b| b.severity.cmp(&a.severity));
+    alerts
+}
+
+/// Evaluate I/O pressure stall information and NFS mounts (RTT over the last
+/// interval, see `nfs::track_interval_rtt`; stale mounts).
+/// `stale` holds the mount points that failed `nfs::stale_mounts`.
+pub fn evaluate_system(
+    pressure:  Option<&SystemPressure>,
+    nfs:       &[NfsMountStats],
+    stale:     &HashSet<String>,
+    alert_cfg: &AlertConfig,
+    held:      &HashMap<String, Severity>,
+) -> Vec<Alert> {
+    let mut alerts: Vec<Alert> = Vec::new();
+    let on = |t: f64| Some(t).filter(|t| *t > 0.0);
+
+    // ── I/O pressure ──────────────────────────────────────────────────
+    if let Some(p) = pressure {
+        let psi = &alert_cfg.psi;
+        let signals = [
+            ("some", "avg10", p.io.some.avg10, psi.some_avg10_warn, psi.some_avg10_crit),
+            ("some", "avg60", p.io.some.avg60, psi.some_avg60_warn, psi.some_avg60_crit),
+            ("full", "avg10", p.io.full.avg10, psi.full_avg10_warn, psi.full_avg10_crit),
+            ("full", "avg60", p.io.full.avg60, psi.full_avg60_warn, psi.full_avg60_crit),
+        ];
+        for (kind, avg, value, warn, crit) in signals {
+            let rule = format!("psi.io.{}.{}", kind, avg);
+            let value = value as f64;
+            let Some(hit) = level(value, on(warn), on(crit), true, alert_cfg.policy(&rule), held.get(&rule)) else {
+                continue;
+            };
+            alerts.push(Alert {
+                severity: hit.severity.clone(),
+                device:  None,
+                mount:   None,
+                rule,
+                value:   Some(value),
+                message: format!("I/O pressure ({} {}) {:.1}% {}", kind, avg, value, hit.versus("%")),
+            });
+        }
+    }
+
+    // ── NFS mounts ────────────────────────────────────────────────────
+    for m in nfs {
+        if stale.contains(&m.mount) {
+            alerts.push(Alert {
+                severity: Severity::Critical,
+                device:   None,
+                mount:    Some(m.mount.clone()),
+                rule:     "nfs.stale".into(),
+                value:    None,
+                message:  format!(
+                    "NFS mount not responding ({}; no answer within {})",
+                    m.device, alert_cfg.nfs_stale_timeout
+                ),
+            });
+            continue;
+        }
+        let thr = alert_cfg.for_mount(&m.mount).thresholds;
+        let Some(rtt) = m.interval_rtt_ms else { continue };
+        let Some(hit) = level(
+            rtt, on(thr.nfs_rtt_warn_ms), on(thr.nfs_rtt_crit_ms), true,
+            alert_cfg.policy("nfs.rtt"), held.get(&cond_key(&m.mount, "nfs.rtt")),
+        ) else { continue };
+        alerts.push(Alert {
+            severity: hit.severity.clone(),
+            device:  None,
+            mount:   Some(m.mount.clone()),
+            rule:    "nfs.rtt".into(),
+            value:   Some(rtt),
+            message: format!("NFS average RTT {:.1}ms {} ({})", rtt, hit.versus("ms"), m.device),
+        });
+    }
+
+    alerts.retain(|a| !disabled_by_override(a, &[], alert_cfg));
     alerts.sort_by(|a, b| b.severity.cmp(&a.severity));
     alerts
 }
@@ -276,6 +814,8 @@ pub fn evaluate_volumes(raids: &[RaidArray], pools: &[ZfsPool]) -> Vec<Alert> {
                 severity: Severity::Critical,
                 device:   Some(arr.name.clone()),
                 mount:    None,
+                rule:     "raid.state".into(),
+                value:    None,
                 message:  format!("RAID {} state is {}", arr.level, arr.state),
             });
         } else if arr.degraded {
@@ -288,6 +828,8 @@ pub fn evaluate_volumes(raids: &[RaidArray], pools: &[ZfsPool]) -> Vec<Alert> {
                 severity,
                 device:  Some(arr.name.clone()),
                 mount:   None,
+                rule:    "raid.degraded".into(),
+                value:   arr.rebuild_pct,
                 message: format!("{} array degraded{}", arr.level, rebuild),
             });
         }
@@ -303,6 +845,8 @@ pub fn evaluate_volumes(raids: &[RaidArray], pools: &[ZfsPool]) -> Vec<Alert> {
                 severity,
                 device:  None,
                 mount:   Some(format!("zpool:{}", pool.name)),
+                rule:    "zfs.health".into(),
+                value:   None,
                 message: format!("ZFS pool {} health: {}", pool.name, pool.health),
             });
         }
@@ -1,14 +1,21 @@
 use crate::alerts::{self, Alert};
 use crate::collectors::{diskstats, filesystem, lsblk, lvm, mdraid, nfs, pressure, process_io, smart as smart_collector, smart_cache, zfs};
+use crate::collectors::kmsg::{self, KernelEvent, KernelLog};
 use crate::collectors::pressure::SystemPressure;
-use crate::util::{ack_store, alert_log, health_history, notify, smart_anomaly, smart_baseline, user_state, webhook, write_endurance};
+use crate::util::alert_log::{AlertEvent, EventKind};
+use crate::util::alert_state::{AlertTracker, Transition};
+use crate::util::correlate::{self, Correlation, Topology};
+use crate::util::digest::Batcher;
+use crate::util::silence::{self, Matcher, Silence};
+use crate::util::notify_state::{self, Dispatch, NotifyState};
+use crate::util::{ack_store, alert_log, delivery, health_history, notifier, smart_anomaly, smart_baseline, smart_history, user_state, write_endurance};
 use crate::config::Config;
 use crate::ui::benchmark_popup;
 use crate::input::{handle_key, Action};
 use crate::models::device::BlockDevice;
 use crate::models::filesystem::Filesystem;
 use crate::models::process::{ProcessIORates, ProcessSort, RawProcessIO};
-use crate::models::smart::SmartData;
+use crate::models::smart::{SelfTestResult, SmartData};
 use crate::models::volume::{LvmState, RaidArray, ZfsPool};
 use crate::ui::theme::{Theme, ThemeVariant};
 use crate::ui::{dashboard, filesystem_view, help, nfs_view, process_view, volume_view};
@@ -227,6 +234,10 @@ pub struct App {
     smart_rx:      mpsc::Receiver<SmartResult>,
     smart_pending: HashSet<String>,
 
+    // Storage errors from /dev/kmsg (background reader)
+    kernel_rx:      mpsc::Receiver<KernelEvent>,
+    pub kernel_log: KernelLog,
+
     // Benchmark
     pub bench_state:  BenchmarkState,
     bench_tx:         mpsc::Sender<(String, Result<f64, String>)>,
@@ -237,12 +248,30 @@ pub struct App {
 
     // SMART anomaly log — first-seen bad attribute timestamps (persisted)
     pub smart_anomalies: smart_anomaly::AnomalyLog,
+    // Timestamped SMART counter history for rate-of-change rules
+    smart_history: smart_history::SmartHistory,
+
+    // Sustain / hysteresis state per alert condition
+    alert_tracker: AlertTracker,
+
+    // Cooldowns, re-notify timers and escalation state (persisted)
+    notify_state: NotifyState,
 
-    // Alert cooldown — maps alert key → Unix timestamp of last fire (in-memory)
-    alert_fired_at: HashMap<String, i64>,
+    // Per-channel batch windows and rate limits
+    notify_batcher: Batcher,
 
     // Alert acknowledgment — keys of alerts the operator has seen this session
     pub acked_alerts: HashSet<String>,
+    /// Alert keys already checked for old-format acks (see `ack_store::migrate`).
+    ack_migrated:     HashSet<String>,
+
+    // Silences (reloaded each slow tick so `dtop --silence` takes effect) and
+    // the keys of current alerts they mute
+    silences: Vec<Silence>,
+    pub silenced_alerts: HashSet<String>,
+
+    // Root-cause grouping and inhibition of the current alerts
+    pub correlation: Correlation,
 
     // Per-alert selection in the alerts panel
     pub alerts_panel_state: ListState,
@@ -257,7 +286,7 @@ pub struct App {
     pub write_endurance: write_endurance::EnduranceMap,
 
     // Alert log viewer state (F6)
-    pub alert_log_entries:   Vec<(String, crate::alerts::Alert)>,
+    pub alert_log_entries:   Vec<AlertEvent>,
     pub alert_log_scroll:    usize,
     pub alert_log_filter:    AlertLogFilter,
     pub alert_log_search:    String,
@@ -278,6 +307,8 @@ pub struct App {
 
     // NFS RTT history: mount → (read_rtt × 10 as u64, write_rtt × 10 as u64)
     pub nfs_rtt_history: HashMap<String, (RingBuffer, RingBuffer)>,
+    /// NFS op and RTT counters from the previous tick, for the per-interval RTT.
+    nfs_rtt_prev: nfs::RttCounters,
 
     // Per-device I/O rate history: device → (read KB/s, write KB/s)
     pub device_io_history: HashMap<String, (RingBuffer, RingBuffer)>,
@@ -286,7 +317,7 @@ pub struct App {
     pub system_pressure: Option<SystemPressure>,
 
     // Filesystem usage history for fill-rate computation: mount → [(Instant, used_bytes)]
-    fs_usage_history: HashMap<String, VecDeque<(Instant, u64)>>,
+    fs_usage_history: filesystem::UsageHistory,
 
     pub should_quit: bool,
 
@@ -353,14 +384,23 @@ impl App {
             last_smart_tick: Instant::now() - SMART_TICK,
             smart_tx,
             smart_rx,
+            kernel_rx:     kmsg::spawn(),
+            kernel_log:    KernelLog::default(),
             smart_pending: HashSet::new(),
             bench_state:  BenchmarkState::Idle,
             bench_tx,
             bench_rx,
             smart_test_status: HashMap::new(),
             smart_anomalies:   smart_anomaly::load(),
-            alert_fired_at:    HashMap::new(),
+            smart_history:     smart_history::load(),
+            alert_tracker:     AlertTracker::new(),
+            notify_state:      notify_state::load(),
+            notify_batcher:    Batcher::new(),
             acked_alerts:      ack_store::load(),
+            ack_migrated:      HashSet::new(),
+            silences:          silence::load(),
+            silenced_alerts:   HashSet::new(),
+            correlation:       Correlation::default(),
             alerts_panel_state: ListState::default(),
             smart_baselines:   HashMap::new(),
             health_history:    health_history::load(),
@@ -377,6 +417,7 @@ impl App {
             detail_show_desc:    false,
             fs_usage_history:    HashMap::new(),
             nfs_rtt_history:     HashMap::new(),
+            nfs_rtt_prev:        HashMap::new(),
             device_io_history:   HashMap::new(),
             system_pressure:     None,
             should_quit:   false,
@@ -411,10 +452,14 @@ impl App {
             }
         }
 
-        // Pre-populate alert history from persistent log (last 50 entries)
-        let recent = alert_log::load_recent(50);
-        for entry in recent.into_iter().rev() {
-            app.alert_history.push_back(entry);
+        if let Some(secs) = app.config.alerts.log_retention_secs() { alert_log::prune(secs); }
+        // Pre-populate alert history from persistent log (last 50 firings)
+        let fired: Vec<AlertEvent> = alert_log::load_events().into_iter()
+            .filter(|e| e.event.in_history())
+            .collect();
+        for ev in fired.iter().rev().take(50) {
+            let time = ev.time().map(|t| t.format("%H:%M:%S").to_string()).unwrap_or_default();
+            app.alert_history.push_back((time, ev.alert()));
         }
 
         Ok(app)
@@ -426,6 +471,8 @@ impl App {
         &mut self,
         terminal: &mut ratatui::Terminal<B>,
     ) -> Result<()> {
+        // Retry webhook deliveries left over from a previous run
+        delivery::start();
         loop {
             self.consume_smart_results();
             self.consume_bench_results();
@@ -496,21 +543,51 @@ impl App {
                 let prev_alerts = self.alerts.clone();
                 self.collect_fast()?;
                 self.last_fast_tick = Instant::now();
-                let mut new_alerts = alerts::evaluate(
+                let mut raw = alerts::evaluate_with_state(
                     &self.devices, &self.filesystems,
                     &self.config.alerts,
+                    &self.alert_tracker.held(),
+                );
+                raw.extend(alerts::evaluate_volumes(&self.raid_arrays, &self.zfs_pools));
+                raw.extend(alerts::evaluate_rates(
+                    &self.devices, &self.smart_history, &self.config.alerts,
+                    chrono::Local::now().timestamp(),
+                ));
+                let nfs_names: Vec<String> = self.nfs_mounts.iter().map(|m| m.mount.clone()).collect();
+                let stale = match self.config.alerts.nfs_stale_timeout() {
+                    Some(t) => nfs::stale_mounts(&nfs_names, t),
+                    None    => HashSet::new(),
+                };
+                raw.extend(alerts::evaluate_system(
+                    self.system_pressure.as_ref(), &self.nfs_mounts, &stale,
+                    &self.config.alerts, &self.alert_tracker.held(),
+                ));
+                self.kernel_log.drain(&self.kernel_rx);
+                raw.extend(alerts::evaluate_kernel(
+                    &self.devices, &self.kernel_log, &self.config.alerts,
+                    chrono::Local::now().timestamp(),
+                ));
+                let (new_alerts, changes) = self.alert_tracker.update(
+                    raw, &self.config.alerts, chrono::Local::now().timestamp(),
                 );
-                new_alerts.extend(alerts::evaluate_volumes(&self.raid_arrays, &self.zfs_pools));
-                new_alerts.sort_by(|a, b| b.severity.cmp(&a.severity));
-                self.update_alert_history(&prev_alerts, &new_alerts);
-                // Desktop notifications for newly-firing alerts
-                if self.config.notifications.notify_send {
-                    let fresh: Vec<&alerts::Alert> = new_alerts.iter()
-                        .filter(|a| !prev_alerts.iter().any(|p| p.key() == a.key()))
-                        .collect();
-                    notify::notify_send(&fresh);
-                }
                 self.alerts = new_alerts;
+                let topo = Topology::build(&self.devices, &self.filesystems, &self.raid_arrays);
+                self.correlation = correlate::correlate(&self.alerts, &topo, &self.config.alerts);
+                self.correlation.order(&mut self.alerts);
+                self.refresh_silenced();
+                let prev_keys: HashSet<String> = prev_alerts.iter().map(|a| a.key()).collect();
+                let mut muted = self.silenced_alerts.clone();
+                muted.extend(self.correlation.inhibited_keys(&self.alerts));
+                if ack_store::migrate(&mut self.acked_alerts, &self.alerts, &mut self.ack_migrated) {
+                    ack_store::save(&self.acked_alerts);
+                }
+                let resolved = self.notify_state.resolved(&changes);
+                let dispatch = self.notify_state.process(
+                    &mut self.alerts, &prev_keys, &self.acked_alerts, &muted,
+                    &self.config, chrono::Local::now().timestamp(),
+                ).with_resolved(&resolved);
+                self.refresh_silenced();
+                self.update_alert_history(&prev_alerts, &changes, &dispatch);
             }
 
             if self.last_slow_tick.elapsed() >= SLOW_TICK {
@@ -530,47 +607,64 @@ impl App {
 
     // ── Alert history ──────────────────────────────────────────────────
 
-    fn update_alert_history(&mut self, prev: &[Alert], new: &[Alert]) {
-        let now_ts  = chrono::Local::now().timestamp();
+    fn update_alert_history(&mut self, prev: &[Alert], changes: &[Transition], dispatch: &Dispatch) {
         let now_str = chrono::Local::now().format("%H:%M:%S").to_string();
-        let cooldown_secs = self.config.alerts.cooldown_hours as i64 * 3600;
-        let mut fresh: Vec<Alert> = Vec::new();
 
         // Clear acks for conditions that have resolved — so re-fires are un-acked
-        let current_keys: HashSet<String> = new.iter().map(|a| a.key()).collect();
+        let current_keys: HashSet<String> = self.alerts.iter().map(|a| a.key()).collect();
         self.acked_alerts.retain(|k| current_keys.contains(k));
 
-        for alert in new {
-            let key = alert.key();
-            let was_present = prev.iter().any(|a| a.key() == key);
-            if !was_present {
-                // Cooldown check: suppress re-firing if within cooldown window
-                if cooldown_secs > 0 {
-                    if let Some(&last_fired) = self.alert_fired_at.get(&key) {
-                        if now_ts - last_fired < cooldown_secs {
-                            continue;
-                        }
-                    }
-                }
-                self.alert_fired_at.insert(key, now_ts);
-                if self.alert_history.len() >= 50 {
-                    self.alert_history.pop_back();
-                }
-                self.alert_history.push_front((now_str.clone(), alert.clone()));
-                fresh.push(alert.clone());
+        let corr = &self.correlation;
+        let mut events: Vec<AlertEvent> = changes.iter()
+            .map(|c| c.event(&self.devices).with_correlation(corr, &c.alert))
+            .collect();
+        events.extend(dispatch.escalated.iter().map(|a| {
+            AlertEvent::new(EventKind::Escalated, a, &self.devices).with_correlation(corr, a)
+        }));
+        alert_log::append(&events);
+
+        // The same entries the log gets, so the history matches `--alerts`
+        for ev in events.iter().filter(|e| e.event.in_history()) {
+            if self.alert_history.len() >= 50 {
+                self.alert_history.pop_back();
             }
+            self.alert_history.push_front((now_str.clone(), ev.alert()));
         }
 
-        if !fresh.is_empty() {
-            alert_log::append(&fresh);
-            if !self.config.notifications.webhook_url.is_empty() {
-                webhook::notify(
-                    &fresh,
-                    &self.config.notifications.webhook_url.clone(),
-                    self.config.notifications.notify_warning,
-                );
-            }
+        let sent = notifier::dispatch(dispatch, &self.config.notifications, &self.correlation, &self.devices, &self.filesystems, &mut self.notify_batcher);
+        let went_out = !sent.keys.is_empty();
+        self.notify_state.record_sent(sent, chrono::Local::now().timestamp());
+        let prev_keys: HashSet<String> = prev.iter().map(|a| a.key()).collect();
+        if !dispatch.is_empty() || went_out || prev_keys != current_keys {
+            notify_state::save(&mut self.notify_state);
+        }
+    }
+
+    /// Recompute which current alerts are muted by a silence or maintenance window.
+    fn refresh_silenced(&mut self) {
+        let now = chrono::Local::now();
+        self.silenced_alerts = self.alerts.iter()
+            .filter(|a| silence::silenced_by(a, &self.silences, &self.config.alerts.maintenance, now).is_some())
+            .map(|a| a.key())
+            .collect();
+    }
+
+    /// Silence the selected alert for an hour, or lift the TUI silence if it already has one.
+    fn toggle_silence_selected(&mut self) {
+        let Some(alert) = self.alerts_panel_state.selected().and_then(|i| self.alerts.get(i)).cloned() else { return };
+        let matcher = Matcher::for_alert(&alert);
+        let now = chrono::Local::now().timestamp();
+        let existing: Vec<u64> = self.silences.iter()
+            .filter(|s| s.is_active(now) && s.matcher == matcher)
+            .map(|s| s.id)
+            .collect();
+        if existing.is_empty() {
+            silence::add(matcher, now, now + 3600, &silence::current_user(), "silenced from TUI");
+        } else {
+            for id in existing { silence::remove(id); }
         }
+        self.silences = silence::load();
+        self.refresh_silenced();
     }
 
     // ── Input dispatch ────────────────────────────────────────────────
@@ -713,7 +807,9 @@ impl App {
                 if self.active_panel == ActivePanel::Alerts {
                     if let Some(idx) = self.alerts_panel_state.selected() {
                         if let Some(alert) = self.alerts.get(idx) {
-                            self.acked_alerts.insert(alert.key());
+                            if self.acked_alerts.insert(alert.key()) {
+                                alert_log::append(&[AlertEvent::new(EventKind::Acked, alert, &self.devices)]);
+                            }
                             ack_store::save(&self.acked_alerts);
                         }
                     }
@@ -812,11 +908,21 @@ impl App {
                 }
             }
 
+            Action::SilenceAlert => {
+                if self.active_view == ActiveView::Dashboard && self.active_panel == ActivePanel::Alerts {
+                    self.toggle_silence_selected();
+                }
+            }
+
             Action::AckAlerts => {
                 // Acknowledge all current alerts — dims them in the panel and clears the badge
+                let mut events = Vec::new();
                 for a in &self.alerts {
-                    self.acked_alerts.insert(a.key());
+                    if self.acked_alerts.insert(a.key()) {
+                        events.push(AlertEvent::new(EventKind::Acked, a, &self.devices));
+                    }
                 }
+                alert_log::append(&events);
                 ack_store::save(&self.acked_alerts);
             }
 
@@ -1151,13 +1257,7 @@ impl App {
                 now_stats.get(&dev.name),
             ) {
                 let io = diskstats::compute_io(prev, curr, elapsed, curr.ios_in_progress);
-                dev.read_bytes_per_sec   = io.read_bytes_per_sec;
-                dev.write_bytes_per_sec  = io.write_bytes_per_sec;
-                dev.read_iops            = io.read_iops;
-                dev.write_iops           = io.write_iops;
-                dev.io_util_pct          = io.io_util_pct;
-                dev.avg_read_latency_ms  = io.avg_read_latency_ms;
-                dev.avg_write_latency_ms = io.avg_write_latency_ms;
+                dev.set_io(&io);
                 dev.read_history .push((io.read_bytes_per_sec  / 1024.0) as u64);
                 dev.write_history.push((io.write_bytes_per_sec / 1024.0) as u64);
                 dev.util_history .push(io.io_util_pct as u64);
@@ -1174,29 +1274,7 @@ impl App {
         }
 
         if let Ok(mut fs) = filesystem::read_filesystems() {
-            let now = Instant::now();
-            // Keep up to 150 samples (~5 min at 2 s default) per mount
-            const HISTORY_CAP: usize = 150;
-            const MIN_SAMPLES: usize = 3;  // need at least a few to get a stable rate
-            for f in &mut fs {
-                let hist = self.fs_usage_history
-                    .entry(f.mount.clone())
-                    .or_default();
-                hist.push_back((now, f.used_bytes));
-                if hist.len() > HISTORY_CAP { hist.pop_front(); }
-
-                if hist.len() >= MIN_SAMPLES {
-                    let (t0, u0) = hist.front().copied().unwrap();
-                    let (t1, u1) = hist.back().copied().unwrap();
-                    let secs = t1.duration_since(t0).as_secs_f64().max(0.001);
-                    let delta = u1 as f64 - u0 as f64;
-                    let rate  = delta / secs;           // bytes/sec, may be negative
-                    f.fill_rate_bps = Some(rate);
-                    if rate > 0.0 && f.avail_bytes > 0 {
-                        f.days_until_full = Some(f.avail_bytes as f64 / rate / 86_400.0);
-                    }
-                }
-            }
+            filesystem::track_fill_rates(&mut fs, &mut self.fs_usage_history);
             self.filesystems = fs;
         }
 
@@ -1215,6 +1293,7 @@ impl App {
 
         // NFS mounts (cheap read of /proc/self/mountstats)
         self.nfs_mounts = nfs::read_nfs_mounts();
+        nfs::track_interval_rtt(&mut self.nfs_mounts, &mut self.nfs_rtt_prev);
 
         // Append RTT samples to per-mount history ringbuffers
         let nfs_rtt_updates: Vec<(String, u64, u64)> = self.nfs_mounts.iter()
@@ -1266,6 +1345,8 @@ impl App {
     // ── Slow data collection (30 s) ───────────────────────────────────
 
     fn collect_slow(&mut self) -> Result<()> {
+        self.silences = silence::load();
+
         // Config hot-reload: detect mtime changes and reload dtop.toml
         if let Some(path) = Config::config_path() {
             if let Ok(meta) = std::fs::metadata(&path) {
@@ -1355,26 +1436,24 @@ impl App {
             .map(|(k, _)| k.clone())
             .collect();
         for name in &test_names {
-            if let Ok(out) = std::process::Command::new("smartctl")
-                .args(["-a", &format!("/dev/{}", name)])
-                .output()
-            {
-                let text = String::from_utf8_lossy(&out.stdout);
-                if let Some(remaining) = parse_smart_test_remaining(&text) {
-                    let done = 100u8.saturating_sub(remaining);
-                    self.smart_test_status.insert(name.clone(), format!("Running: {}% done", done));
-                } else if !text.contains("% of test remaining") {
-                    // No longer in progress — determine final result
-                    let result = if text.contains("without error") {
-                        "✓ Completed OK".to_string()
-                    } else if text.contains("FAILED!") || (text.contains("# 1") && text.contains("Failed")) {
-                        "✗ FAILED".to_string()
-                    } else if text.contains("borted") {
-                        "⚠ Aborted".to_string()
-                    } else {
-                        continue; // ambiguous — keep polling
-                    };
-                    self.smart_test_status.insert(name.clone(), result);
+            let Some(smart) = smart_collector::poll_device(name) else { continue };
+            if let Some(remaining) = smart.self_test_remaining {
+                let done = 100u8.saturating_sub(remaining);
+                self.smart_test_status.insert(name.clone(), format!("Running: {}% done", done));
+            } else if let Some(latest) = smart.self_tests.first() {
+                // No longer in progress — the newest log entry is the result
+                let result = match latest.result {
+                    SelfTestResult::Passed  => "✓ Completed OK".to_string(),
+                    SelfTestResult::Failed  => match latest.failing_lba {
+                        Some(lba) => format!("✗ FAILED at LBA {}", lba),
+                        None      => "✗ FAILED".to_string(),
+                    },
+                    SelfTestResult::Aborted => "⚠ Aborted".to_string(),
+                    SelfTestResult::InProgress => continue,
+                };
+                self.smart_test_status.insert(name.clone(), result);
+                if let Some(dev) = self.devices.iter_mut().find(|d| &d.name == name) {
+                    dev.smart = Some(smart);
                 }
             }
         }
@@ -1405,6 +1484,8 @@ impl App {
         let mut cache_dirty   = false;
         let mut anomaly_dirty = false;
         let mut history_dirty = false;
+        let mut counters_dirty = false;
+        let rate_attrs: Vec<u32> = self.config.alerts.rate_rules.iter().map(|r| r.attr).collect();
         while let Ok(result) = self.smart_rx.try_recv() {
             self.smart_pending.remove(&result.device_name);
             if let Some(dev) = self.devices.iter_mut().find(|d| d.name == result.device_name) {
@@ -1419,6 +1500,9 @@ impl App {
                     if smart_anomaly::update(&mut self.smart_anomalies, &dev.name, smart) {
                         anomaly_dirty = true;
                     }
+                    if smart_history::record(&mut self.smart_history, dev, smart, &rate_attrs) {
+                        counters_dirty = true;
+                    }
                 }
                 cache_dirty = true;
             }
@@ -1441,6 +1525,9 @@ impl App {
         if history_dirty {
             health_history::save(&self.health_history);
         }
+        if counters_dirty {
+            smart_history::save(&self.smart_history);
+        }
     }
 
     // ── Benchmark ────────────────────────────────────────────────────
@@ -1555,21 +1642,6 @@ fn parse_dd_rate(s: &str) -> Option<f64> {
     None
 }
 
-/// Parse "N% of test remaining" from `smartctl -a` output.
-/// Returns the remaining percentage (0–100), or None if not present.
-fn parse_smart_test_remaining(text: &str) -> Option<u8> {
-    for line in text.lines() {
-        if line.contains("% of test remaining") {
-            for word in line.split_whitespace() {
-                if word.ends_with('%') {
-                    return word.trim_end_matches('%').parse::<u8>().ok();
-                }
-            }
-        }
-    }
-    None
-}
-
 /// Simple glob match: `*` matches any number of chars, no other wildcards.
 fn glob_match(pattern: &str, name: &str) -> bool {
     if let Some(prefix) = pattern.strip_suffix('*') {
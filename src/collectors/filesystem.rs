@@ -1,5 +1,10 @@
 use crate::models::filesystem::Filesystem;
 use anyhow::Result;
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+/// Recent (time, used bytes) samples per mount, for fill-rate estimates.
+pub type UsageHistory = HashMap<String, VecDeque<(Instant, u64)>>;
 
 /// Filesystems to skip — not useful for sysadmins.
 const SKIP_FS: &[&str] = &[
@@ -13,6 +18,35 @@ const SKIP_MOUNT_PREFIX: &[&str] = &[
     "/proc", "/sys", "/dev", "/run/user", "/snap",
 ];
 
+/// How long one read waits for an NFS mount's statvfs() before skipping it.
+const NFS_STATVFS_WAIT: Duration = Duration::from_millis(500);
+
+/// Record each filesystem's usage in `history` and set its fill rate (and
+/// days until full, while it is filling) once a few samples exist.
+pub fn track_fill_rates(fs: &mut [Filesystem], history: &mut UsageHistory) {
+    let now = Instant::now();
+    // Keep up to 150 samples (~5 min at 2 s default) per mount
+    const HISTORY_CAP: usize = 150;
+    const MIN_SAMPLES: usize = 3;  // need at least a few to get a stable rate
+    for f in fs {
+        let hist = history.entry(f.mount.clone()).or_default();
+        hist.push_back((now, f.used_bytes));
+        if hist.len() > HISTORY_CAP { hist.pop_front(); }
+
+        if hist.len() >= MIN_SAMPLES {
+            let (t0, u0) = hist.front().copied().unwrap();
+            let (t1, u1) = hist.back().copied().unwrap();
+            let secs = t1.duration_since(t0).as_secs_f64().max(0.001);
+            let delta = u1 as f64 - u0 as f64;
+            let rate  = delta / secs;           // bytes/sec, may be negative
+            f.fill_rate_bps = Some(rate);
+            if rate > 0.0 && f.avail_bytes > 0 {
+                f.days_until_full = Some(f.avail_bytes as f64 / rate / 86_400.0);
+            }
+        }
+    }
+}
+
 pub fn read_filesystems() -> Result<Vec<Filesystem>> {
     let mounts = parse_mounts()?;
     let mut out = Vec::new();
@@ -22,9 +56,15 @@ pub fn read_filesystems() -> Result<Vec<Filesystem>> {
         if SKIP_MOUNT_PREFIX.iter().any(|p| mount.starts_with(p)) { continue; }
         // Skip loop-mounted snaps
         if device.starts_with("/dev/loop") { continue; }
-
-        if let Ok(fs) = statvfs_for(device, mount, fs_type) {
-            out.push(fs);
+        // A hung NFS server would block statvfs() indefinitely: ask the mount's
+        // probe thread instead, and skip the mount while its probe is running
+        let stat = if fs_type.starts_with("nfs") {
+            super::nfs::statvfs(mount, NFS_STATVFS_WAIT)
+        } else {
+            nix::sys::statvfs::statvfs(mount.as_str()).ok()
+        };
+        if let Some(stat) = stat {
+            out.push(filesystem(device, mount, fs_type, &stat));
         }
     }
 
@@ -44,17 +84,14 @@ fn parse_mounts() -> Result<Vec<(String, String, String)>> {
     Ok(v)
 }
 
-fn statvfs_for(device: &str, mount: &str, fs_type: &str) -> Result<Filesystem> {
-    use nix::sys::statvfs::statvfs;
-    let stat = statvfs(mount)?;
-
-    let frsize = stat.fragment_size() as u64;
+fn filesystem(device: &str, mount: &str, fs_type: &str, stat: &nix::sys::statvfs::Statvfs) -> Filesystem {
+    let frsize = stat.fragment_size();
     let total_bytes  = stat.blocks()            * frsize;
     let avail_bytes  = stat.blocks_available()  * frsize;
     let free_bytes   = stat.blocks_free()        * frsize;
     let used_bytes   = total_bytes.saturating_sub(free_bytes);
 
-    Ok(Filesystem {
+    Filesystem {
         device:       device.to_string(),
         mount:        mount.to_string(),
         fs_type:      fs_type.to_string(),
@@ -65,5 +102,5 @@ fn statvfs_for(device: &str, mount: &str, fs_type: &str) -> Result<Filesystem> {
         free_inodes:  stat.files_free(),
         fill_rate_bps:   None,
         days_until_full: None,
-    })
+    }
 }
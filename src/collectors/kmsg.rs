@@ -0,0 +1,269 @@
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::io::Read;
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+/// Events kept per device (oldest dropped first).
+const MAX_EVENTS_PER_DEVICE: usize = 200;
+
+/// Least time between rebuilds of the device map when a message names an
+/// unknown device, so a burst of them costs one /sys scan.
+const RESOLVER_REBUILD: Duration = Duration::from_secs(5);
+
+/// Storage problem classes recognised in kernel messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KernelEventKind {
+    IoError,
+    MediumError,
+    LinkReset,
+    Timeout,
+    ControllerDown,
+}
+
+impl KernelEventKind {
+    pub const ALL: [KernelEventKind; 5] = [
+        KernelEventKind::ControllerDown,
+        KernelEventKind::MediumError,
+        KernelEventKind::IoError,
+        KernelEventKind::LinkReset,
+        KernelEventKind::Timeout,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            KernelEventKind::IoError        => "I/O error",
+            KernelEventKind::MediumError    => "medium error",
+            KernelEventKind::LinkReset      => "link reset",
+            KernelEventKind::Timeout        => "command timeout",
+            KernelEventKind::ControllerDown => "controller down",
+        }
+    }
+
+    /// Suffix of the alert rule ID: `kernel.<id>`.
+    pub fn id(&self) -> &'static str {
+        match self {
+            KernelEventKind::IoError        => "io_error",
+            KernelEventKind::MediumError    => "medium_error",
+            KernelEventKind::LinkReset      => "link_reset",
+            KernelEventKind::Timeout        => "timeout",
+            KernelEventKind::ControllerDown => "controller_down",
+        }
+    }
+}
+
+/// One classified storage message from the kernel ring buffer.
+#[derive(Debug, Clone)]
+pub struct KernelEvent {
+    /// Unix timestamp
+    pub ts:      i64,
+    /// Whole-disk device name ("sdb", "nvme0n1")
+    pub device:  String,
+    pub kind:    KernelEventKind,
+    pub message: String,
+}
+
+/// Recent storage kernel events, per device, oldest first.
+#[derive(Debug, Default)]
+pub struct KernelLog {
+    pub events: HashMap<String, VecDeque<KernelEvent>>,
+}
+
+impl KernelLog {
+    pub fn push(&mut self, ev: KernelEvent) {
+        let q = self.events.entry(ev.device.clone()).or_default();
+        if q.len() >= MAX_EVENTS_PER_DEVICE {
+            q.pop_front();
+        }
+        q.push_back(ev);
+    }
+
+    /// Drain everything the watcher thread has sent so far. Returns true if anything arrived.
+    pub fn drain(&mut self, rx: &mpsc::Receiver<KernelEvent>) -> bool {
+        let mut any = false;
+        while let Ok(ev) = rx.try_recv() {
+            self.push(ev);
+            any = true;
+        }
+        any
+    }
+
+    pub fn for_device(&self, device: &str) -> Option<&VecDeque<KernelEvent>> {
+        self.events.get(device)
+    }
+}
+
+/// Start a background thread that follows `/dev/kmsg` and sends every storage
+/// event it recognises. The existing ring buffer is replayed first, so recent
+/// errors from before startup are counted too. If `/dev/kmsg` can't be opened
+/// (no permission, containers) the thread exits and nothing is ever sent.
+pub fn spawn() -> mpsc::Receiver<KernelEvent> {
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        let Ok(mut file) = fs::File::open("/dev/kmsg") else { return };
+        let mut resolver = Resolver::new();
+        // Each read() returns exactly one record
+        let mut buf = vec![0u8; 8192];
+        loop {
+            let n = match file.read(&mut buf) {
+                Ok(0)  => return,
+                Ok(n)  => n,
+                // EPIPE: records were overwritten before we read them — carry on
+                Err(e) if e.raw_os_error() == Some(libc::EPIPE) => continue,
+                Err(_) => return,
+            };
+            let record = String::from_utf8_lossy(&buf[..n]);
+            let Some((usec, msg)) = parse_record(&record) else { continue };
+            let Some((device, kind)) = classify(msg, &mut resolver) else { continue };
+            let ev = KernelEvent {
+                ts: boot_time() + (usec / 1_000_000) as i64,
+                device,
+                kind,
+                message: msg.to_string(),
+            };
+            if tx.send(ev).is_err() { return; }
+        }
+    });
+    rx
+}
+
+/// Split a `/dev/kmsg` record "prio,seq,usec,flags;message\n KEY=val…"
+/// into (microseconds since boot, message).
+fn parse_record(record: &str) -> Option<(u64, &str)> {
+    let (header, rest) = record.split_once(';')?;
+    let usec = header.split(',').nth(2)?.parse().ok()?;
+    // Continuation lines (dictionary KEY=value) start with a space
+    let msg = rest.lines().next().unwrap_or("").trim_end();
+    Some((usec, msg))
+}
+
+/// Unix time at which CLOCK_MONOTONIC was zero. /dev/kmsg stamps come from
+/// the kernel's local clock, which like CLOCK_MONOTONIC (and unlike uptime,
+/// CLOCK_BOOTTIME) stops during suspend. Taken per record, so events after a
+/// resume are placed correctly; replayed records from before a suspend still
+/// appear earlier than they happened, by the time spent suspended.
+fn boot_time() -> i64 {
+    let mut ts = libc::timespec { tv_sec: 0, tv_nsec: 0 };
+    // SAFETY: clock_gettime only writes the timespec we pass
+    let mono = if unsafe { libc::clock_gettime(libc::CLOCK_MONOTONIC, &mut ts) } == 0 { ts.tv_sec } else { 0 };
+    chrono::Local::now().timestamp() - mono
+}
+
+/// Maps kernel-side names (ata ports, NVMe controllers) to block devices.
+/// Rebuilt when a name doesn't resolve, so hot-plugged disks are picked up.
+struct Resolver {
+    /// "ata3" → "sdb"
+    ata:    HashMap<String, String>,
+    /// Whole-disk names from /sys/block
+    blocks: Vec<String>,
+    built:  Instant,
+}
+
+impl Resolver {
+    fn new() -> Self {
+        let mut ata    = HashMap::new();
+        let mut blocks = Vec::new();
+        if let Ok(entries) = fs::read_dir("/sys/block") {
+            for e in entries.flatten() {
+                let name = e.file_name().to_string_lossy().to_string();
+                // /sys/block/sdb/device → …/ata3/host2/target2:0:0/2:0:0:0
+                if let Ok(path) = fs::canonicalize(e.path().join("device")) {
+                    if let Some(port) = path.iter()
+                        .map(|c| c.to_string_lossy())
+                        .find(|c| c.starts_with("ata") && c[3..].chars().all(|ch| ch.is_ascii_digit()) && c.len() > 3)
+                    {
+                        ata.insert(port.to_string(), name.clone());
+                    }
+                }
+                blocks.push(name);
+            }
+        }
+        Self { ata, blocks, built: Instant::now() }
+    }
+
+    /// `lookup`, rescanning /sys/block first if it misses (at most once per `RESOLVER_REBUILD`).
+    fn resolve(&mut self, token: &str) -> Option<String> {
+        if let Some(dev) = self.lookup(token) { return Some(dev); }
+        if self.built.elapsed() < RESOLVER_REBUILD { return None; }
+        *self = Self::new();
+        self.lookup(token)
+    }
+
+    fn lookup(&self, token: &str) -> Option<String> {
+        if let Some(dev) = self.ata.get(token) {
+            return Some(dev.clone());
+        }
+        // NVMe controller "nvme0" → first namespace "nvme0n1"
+        if token.starts_with("nvme") && !token[4..].contains('n') {
+            let prefix = format!("{}n", token);
+            return self.blocks.iter().find(|b| b.starts_with(&prefix)).cloned();
+        }
+        let disk = parent_disk(token);
+        if self.blocks.is_empty() || self.blocks.iter().any(|b| b == disk) {
+            Some(disk.to_string())
+        } else {
+            None
+        }
+    }
+}
+
+/// "sdb1" → "sdb", "nvme0n1p2" → "nvme0n1", "vda" → "vda".
+fn parent_disk(name: &str) -> &str {
+    if name.starts_with("nvme") || name.starts_with("mmcblk") {
+        return match name.rfind('p') {
+            Some(i) if i > 4 && name[i + 1..].chars().all(|c| c.is_ascii_digit()) && i + 1 < name.len() => &name[..i],
+            _ => name,
+        };
+    }
+    name.trim_end_matches(|c: char| c.is_ascii_digit())
+}
+
+/// Recognise a storage problem and the device it concerns.
+fn classify(msg: &str, resolver: &mut Resolver) -> Option<(String, KernelEventKind)> {
+    let lower = msg.to_lowercase();
+    let kind = if lower.contains("medium error") || lower.contains("unrecovered read error") {
+        KernelEventKind::MediumError
+    } else if lower.contains("controller is down") || lower.contains("controller fatal status")
+        || lower.contains("removing nvme device")
+    {
+        KernelEventKind::ControllerDown
+    } else if lower.contains("resetting link") || lower.contains("comreset failed")
+        || lower.contains("reset failed") || lower.contains("link is slow to respond")
+    {
+        KernelEventKind::LinkReset
+    } else if lower.contains("timed out") || lower.contains("timeout") || lower.contains("exception emask") {
+        KernelEventKind::Timeout
+    } else if lower.contains("i/o error") || lower.contains("blk_update_request") {
+        KernelEventKind::IoError
+    } else {
+        return None;
+    };
+    let device = device_token(msg).and_then(|t| resolver.resolve(&t))?;
+    Some((device, kind))
+}
+
+/// The device a kernel message talks about: "dev sdb," / "[sdb]" / "ata3:" / "nvme0:".
+fn device_token(msg: &str) -> Option<String> {
+    if let Some(i) = msg.find(" dev ") {
+        let tok: String = msg[i + 5..].chars().take_while(|c| c.is_ascii_alphanumeric()).collect();
+        if !tok.is_empty() { return Some(tok); }
+    }
+    if let (Some(a), Some(b)) = (msg.find('['), msg.find(']')) {
+        let tok = &msg[a + 1..b.max(a + 1)];
+        if !tok.is_empty() && tok.chars().all(|c| c.is_ascii_alphanumeric()) && !tok.chars().all(|c| c.is_ascii_digit()) {
+            return Some(tok.to_string());
+        }
+    }
+    for word in msg.split_whitespace() {
+        let w = word.trim_end_matches(':');
+        // "ata3" or "ata3.00"
+        let port = w.split('.').next().unwrap_or(w);
+        if port.len() > 3 && port.starts_with("ata") && port[3..].chars().all(|c| c.is_ascii_digit()) {
+            return Some(port.to_string());
+        }
+        if w.len() > 4 && w.starts_with("nvme") && w[4..].chars().all(|c| c.is_ascii_alphanumeric()) {
+            return Some(w.to_string());
+        }
+    }
+    None
+}
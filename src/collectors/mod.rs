@@ -2,6 +2,7 @@ pub mod diskstats;
 pub mod pressure;
 pub mod filesystem;
 pub mod lsblk;
+pub mod kmsg;
 pub mod lvm;
 pub mod mdraid;
 pub mod nfs;
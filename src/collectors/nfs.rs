@@ -1,3 +1,9 @@
+use nix::sys::statvfs::Statvfs;
+use std::collections::{HashMap, HashSet};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
 /// NFS/network mount statistics parsed from /proc/self/mountstats.
 #[derive(Debug, Clone)]
 pub struct NfsMountStats {
@@ -7,8 +13,13 @@ pub struct NfsMountStats {
     pub age_secs:          u64,
     pub read_ops:          u64,
     pub write_ops:         u64,
-    pub read_rtt_ms:       f64,      // average RTT per read op (ms)
-    pub write_rtt_ms:      f64,      // average RTT per write op (ms)
+    pub read_rtt_ms:       f64,      // average RTT per read op since mount (ms)
+    pub write_rtt_ms:      f64,      // average RTT per write op since mount (ms)
+    pub read_rtt_total_ms: f64,      // cumulative read RTT (ms)
+    pub write_rtt_total_ms: f64,     // cumulative write RTT (ms)
+    /// Worst of the read/write average RTT over the ops completed since the
+    /// previous sample; None on the first sample or when no ops completed.
+    pub interval_rtt_ms:   Option<f64>,
     pub server_bytes_read: u64,
     pub server_bytes_written: u64,
 }
@@ -73,6 +84,9 @@ pub fn read_nfs_mounts() -> Vec<NfsMountStats> {
                         write_ops: 0,
                         read_rtt_ms: 0.0,
                         write_rtt_ms: 0.0,
+                        read_rtt_total_ms: 0.0,
+                        write_rtt_total_ms: 0.0,
+                        interval_rtt_ms: None,
                         server_bytes_read: 0,
                         server_bytes_written: 0,
                     });
@@ -125,9 +139,11 @@ pub fn read_nfs_mounts() -> Vec<NfsMountStats> {
                     if upper.starts_with("READ:") {
                         m.read_ops   = ops;
                         m.read_rtt_ms = avg_rtt;
+                        m.read_rtt_total_ms = rtt_total;
                     } else {
                         m.write_ops   = ops;
                         m.write_rtt_ms = avg_rtt;
+                        m.write_rtt_total_ms = rtt_total;
                     }
                 }
             }
@@ -144,3 +160,132 @@ pub fn read_nfs_mounts() -> Vec<NfsMountStats> {
 
     mounts
 }
+
+/// Per mount: (read ops, read RTT total, write ops, write RTT total) at the previous sample.
+pub type RttCounters = HashMap<String, (u64, f64, u64, f64)>;
+
+/// Set each mount's `interval_rtt_ms` from the ops completed since the
+/// counters in `prev`, then store the current counters there. A remount
+/// (counters going backwards) starts over.
+pub fn track_interval_rtt(mounts: &mut [NfsMountStats], prev: &mut RttCounters) {
+    let avg = |ops: u64, prev_ops: u64, total: f64, prev_total: f64| {
+        (ops > prev_ops).then(|| (total - prev_total).max(0.0) / (ops - prev_ops) as f64)
+    };
+    for m in mounts.iter_mut() {
+        let curr = (m.read_ops, m.read_rtt_total_ms, m.write_ops, m.write_rtt_total_ms);
+        m.interval_rtt_ms = prev.get(&m.mount)
+            .filter(|p| curr.0 >= p.0 && curr.2 >= p.2)
+            .and_then(|p| {
+                match (avg(curr.0, p.0, curr.1, p.1), avg(curr.2, p.2, curr.3, p.3)) {
+                    (Some(r), Some(w)) => Some(r.max(w)),
+                    (r, w)             => r.or(w),
+                }
+            });
+        prev.insert(m.mount.clone(), curr);
+    }
+    prev.retain(|mount, _| mounts.iter().any(|m| &m.mount == mount));
+}
+
+/// Mounts with a statvfs() probe still running, and when each probe started.
+fn in_flight() -> &'static Mutex<HashMap<String, Instant>> {
+    static IN_FLIGHT: OnceLock<Mutex<HashMap<String, Instant>>> = OnceLock::new();
+    IN_FLIGHT.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// True if a probe of `mount` has been running for at least `min`. Other
+/// collectors use this to avoid blocking on a hung mount.
+pub fn probe_stuck(mount: &str, min: Duration) -> bool {
+    in_flight().lock().is_ok_and(|m| m.get(mount).is_some_and(|t| t.elapsed() >= min))
+}
+
+/// One long-lived probe thread per mount; a send asks it for another statvfs().
+fn probers() -> &'static Mutex<HashMap<String, Sender<()>>> {
+    static PROBERS: OnceLock<Mutex<HashMap<String, Sender<()>>>> = OnceLock::new();
+    PROBERS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// The last statvfs() each probe thread got back.
+fn answers() -> &'static Mutex<HashMap<String, Statvfs>> {
+    static ANSWERS: OnceLock<Mutex<HashMap<String, Statvfs>>> = OnceLock::new();
+    ANSWERS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn spawn_prober(mount: &str) -> Sender<()> {
+    let (tx, rx) = mpsc::channel::<()>();
+    let mount = mount.to_string();
+    std::thread::spawn(move || {
+        // Ends once the mount is gone and its sender dropped
+        for () in rx {
+            let answer = nix::sys::statvfs::statvfs(mount.as_str());
+            if let Ok(mut answers) = answers().lock() {
+                match answer {
+                    Ok(st) => { answers.insert(mount.clone(), st); }
+                    Err(_) => { answers.remove(&mount); }
+                }
+            }
+            if let Ok(mut probes) = in_flight().lock() { probes.remove(&mount); }
+        }
+    });
+    tx
+}
+
+/// Mark `mount` in flight and ask its probe thread for a statvfs(), starting
+/// the thread if needed. Callers hold both locks, in_flight first.
+fn start_probe(mount: &str, probes: &mut HashMap<String, Instant>, workers: &mut HashMap<String, Sender<()>>) {
+    probes.insert(mount.to_string(), Instant::now());
+    let tx = workers.entry(mount.to_string()).or_insert_with(|| spawn_prober(mount));
+    if tx.send(()).is_err() {
+        let tx = spawn_prober(mount);
+        let _ = tx.send(());
+        workers.insert(mount.to_string(), tx);
+    }
+}
+
+/// statvfs() `mount` on its probe thread, waiting at most `wait` for the
+/// answer. None if the probe is still running, so a hung server never blocks
+/// the caller; a probe already in flight is not waited for at all.
+pub fn statvfs(mount: &str, wait: Duration) -> Option<Statvfs> {
+    {
+        let (mut probes, mut workers) = (in_flight().lock().ok()?, probers().lock().ok()?);
+        if probes.contains_key(mount) { return None; }
+        start_probe(mount, &mut probes, &mut workers);
+    }
+    let start = Instant::now();
+    while probe_stuck(mount, Duration::ZERO) {
+        if start.elapsed() >= wait { return None; }
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    answers().lock().ok()?.get(mount).copied()
+}
+
+/// Find unresponsive mounts without blocking the caller: statvfs() each mount
+/// on its probe thread and report those whose probe has been stuck for longer
+/// than `timeout`. A hung probe stays in flight (its thread is parked in the
+/// kernel); the mount is not probed again until it returns.
+pub fn stale_mounts(mounts: &[String], timeout: Duration) -> HashSet<String> {
+    let (Ok(mut probes), Ok(mut workers)) = (in_flight().lock(), probers().lock()) else { return HashSet::new() };
+    workers.retain(|m, _| mounts.contains(m));
+    if let Ok(mut answers) = answers().lock() { answers.retain(|m, _| mounts.contains(m)); }
+    let mut stale = HashSet::new();
+    for mount in mounts {
+        if let Some(started) = probes.get(mount) {
+            if started.elapsed() >= timeout {
+                stale.insert(mount.clone());
+            }
+            continue;
+        }
+        start_probe(mount, &mut probes, &mut workers);
+    }
+    stale
+}
+
+/// Blocking variant for one-shot commands: wait up to `timeout` for the probes
+/// to answer, then report the mounts that did not.
+pub fn wait_stale_mounts(mounts: &[String], timeout: Duration) -> HashSet<String> {
+    stale_mounts(mounts, timeout);
+    let start = Instant::now();
+    while start.elapsed() < timeout && mounts.iter().any(|m| probe_stuck(m, Duration::ZERO)) {
+        std::thread::sleep(Duration::from_millis(50));
+    }
+    mounts.iter().filter(|m| probe_stuck(m, timeout)).cloned().collect()
+}
@@ -23,6 +23,12 @@ pub struct SystemPressure {
     pub mem: PsiResource,
 }
 
+/// A PSI average as f64 for export. The kernel reports two decimals; this
+/// keeps f32 noise ("12.340000152") out of the output.
+pub fn psi_value(v: f32) -> f64 {
+    (v as f64 * 100.0).round() / 100.0
+}
+
 fn parse_psi_file(path: &str) -> Option<PsiResource> {
     let text = fs::read_to_string(path).ok()?;
     let mut res = PsiResource::default();
@@ -1,4 +1,4 @@
-use crate::models::smart::{NvmeHealth, SmartAttribute, SmartData, SmartStatus};
+use crate::models::smart::{NvmeHealth, SelfTestEntry, SelfTestResult, SmartAttribute, SmartData, SmartStatus};
 use serde_json::Value;
 use std::process::Command;
 
@@ -32,7 +32,15 @@ pub fn poll_device(name: &str) -> Option<SmartData> {
     // NVMe health log
     let nvme = parse_nvme_health(&v);
 
-    let mut data = SmartData { status, temperature, power_on_hours, attributes, nvme };
+    // Self-test log and any test in progress
+    let self_test_supported = !v["ata_smart_self_test_log"].is_null() || !v["nvme_self_test_log"].is_null();
+    let self_tests = parse_self_tests(&v);
+    let self_test_remaining = parse_self_test_remaining(&v);
+
+    let mut data = SmartData {
+        status, temperature, power_on_hours, attributes, nvme,
+        self_test_supported, self_tests, self_test_remaining,
+    };
     data.derive_status();
     Some(data)
 }
@@ -58,6 +66,16 @@ fn parse_ata_attributes(v: &Value) -> Vec<SmartAttribute> {
     }).collect()
 }
 
+/// The ATA self-test log stores the drive's power-on hours in 16 bits, so they
+/// wrap every 65536 hours (~7.5 years). Take the latest wrap that doesn't put
+/// the test after the drive's current power-on hours.
+fn ata_lifetime_hours(hours: u64, power_on_hours: Option<u64>) -> u64 {
+    match power_on_hours {
+        Some(poh) if hours < 65536 && hours <= poh => hours + (poh - hours) / 65536 * 65536,
+        _ => hours,
+    }
+}
+
 fn parse_nvme_health(v: &Value) -> Option<NvmeHealth> {
     let log = &v["nvme_smart_health_information_log"];
     if log.is_null() || !log.is_object() { return None; }
@@ -76,3 +94,69 @@ fn parse_nvme_health(v: &Value) -> Option<NvmeHealth> {
         error_log_entries:         log["num_err_log_entries"].as_u64().unwrap_or(0),
     })
 }
+
+/// ATA `ata_smart_self_test_log` or NVMe `nvme_self_test_log` entries, newest first.
+pub fn parse_self_tests(v: &Value) -> Vec<SelfTestEntry> {
+    let mut entries = Vec::new();
+    let power_on_hours = v["power_on_time"]["hours"].as_u64();
+
+    // ATA: status.value high nibble — 0 ok, 1-2 aborted/interrupted, 3-8 failed, 15 running
+    if let Some(table) = v["ata_smart_self_test_log"]["standard"]["table"].as_array() {
+        for row in table {
+            let code = row["status"]["value"].as_u64().unwrap_or(0) >> 4;
+            let result = match code {
+                0      => SelfTestResult::Passed,
+                3..=8  => SelfTestResult::Failed,
+                15     => SelfTestResult::InProgress,
+                _      => SelfTestResult::Aborted,
+            };
+            let test_type = row["type"]["string"].as_str().unwrap_or("?").to_string();
+            entries.push(SelfTestEntry {
+                extended:    row["type"]["value"].as_u64() == Some(2) || test_type.contains("Extended"),
+                test_type,
+                result,
+                status:      row["status"]["string"].as_str().unwrap_or("?").to_string(),
+                hours:       ata_lifetime_hours(row["lifetime_hours"].as_u64().unwrap_or(0), power_on_hours),
+                failing_lba: row["lba"].as_u64(),
+            });
+        }
+    }
+
+    // NVMe: self_test_result.value — 0 ok, 5-7 failed, 15 unused entry, others aborted
+    if let Some(table) = v["nvme_self_test_log"]["table"].as_array() {
+        for row in table {
+            let code = row["self_test_result"]["value"].as_u64().unwrap_or(0);
+            if code == 15 { continue; }
+            let result = match code {
+                0     => SelfTestResult::Passed,
+                5..=7 => SelfTestResult::Failed,
+                _     => SelfTestResult::Aborted,
+            };
+            let test_type = row["self_test_code"]["string"].as_str().unwrap_or("?").to_string();
+            entries.push(SelfTestEntry {
+                extended:    row["self_test_code"]["value"].as_u64() == Some(2) || test_type.contains("Extended"),
+                test_type,
+                result,
+                status:      row["self_test_result"]["string"].as_str().unwrap_or("?").to_string(),
+                hours:       row["power_on_hours"].as_u64().unwrap_or(0),
+                failing_lba: row["lba"].as_u64(),
+            });
+        }
+    }
+
+    entries
+}
+
+/// Percent remaining of a running self-test, if one is in progress.
+pub fn parse_self_test_remaining(v: &Value) -> Option<u8> {
+    let ata = &v["ata_smart_data"]["self_test"]["status"];
+    if ata["value"].as_u64().is_some_and(|s| s >> 4 == 15) {
+        return Some(ata["remaining_percent"].as_u64().unwrap_or(0) as u8);
+    }
+    let nvme = &v["nvme_self_test_log"];
+    if nvme["current_self_test_operation"]["value"].as_u64().is_some_and(|op| op != 0) {
+        let done = nvme["current_self_test_completion_percent"].as_u64().unwrap_or(0);
+        return Some(100u64.saturating_sub(done) as u8);
+    }
+    None
+}
@@ -1,11 +1,16 @@
+use crate::collectors::smart;
 use crate::models::smart::SmartData;
 use serde_json;
 use std::collections::HashMap;
 use std::fs;
 use std::path::PathBuf;
+use std::time::Duration;
 
 pub type SmartCache = HashMap<String, SmartData>;
 
+/// How old the cache may get before one-shot exporters poll smartctl again.
+pub const MAX_AGE: Duration = Duration::from_secs(3600);
+
 pub fn cache_path() -> Option<PathBuf> {
     dirs::data_local_dir().map(|p| p.join("dtop").join("smart_cache.json"))
 }
@@ -34,3 +39,56 @@ pub fn save(cache: &SmartCache) {
         let _ = fs::write(&path, text);
     }
 }
+
+/// Devices whose last one-shot poll failed, with when (Unix timestamp), so
+/// they are not polled again on every call.
+fn failed_path() -> Option<PathBuf> {
+    dirs::data_local_dir().map(|p| p.join("dtop").join("smart_failed.json"))
+}
+
+fn load_failed() -> HashMap<String, i64> {
+    let Some(path) = failed_path() else { return HashMap::new() };
+    fs::read_to_string(path).ok().and_then(|t| serde_json::from_str(&t).ok()).unwrap_or_default()
+}
+
+fn save_failed(failed: &HashMap<String, i64>) {
+    let Some(path) = failed_path() else { return };
+    if let Ok(text) = serde_json::to_string(failed) {
+        let _ = fs::write(path, text);
+    }
+}
+
+/// Time since the cache was last written, if it exists.
+pub fn age() -> Option<Duration> {
+    let modified = fs::metadata(cache_path()?).ok()?.modified().ok()?;
+    Some(modified.elapsed().unwrap_or_default())
+}
+
+/// SMART data for `devices` without a smartctl run per call: the cache as it
+/// is, except that with `poll` set, devices missing from it — or all of them
+/// once it is older than `MAX_AGE` — are polled and the cache rewritten.
+/// Failed polls are remembered and not retried within `MAX_AGE`.
+pub fn load_fresh(devices: &[String], poll: bool) -> SmartCache {
+    let mut cache = load();
+    if !poll { return cache; }
+    let now = chrono::Local::now().timestamp();
+    let mut failed = load_failed();
+    failed.retain(|_, &mut t| now - t < MAX_AGE.as_secs() as i64);
+    let stale = age().is_none_or(|a| a > MAX_AGE);
+    let mut polled = false;
+    for name in devices {
+        if (stale || !cache.contains_key(name)) && !failed.contains_key(name) {
+            polled = true;
+            match smart::poll_device(name) {
+                Some(data) => { cache.insert(name.clone(), data); failed.remove(name); }
+                None       => { failed.insert(name.clone(), now); }
+            }
+        }
+    }
+    // Rewritten even if every poll failed, so its age restarts
+    if polled {
+        save(&cache);
+        save_failed(&failed);
+    }
+    cache
+}
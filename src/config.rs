@@ -17,6 +17,176 @@ pub struct Config {
 
     #[serde(default)]
     pub notifications: NotificationsConfig,
+
+    #[serde(default)]
+    pub daemon: DaemonConfig,
+
+    #[serde(default)]
+    pub mqtt: MqttConfig,
+
+    #[serde(default)]
+    pub influxdb: InfluxConfig,
+
+    #[serde(default)]
+    pub graphite: GraphiteConfig,
+}
+
+/// Output of `dtop --daemon`.
+///
+/// Example in dtop.toml:
+/// ```toml
+/// [daemon]
+/// log = ["journald"]           # any of stderr, syslog, journald, auto
+/// syslog_facility = "local3"
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DaemonConfig {
+    /// Where alert lines go. "auto" = journald (with DTOP_* fields) when started
+    /// by systemd, stderr otherwise; "syslog" = RFC 5424 to /dev/log.
+    pub log: Vec<String>,
+    /// Facility for the syslog sink: "daemon", "user" or "local0" … "local7".
+    pub syslog_facility: String,
+}
+
+impl Default for DaemonConfig {
+    fn default() -> Self {
+        Self { log: vec!["auto".into()], syslog_facility: "daemon".into() }
+    }
+}
+
+/// MQTT publishing from `dtop --daemon`; disabled while `host` is empty.
+///
+/// Example in dtop.toml:
+/// ```toml
+/// [mqtt]
+/// host     = "broker.lan"
+/// port     = 8883
+/// tls      = true
+/// username = "dtop"
+/// password = "…"
+/// interval = "30s"
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MqttConfig {
+    pub host:             String,
+    pub port:             u16,
+    pub tls:              bool,
+    pub username:         String,
+    pub password:         String,
+    /// Empty = dtop-<hostname>.
+    pub client_id:        String,
+    /// Root of every topic; empty = dtop/<hostname>.
+    pub topic_prefix:     String,
+    /// How often device and filesystem state is published ("30s", "2m").
+    pub interval:         String,
+    /// 0 (at most once) or 1 (at least once).
+    pub qos:              u8,
+    /// Keep state topics on the broker for new subscribers.
+    pub retain:           bool,
+    pub keepalive_secs:   u16,
+    /// Publish Home Assistant discovery configs so disks appear as sensors.
+    pub discovery:        bool,
+    pub discovery_prefix: String,
+}
+
+impl Default for MqttConfig {
+    fn default() -> Self {
+        Self {
+            host:             String::new(),
+            port:             1883,
+            tls:              false,
+            username:         String::new(),
+            password:         String::new(),
+            client_id:        String::new(),
+            topic_prefix:     String::new(),
+            interval:         "30s".into(),
+            qos:              0,
+            retain:           true,
+            keepalive_secs:   60,
+            discovery:        true,
+            discovery_prefix: "homeassistant".into(),
+        }
+    }
+}
+
+/// InfluxDB line-protocol push from `dtop --daemon`; disabled while both
+/// `url` and `udp` are empty.
+///
+/// Example in dtop.toml:
+/// ```toml
+/// [influxdb]
+/// url   = "http://influx.lan:8086/api/v2/write?org=ops&bucket=disks"
+/// token = "…"
+/// tags  = { site = "fra1" }
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InfluxConfig {
+    /// HTTP write endpoint: v1 `/write?db=…` or v2 `/api/v2/write?org=…&bucket=…`.
+    /// Timestamps are sent in nanoseconds, the default precision.
+    pub url:                String,
+    /// "host:port" of a UDP listener; used instead of `url` when set.
+    pub udp:                String,
+    /// v2 API token (`Authorization: Token …`).
+    pub token:              String,
+    /// v1 credentials (basic auth), when no token is set.
+    pub username:           String,
+    pub password:           String,
+    /// Prepended to every measurement name ("dtop_device", "dtop_filesystem", …).
+    pub measurement_prefix: String,
+    /// Device identity added as tags: any of "alias", "serial", "model", "type".
+    pub device_tags:        Vec<String>,
+    /// Extra tags on every point.
+    pub tags:               HashMap<String, String>,
+    pub interval:           String,
+    pub timeout_secs:       u64,
+}
+
+impl Default for InfluxConfig {
+    fn default() -> Self {
+        Self {
+            url:                String::new(),
+            udp:                String::new(),
+            token:              String::new(),
+            username:           String::new(),
+            password:           String::new(),
+            measurement_prefix: "dtop_".into(),
+            device_tags:        vec!["alias".into(), "serial".into(), "model".into(), "type".into()],
+            tags:               HashMap::new(),
+            interval:           "30s".into(),
+            timeout_secs:       10,
+        }
+    }
+}
+
+/// Graphite plaintext push (TCP) from `dtop --daemon`; disabled while `host` is empty.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GraphiteConfig {
+    pub host:         String,
+    pub port:         u16,
+    /// Root of every metric path; "{host}" becomes the hostname (dots replaced).
+    pub prefix:       String,
+    /// What names a device in the path: "device" (kernel name), "alias" or
+    /// "serial"; falls back to the kernel name when unset.
+    pub device_key:   String,
+    pub interval:     String,
+    pub timeout_secs: u64,
+}
+
+impl Default for GraphiteConfig {
+    fn default() -> Self {
+        Self {
+            host:         String::new(),
+            port:         2003,
+            prefix:       "dtop.{host}".into(),
+            device_key:   "device".into(),
+            interval:     "30s".into(),
+            timeout_secs: 10,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -37,6 +207,323 @@ pub struct AlertConfig {
     /// Per-attribute SMART alert rules evaluated against raw values.
     #[serde(default = "SmartAlertRule::defaults")]
     pub smart_rules: Vec<SmartAlertRule>,
+    /// Rate-of-change rules on SMART counters, evaluated against timestamped history.
+    #[serde(default = "RateAlertRule::defaults")]
+    pub rate_rules: Vec<RateAlertRule>,
+    /// User-defined expression rules evaluated alongside the built-in checks.
+    #[serde(default)]
+    pub rules: Vec<AlertRule>,
+    /// Sustain / hysteresis policy per condition, keyed by rule ID or glob ("smart.attr.*").
+    #[serde(default)]
+    pub conditions: HashMap<String, ConditionPolicy>,
+    /// Recurring windows during which matching alerts are silenced.
+    #[serde(default)]
+    pub maintenance: Vec<MaintenanceWindow>,
+    /// Threshold overrides and disabled checks for specific mounts and devices.
+    #[serde(default)]
+    pub overrides: AlertOverrides,
+    /// How long a storage error in the kernel log keeps its `kernel.*` alert active ("1h").
+    #[serde(default = "default_kernel_window")]
+    pub kernel_window: String,
+    /// I/O pressure stall thresholds.
+    #[serde(default)]
+    pub psi: PsiThresholds,
+    /// An NFS mount whose stat() has not returned after this long is reported stale ("10s").
+    /// Empty or "0" disables the check.
+    #[serde(default = "default_nfs_stale_timeout")]
+    pub nfs_stale_timeout: String,
+    /// Group alerts along the storage stack (member disk → array ← filesystem)
+    /// and apply the built-in inhibitions.
+    #[serde(default = "default_correlate")]
+    pub correlate: bool,
+    /// User-defined inhibition rules.
+    #[serde(default)]
+    pub inhibit: Vec<InhibitRule>,
+    /// Events older than this are pruned from `alerts.jsonl` ("90d").
+    /// Empty or "0" keeps everything.
+    #[serde(default = "default_log_retention")]
+    pub log_retention: String,
+}
+
+fn default_correlate() -> bool { true }
+fn default_log_retention() -> String { "90d".into() }
+
+/// Mute alerts matching `target` while an alert matching `source` is firing.
+/// Inhibited alerts stay visible (dimmed) but do not notify or escalate.
+///
+/// Example in dtop.toml:
+/// ```toml
+/// [[alerts.inhibit]]
+/// source     = "rule=raid.degraded"   # same syntax as `dtop --silence`
+/// target     = "rule=temperature"
+/// same_stack = true                   # only targets on the source's disks/filesystems (default)
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InhibitRule {
+    pub source: String,
+    pub target: String,
+    #[serde(default = "default_same_stack")]
+    pub same_stack: bool,
+}
+
+fn default_same_stack() -> bool { true }
+
+fn default_nfs_stale_timeout() -> String { "10s".into() }
+
+/// Thresholds on `/proc/pressure/io`, in percent of wall time stalled. 0 = disabled.
+///
+/// "some" = at least one task was stalled on I/O; "full" = every runnable task was.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PsiThresholds {
+    pub some_avg10_warn: f64,
+    pub some_avg10_crit: f64,
+    pub some_avg60_warn: f64,
+    pub some_avg60_crit: f64,
+    pub full_avg10_warn: f64,
+    pub full_avg10_crit: f64,
+    pub full_avg60_warn: f64,
+    pub full_avg60_crit: f64,
+}
+
+fn default_kernel_window() -> String { "1h".into() }
+
+/// Per-mount / per-device threshold overrides.
+///
+/// Example in dtop.toml:
+/// ```toml
+/// [alerts.overrides."/srv/archive"]        # mount point (glob allowed: "/mnt/*")
+/// filesystem_warn_pct = 98
+/// filesystem_crit_pct = 99.5
+/// disable             = ["fs.fill_rate"]
+///
+/// [alerts.overrides.serial."ZA1234*"]      # drive serial (glob)
+/// temperature_warn_hdd = 58
+/// temperature_crit_hdd = 65
+///
+/// [alerts.overrides.device."sdc"]          # kernel name or alias (glob)
+/// disable = ["io.*"]
+/// ```
+///
+/// Mount blocks apply to filesystem checks; device and serial blocks to device
+/// checks (serial wins over device). Where several keys match, more specific
+/// (longer) keys win. `disable` takes rule-ID globs and also covers expression
+/// rules ("rule.<id>") and rate rules ("smart.rate.*").
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AlertOverrides {
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub device: HashMap<String, ThresholdOverride>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub serial: HashMap<String, ThresholdOverride>,
+    /// Any other key is a mount point.
+    #[serde(flatten)]
+    pub mounts: HashMap<String, ThresholdOverride>,
+}
+
+/// Any subset of `AlertThresholds`, plus checks to switch off.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ThresholdOverride {
+    pub filesystem_warn_pct:  Option<f64>,
+    pub filesystem_crit_pct:  Option<f64>,
+    pub inode_warn_pct:       Option<f64>,
+    pub inode_crit_pct:       Option<f64>,
+    pub temperature_warn_ssd: Option<i32>,
+    pub temperature_crit_ssd: Option<i32>,
+    pub temperature_warn_hdd: Option<i32>,
+    pub temperature_crit_hdd: Option<i32>,
+    pub io_util_warn_pct:     Option<f64>,
+    pub latency_warn_ms:      Option<f64>,
+    pub latency_crit_ms:      Option<f64>,
+    pub fill_days_warn:       Option<f64>,
+    pub fill_days_crit:       Option<f64>,
+    pub selftest_max_age_days: Option<f64>,
+    pub nfs_rtt_warn_ms:      Option<f64>,
+    pub nfs_rtt_crit_ms:      Option<f64>,
+    /// Rule-ID globs to disable: ["temperature", "smart.attr.*"]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub disable:              Vec<String>,
+}
+
+/// Thresholds after applying every matching override block.
+#[derive(Debug, Clone)]
+pub struct EffectiveThresholds {
+    pub thresholds: AlertThresholds,
+    /// Rule-ID globs disabled for this target
+    pub disabled:   Vec<String>,
+    /// Override blocks that matched, for `--config`: `serial."ZA1234"`
+    pub sources:    Vec<String>,
+}
+
+impl EffectiveThresholds {
+    pub fn is_disabled(&self, rule: &str) -> bool {
+        self.disabled.iter().any(|p| crate::util::glob::glob_match(p, rule))
+    }
+}
+
+impl AlertThresholds {
+    fn apply(&mut self, o: &ThresholdOverride) {
+        if let Some(v) = o.filesystem_warn_pct  { self.filesystem_warn_pct  = v; }
+        if let Some(v) = o.filesystem_crit_pct  { self.filesystem_crit_pct  = v; }
+        if let Some(v) = o.inode_warn_pct       { self.inode_warn_pct       = v; }
+        if let Some(v) = o.inode_crit_pct       { self.inode_crit_pct       = v; }
+        if let Some(v) = o.temperature_warn_ssd { self.temperature_warn_ssd = v; }
+        if let Some(v) = o.temperature_crit_ssd { self.temperature_crit_ssd = v; }
+        if let Some(v) = o.temperature_warn_hdd { self.temperature_warn_hdd = v; }
+        if let Some(v) = o.temperature_crit_hdd { self.temperature_crit_hdd = v; }
+        if let Some(v) = o.io_util_warn_pct     { self.io_util_warn_pct     = v; }
+        if let Some(v) = o.latency_warn_ms      { self.latency_warn_ms      = v; }
+        if let Some(v) = o.latency_crit_ms      { self.latency_crit_ms      = v; }
+        if let Some(v) = o.fill_days_warn       { self.fill_days_warn       = v; }
+        if let Some(v) = o.fill_days_crit       { self.fill_days_crit       = v; }
+        if let Some(v) = o.selftest_max_age_days { self.selftest_max_age_days = v; }
+        if let Some(v) = o.nfs_rtt_warn_ms      { self.nfs_rtt_warn_ms      = v; }
+        if let Some(v) = o.nfs_rtt_crit_ms      { self.nfs_rtt_crit_ms      = v; }
+    }
+}
+
+/// Matching entries of one override table, least specific (shortest key) first,
+/// equal lengths in key order so the result doesn't depend on HashMap iteration.
+fn matching<'a>(
+    table:  &'a HashMap<String, ThresholdOverride>,
+    values: &[&str],
+) -> Vec<(&'a String, &'a ThresholdOverride)> {
+    let mut hits: Vec<_> = table.iter()
+        .filter(|(k, _)| values.iter().any(|v| crate::util::glob::glob_match(k, v)))
+        .collect();
+    hits.sort_by(|a, b| a.0.len().cmp(&b.0.len()).then(a.0.cmp(b.0)));
+    hits
+}
+
+/// A recurring maintenance window; matching alerts still show (dimmed) but do not
+/// notify or affect `--check`.
+///
+/// Example in dtop.toml:
+/// ```toml
+/// [[alerts.maintenance]]
+/// name     = "weekly scrub"
+/// days     = ["sun"]        # empty = every day
+/// start    = "02:00"        # local time, HH:MM
+/// duration = "4h"
+/// match    = "device=sd*"   # same syntax as `dtop --silence`; empty = all alerts
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MaintenanceWindow {
+    pub name: String,
+    /// Weekdays ("mon", "tue", …); empty = every day
+    #[serde(default)]
+    pub days: Vec<String>,
+    /// Start time, "HH:MM" local
+    pub start: String,
+    /// Length, e.g. "90m" or "4h"
+    pub duration: String,
+    /// Alert matcher: "device=sd*,severity=warn"
+    #[serde(rename = "match", default)]
+    pub matcher: String,
+    /// `matcher`, parsed on first use (see `MaintenanceWindow::parsed`).
+    #[serde(skip)]
+    pub parsed: std::sync::OnceLock<Result<crate::util::silence::Matcher, String>>,
+}
+
+/// How long a condition must hold before it fires, and where it clears.
+///
+/// Example in dtop.toml:
+/// ```toml
+/// [alerts.conditions."io.latency"]
+/// for        = "5m"    # must hold for 5 minutes before firing
+/// clear_warn = 30.0    # once firing, stays WARN until latency drops below 30ms
+/// clear_crit = 150.0   # once CRIT, stays CRIT until latency drops below 150ms
+///
+/// [alerts.conditions."fs.usage"]
+/// clear_warn = 82.0
+/// ```
+///
+/// Clear thresholds apply to the numeric checks (temperature, io.util, io.latency,
+/// fs.usage, fs.inodes, fs.fill_rate); for fill_rate they are in days and the alert
+/// clears once the projection rises above them. One-shot commands (`--check`,
+/// `--summary`) evaluate a single sample and ignore `for`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ConditionPolicy {
+    /// Sustain duration: "30s", "5m", "1h". Empty = fire on the first sample.
+    #[serde(rename = "for", default, skip_serializing_if = "Option::is_none")]
+    pub hold_for: Option<String>,
+    /// Warning clears once the value is back past this (default: the warning threshold).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub clear_warn: Option<f64>,
+    /// Critical clears once the value is back past this (default: the critical threshold).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub clear_crit: Option<f64>,
+}
+
+impl AlertConfig {
+    fn effective(&self, blocks: Vec<(String, &ThresholdOverride)>) -> EffectiveThresholds {
+        let mut eff = EffectiveThresholds {
+            thresholds: self.thresholds.clone(),
+            disabled:   Vec::new(),
+            sources:    Vec::new(),
+        };
+        for (label, o) in blocks {
+            eff.thresholds.apply(o);
+            eff.disabled.extend(o.disable.iter().cloned());
+            eff.sources.push(label);
+        }
+        eff
+    }
+
+    /// Thresholds for a block device, matched by kernel name / alias and serial.
+    pub fn for_device(&self, names: &[&str], serial: Option<&str>) -> EffectiveThresholds {
+        let mut blocks: Vec<(String, &ThresholdOverride)> = matching(&self.overrides.device, names)
+            .into_iter()
+            .map(|(k, o)| (format!("device.\"{}\"", k), o))
+            .collect();
+        if let Some(sn) = serial {
+            blocks.extend(matching(&self.overrides.serial, &[sn]).into_iter()
+                .map(|(k, o)| (format!("serial.\"{}\"", k), o)));
+        }
+        self.effective(blocks)
+    }
+
+    /// Thresholds for a mounted filesystem (or "zpool:<name>").
+    pub fn for_mount(&self, mount: &str) -> EffectiveThresholds {
+        let blocks = matching(&self.overrides.mounts, &[mount]).into_iter()
+            .map(|(k, o)| (format!("\"{}\"", k), o))
+            .collect();
+        self.effective(blocks)
+    }
+
+    /// Policy for a rule ID: exact key first, then the most specific matching
+    /// glob key (longest literal prefix, ties broken by the key itself).
+    pub fn policy(&self, rule: &str) -> Option<&ConditionPolicy> {
+        let literal = |pat: &str| pat.find(['*', '?']).unwrap_or(pat.len());
+        self.conditions.get(rule).or_else(|| {
+            self.conditions.iter()
+                .filter(|(pat, _)| crate::util::glob::glob_match(pat, rule))
+                .min_by(|a, b| literal(b.0).cmp(&literal(a.0)).then(a.0.cmp(b.0)))
+                .map(|(_, p)| p)
+        })
+    }
+
+    /// Seconds a condition must hold before firing (expression rules may set `for` inline).
+    pub fn hold_secs(&self, rule: &str) -> u64 {
+        let inline = rule.strip_prefix("rule.")
+            .and_then(|id| self.rules.iter().find(|r| r.id == id))
+            .and_then(|r| r.hold_for.as_deref());
+        inline
+            .or_else(|| self.policy(rule).and_then(|p| p.hold_for.as_deref()))
+            .and_then(crate::util::human::parse_duration)
+            .unwrap_or(0)
+    }
+
+    /// Alert log retention in seconds; None keeps everything.
+    pub fn log_retention_secs(&self) -> Option<i64> {
+        crate::util::human::parse_duration(&self.log_retention).filter(|&s| s > 0).map(|s| s as i64)
+    }
+
+    /// Stale-mount probe timeout; None when the check is disabled.
+    pub fn nfs_stale_timeout(&self) -> Option<std::time::Duration> {
+        crate::util::human::parse_duration(&self.nfs_stale_timeout)
+            .filter(|&s| s > 0)
+            .map(std::time::Duration::from_secs)
+    }
 }
 
 /// A configurable SMART attribute alert rule.
@@ -87,6 +574,108 @@ impl SmartAlertRule {
     }
 }
 
+/// Alert when a SMART counter grows by at least `delta` within `window`.
+///
+/// Example in dtop.toml:
+/// ```toml
+/// [[alerts.rate_rules]]
+/// attr     = 5        # Reallocated Sectors; 9999 = NVMe media errors
+/// window   = "7d"     # "24h", "1d", "7d"
+/// delta    = 1        # minimum growth within the window
+/// severity = "crit"   # "warn" or "crit"
+/// ```
+///
+/// A count that has been stable for years produces no rate alert (the static
+/// `smart_rules` still warn on it); growth escalates.
+/// When several rules match the same attribute, the most severe one wins.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RateAlertRule {
+    /// SMART attribute ID, or 9999 for NVMe media errors
+    pub attr: u32,
+    /// Look-back window, e.g. "1d" or "7d"
+    pub window: String,
+    /// Minimum raw-value growth within the window
+    pub delta: u64,
+    /// "warn" or "crit"
+    pub severity: String,
+    /// Optional custom message; None = auto-generated
+    #[serde(default)]
+    pub message: Option<String>,
+}
+
+impl RateAlertRule {
+    pub fn defaults() -> Vec<Self> {
+        let rule = |attr, window: &str, delta, severity: &str| RateAlertRule {
+            attr, window: window.into(), delta, severity: severity.into(), message: None,
+        };
+        vec![
+            rule(5,    "7d", 1,  "crit"),  // Reallocated sectors growing
+            rule(197,  "1d", 1,  "crit"),  // Pending sectors growing
+            rule(198,  "7d", 1,  "crit"),  // Offline uncorrectable growing
+            rule(199,  "1d", 10, "warn"),  // CRC errors: cabling / backplane
+            rule(9999, "7d", 1,  "crit"),  // NVMe media errors growing
+        ]
+    }
+
+    pub fn window_secs(&self) -> Option<i64> {
+        crate::util::human::parse_duration(&self.window).map(|s| s as i64)
+    }
+}
+
+/// A user-defined alert rule written as an expression over collected metrics.
+///
+/// Example in dtop.toml:
+/// ```toml
+/// [[alerts.rules]]
+/// id       = "hdd-write-latency"
+/// expr     = 'device.write_latency_ms > 30 && device.type == "HDD"'
+/// severity = "warn"                       # "warn" or "crit"
+/// message  = "write latency {device.write_latency_ms}ms"
+/// devices  = ["sd*"]                      # optional glob selectors
+///
+/// [[alerts.rules]]
+/// id       = "var-inodes"
+/// expr     = 'fs.inode_pct > 90 && fs.mount =~ "^/var"'
+/// severity = "crit"
+/// for      = "5m"                         # must hold this long before firing
+/// clear    = "fs.inode_pct < 85"          # once firing, stays until this is true
+/// ```
+///
+/// Rules that reference `fs.*` are evaluated once per filesystem (with the backing
+/// device's `device.*` metrics when known); otherwise once per block device, where
+/// `mounts` selects devices with a matching filesystem on them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlertRule {
+    /// Short unique identifier, shown in the alert log and used as the rule ID.
+    pub id: String,
+    /// Boolean expression, e.g. `device.temperature >= 50 && device.rotational`.
+    pub expr: String,
+    /// "warn" or "crit"
+    #[serde(default = "default_rule_severity")]
+    pub severity: String,
+    /// Message template; `{device}`, `{mount}` and any metric name in braces are substituted.
+    #[serde(default)]
+    pub message: Option<String>,
+    /// Glob patterns matched against device name, alias or serial. Empty = all devices.
+    #[serde(default)]
+    pub devices: Vec<String>,
+    /// Glob patterns matched against mount points (for device rules, the mounts
+    /// on the device). Empty = all mounts.
+    #[serde(default)]
+    pub mounts: Vec<String>,
+    /// Sustain duration before firing ("5m"). Empty = fire on the first sample.
+    #[serde(rename = "for", default, skip_serializing_if = "Option::is_none")]
+    pub hold_for: Option<String>,
+    /// Clear expression: once firing, the alert stays active until this is true.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub clear: Option<String>,
+    /// `expr` and `clear`, parsed on first use (`None` if invalid).
+    #[serde(skip)]
+    pub parsed: std::sync::OnceLock<(Option<crate::util::expr::Expr>, Option<crate::util::expr::Expr>)>,
+}
+
+fn default_rule_severity() -> String { "warn".into() }
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AlertThresholds {
     pub filesystem_warn_pct:  f64,
@@ -106,8 +695,21 @@ pub struct AlertThresholds {
     pub fill_days_warn:       f64,
     /// Alert (critical) when a filesystem is projected to fill within this many days. 0 = disabled.
     pub fill_days_crit:       f64,
+    /// Warn when a drive has not completed an extended self-test within this many
+    /// power-on days (the drive's clock, so days spent powered off don't count). 0 = disabled.
+    #[serde(default)]
+    pub selftest_max_age_days: f64,
+    /// Average NFS round-trip time per op (ms, over the last interval) warning threshold. 0 = disabled.
+    #[serde(default = "default_nfs_rtt_warn")]
+    pub nfs_rtt_warn_ms:      f64,
+    /// Average NFS round-trip time per op (ms, over the last interval) critical threshold. 0 = disabled.
+    #[serde(default = "default_nfs_rtt_crit")]
+    pub nfs_rtt_crit_ms:      f64,
 }
 
+fn default_nfs_rtt_warn() -> f64 { 50.0 }
+fn default_nfs_rtt_crit() -> f64 { 250.0 }
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DevicesConfig {
     /// Glob-style patterns of devices to exclude (e.g. "loop*", "sr*")
@@ -127,6 +729,328 @@ pub struct NotificationsConfig {
     pub notify_warning: bool,
     /// Send a desktop notification via notify-send when new alerts fire (TUI mode).
     pub notify_send: bool,
+    /// Re-send active, unacknowledged alerts every this many hours (0 = never).
+    #[serde(default)]
+    pub renotify_hours: u64,
+    /// Raise unacknowledged Warnings to Critical once they have been firing this long (0 = never).
+    #[serde(default)]
+    pub escalate_after_hours: u64,
+    /// Webhook for escalations; empty = use `webhook_url`.
+    #[serde(default)]
+    pub escalation_webhook_url: String,
+    /// Payload, headers and signing for `webhook_url`.
+    #[serde(default)]
+    pub webhook: WebhookOptions,
+    /// Payload, headers and signing for `escalation_webhook_url`.
+    #[serde(default)]
+    pub escalation_webhook: WebhookOptions,
+    /// Named notification channels. When empty, channels are derived from
+    /// `webhook_url`, `escalation_webhook_url` and `notify_send`.
+    #[serde(default)]
+    pub channels: Vec<ChannelConfig>,
+    /// Which alerts go to which channels. When empty, every warning and critical
+    /// goes to every channel.
+    #[serde(default)]
+    pub routes: Vec<RouteConfig>,
+    /// Mail relay used by email channels and scheduled reports.
+    #[serde(default)]
+    pub smtp: SmtpConfig,
+    /// Reports the daemon mails on a schedule.
+    #[serde(default)]
+    pub reports: Vec<ReportConfig>,
+    /// Default `batch_window` for channels that don't set one.
+    #[serde(default)]
+    pub batch_window: String,
+    /// Default `max_per_hour` for channels that don't set one (0 = unlimited).
+    #[serde(default)]
+    pub max_per_hour: u32,
+    /// Daily digest of the last 24 hours of alerts.
+    #[serde(default)]
+    pub digest: DigestConfig,
+}
+
+/// Delivery mechanism of a notification channel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChannelKind {
+    Webhook,
+    Exec,
+    Email,
+    Syslog,
+    Journald,
+    Desktop,
+}
+
+impl ChannelKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ChannelKind::Webhook => "webhook",
+            ChannelKind::Exec    => "exec",
+            ChannelKind::Email   => "email",
+            ChannelKind::Syslog  => "syslog",
+            ChannelKind::Journald => "journald",
+            ChannelKind::Desktop => "desktop",
+        }
+    }
+}
+
+/// A named place notifications can be sent.
+///
+/// Example in dtop.toml:
+/// ```toml
+/// [[notifications.channels]]
+/// name = "pager"
+/// type = "webhook"
+/// url  = "https://events.example.com/hook"
+/// bearer_token = "…"                  # any [notifications.webhook] option
+///
+/// [[notifications.channels]]
+/// name    = "ticket"
+/// type    = "exec"
+/// command = "/usr/local/bin/open-ticket"   # run via sh -c per alert, JSON on stdin
+///
+/// [[notifications.channels]]
+/// name = "ops-mail"
+/// type = "email"
+/// to   = ["ops@example.com"]
+///
+/// [[notifications.channels]]
+/// name = "log"
+/// type = "syslog"
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChannelConfig {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub kind: ChannelKind,
+    /// webhook: target URL
+    #[serde(default)]
+    pub url: String,
+    /// webhook: payload, headers and signing
+    #[serde(flatten)]
+    pub webhook: WebhookOptions,
+    /// exec: shell command. It runs once per alert event with `DTOP_*` variables
+    /// set and the event as JSON on stdin; `timeout_secs` limits its run time.
+    #[serde(default)]
+    pub command: String,
+    /// exec: commands of this channel allowed to run at once; more wait their turn.
+    #[serde(default = "default_max_concurrent")]
+    pub max_concurrent: usize,
+    /// syslog: facility ("daemon", "local0" … "local7"); empty = daemon
+    #[serde(default)]
+    pub facility: String,
+    /// email: recipients
+    #[serde(default)]
+    pub to: Vec<String>,
+    /// email: sender address; empty = `smtp.from`
+    #[serde(default)]
+    pub from: String,
+    /// Collect alerts for this long ("30s", "2m") and send them as one message;
+    /// unset = `notifications.batch_window`, empty = send every tick.
+    #[serde(default)]
+    pub batch_window: Option<String>,
+    /// Messages allowed per rolling hour; unset = `notifications.max_per_hour`,
+    /// 0 = unlimited. Criticals still go out over the limit, and the next
+    /// message says how many alerts were held back.
+    #[serde(default)]
+    pub max_per_hour: Option<u32>,
+    /// Tell this channel when an alert it was sent stops firing (default true).
+    #[serde(default = "default_send_resolved")]
+    pub send_resolved: bool,
+}
+
+fn default_max_concurrent() -> usize { 4 }
+fn default_send_resolved() -> bool { true }
+
+impl ChannelConfig {
+    pub fn new(name: &str, kind: ChannelKind) -> Self {
+        Self {
+            name:    name.to_string(),
+            kind,
+            url:     String::new(),
+            webhook: WebhookOptions::default(),
+            command: String::new(),
+            max_concurrent: default_max_concurrent(),
+            facility: String::new(),
+            to:      Vec::new(),
+            from:    String::new(),
+            batch_window: None,
+            max_per_hour: None,
+            send_resolved: default_send_resolved(),
+        }
+    }
+
+    /// Batch window in seconds (0 = none).
+    pub fn batch_secs(&self) -> u64 {
+        self.batch_window.as_deref().and_then(crate::util::human::parse_duration).unwrap_or(0)
+    }
+}
+
+/// SMTP relay for email channels and reports. The default suits a local MTA.
+///
+/// Example in dtop.toml:
+/// ```toml
+/// [notifications.smtp]
+/// host     = "smtp.example.com"
+/// port     = 587
+/// security = "starttls"          # none | starttls | tls (implicit, port 465)
+/// username = "dtop"
+/// password = "…"
+/// from     = "dtop <dtop@example.com>"
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SmtpConfig {
+    pub host:         String,
+    pub port:         u16,
+    pub security:     String,
+    /// AUTH PLAIN/LOGIN when set; only sent over TLS unless the relay is loopback.
+    pub username:     String,
+    pub password:     String,
+    /// Default sender; channels may override it. Empty = dtop@<hostname>.
+    pub from:         String,
+    pub timeout_secs: u64,
+}
+
+impl Default for SmtpConfig {
+    fn default() -> Self {
+        Self {
+            host:         "localhost".to_string(),
+            port:         25,
+            security:     "none".to_string(),
+            username:     String::new(),
+            password:     String::new(),
+            from:         String::new(),
+            timeout_secs: 30,
+        }
+    }
+}
+
+/// A health report the daemon mails through email channels on a schedule.
+///
+/// Example in dtop.toml:
+/// ```toml
+/// [[notifications.reports]]
+/// name     = "weekly"
+/// channels = ["ops-mail"]
+/// format   = "html"        # html | markdown
+/// schedule = "weekly"      # daily | weekly
+/// day      = "mon"
+/// at       = "08:00"
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReportConfig {
+    pub name:     String,
+    pub channels: Vec<String>,
+    /// "html" (default) or "markdown"
+    #[serde(default)]
+    pub format:   String,
+    /// "daily" or "weekly" (default)
+    #[serde(default)]
+    pub schedule: String,
+    /// Weekday for weekly reports; empty = Monday.
+    #[serde(default)]
+    pub day:      String,
+    /// Local time "HH:MM"; empty = 08:00.
+    #[serde(default)]
+    pub at:       String,
+}
+
+/// A daily summary of the last 24 hours of alerts, with counts per device,
+/// sent to `channels` (any type). Disabled while `channels` is empty.
+///
+/// Example in dtop.toml:
+/// ```toml
+/// [notifications.digest]
+/// channels = ["ops-mail"]
+/// at       = "08:00"
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DigestConfig {
+    pub channels: Vec<String>,
+    /// Local time "HH:MM"; empty = 08:00.
+    pub at:       String,
+}
+
+/// Send matching alerts to `channels`. Every route that matches an alert is used;
+/// empty selectors match anything.
+///
+/// Example in dtop.toml:
+/// ```toml
+/// [[notifications.routes]]       # criticals on the data disks page someone
+/// channels = ["pager"]
+/// severity = "crit"
+/// devices  = ["sdb", "sdc", "nvme1*"]
+///
+/// [[notifications.routes]]       # filesystem warnings to chat, office hours only
+/// channels = ["chat"]
+/// rules    = ["fs.*"]
+/// hours    = "08:00-18:00"
+/// days     = ["mon", "tue", "wed", "thu", "fri"]
+///
+/// [[notifications.routes]]       # everything to syslog
+/// channels = ["log"]
+/// severity = "info"
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RouteConfig {
+    /// Channel names to deliver to.
+    pub channels: Vec<String>,
+    /// Minimum severity: "info", "warn" or "crit". Empty = "warn".
+    pub severity: String,
+    /// Device name globs.
+    pub devices: Vec<String>,
+    /// Mount point globs.
+    pub mounts: Vec<String>,
+    /// Alert kinds as rule-ID globs ("fs.*", "smart.*", "kernel.*").
+    pub rules: Vec<String>,
+    /// Notification events: "fired", "reminder", "escalated". Empty = all.
+    pub events: Vec<String>,
+    /// Local time of day, "HH:MM-HH:MM" (may wrap past midnight). Empty = always.
+    pub hours: String,
+    /// Weekdays ("mon", "tue", …); empty = every day.
+    pub days: Vec<String>,
+}
+
+/// How alerts are delivered to one webhook endpoint.
+///
+/// Example in dtop.toml:
+/// ```toml
+/// [notifications.webhook]
+/// template     = '{"summary": "{{text}}", "alerts": {{alerts}}}'
+/// bearer_token = "s3cret"
+/// hmac_secret  = "shared-key"      # adds X-Dtop-Timestamp / X-Dtop-Signature
+/// headers      = { "X-Team" = "storage" }
+/// ```
+///
+/// Template placeholders: `{{text}}`, `{{kind}}`, `{{severity}}`, `{{count}}`,
+/// `{{host}}`, `{{timestamp}}` (JSON-escaped, without quotes), `{{duration}}`
+/// (seconds a resolved alert was firing) and `{{alerts}}` (a JSON array).
+///
+/// `format` selects a built-in payload instead of the template: "ntfy", "gotify",
+/// "pagerduty" (Events v2, needs `routing_key`), "alertmanager" (`/api/v2/alerts`)
+/// or "teams" (Adaptive Card).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WebhookOptions {
+    /// Built-in payload format; empty = use `template`.
+    pub format: String,
+    /// Request body template; empty = Slack/Discord-style `{"text": "{{text}}"}`.
+    pub template: String,
+    /// PagerDuty integration (routing) key.
+    pub routing_key: String,
+    /// Extra request headers.
+    pub headers: HashMap<String, String>,
+    /// Sent as `Authorization: Bearer <token>` when set.
+    pub bearer_token: String,
+    /// HMAC-SHA256 key; the signature covers "<timestamp>.<body>".
+    pub hmac_secret: String,
+    /// Connect / read timeout per attempt, in seconds.
+    pub timeout_secs: u64,
+    /// Attempts before a delivery is dropped; retries back off from 30s to 1h.
+    pub max_attempts: u32,
 }
 
 // ── Defaults ─────────────────────────────────────────────────────────
@@ -138,6 +1062,10 @@ impl Default for Config {
             alerts:        AlertConfig::default(),
             devices:       DevicesConfig::default(),
             notifications: NotificationsConfig::default(),
+            daemon:        DaemonConfig::default(),
+            mqtt:          MqttConfig::default(),
+            influxdb:      InfluxConfig::default(),
+            graphite:      GraphiteConfig::default(),
         }
     }
 }
@@ -154,6 +1082,17 @@ impl Default for AlertConfig {
             thresholds:   AlertThresholds::default(),
             cooldown_hours: 0,
             smart_rules:  SmartAlertRule::defaults(),
+            rate_rules:   RateAlertRule::defaults(),
+            rules:        Vec::new(),
+            conditions:   HashMap::new(),
+            maintenance:  Vec::new(),
+            overrides:    AlertOverrides::default(),
+            kernel_window: default_kernel_window(),
+            psi:          PsiThresholds::default(),
+            nfs_stale_timeout: default_nfs_stale_timeout(),
+            correlate:    true,
+            inhibit:      Vec::new(),
+            log_retention: default_log_retention(),
         }
     }
 }
@@ -174,6 +1113,24 @@ impl Default for AlertThresholds {
             latency_crit_ms:      200.0,
             fill_days_warn:       14.0,
             fill_days_crit:       3.0,
+            selftest_max_age_days: 0.0,
+            nfs_rtt_warn_ms:      default_nfs_rtt_warn(),
+            nfs_rtt_crit_ms:      default_nfs_rtt_crit(),
+        }
+    }
+}
+
+impl Default for PsiThresholds {
+    fn default() -> Self {
+        Self {
+            some_avg10_warn: 0.0,
+            some_avg10_crit: 0.0,
+            some_avg60_warn: 30.0,
+            some_avg60_crit: 0.0,
+            full_avg10_warn: 0.0,
+            full_avg10_crit: 0.0,
+            full_avg60_warn: 10.0,
+            full_avg60_crit: 25.0,
         }
     }
 }
@@ -185,6 +1142,105 @@ impl Default for NotificationsConfig {
             notify_critical:  true,
             notify_warning:   false,
             notify_send:      false,
+            renotify_hours:   0,
+            escalate_after_hours:   0,
+            escalation_webhook_url: String::new(),
+            webhook:          WebhookOptions::default(),
+            escalation_webhook: WebhookOptions::default(),
+            channels:         Vec::new(),
+            routes:           Vec::new(),
+            smtp:             SmtpConfig::default(),
+            reports:          Vec::new(),
+            batch_window:     String::new(),
+            max_per_hour:     0,
+            digest:           DigestConfig::default(),
+        }
+    }
+}
+
+impl NotificationsConfig {
+    /// The configured channels, or those implied by the single-webhook settings.
+    /// Channel-level `batch_window` and `max_per_hour` are filled in from the
+    /// notification-wide defaults.
+    pub fn effective_channels(&self) -> Vec<ChannelConfig> {
+        let mut out = if self.channels.is_empty() { self.implicit_channels() } else { self.channels.clone() };
+        for ch in &mut out {
+            ch.batch_window.get_or_insert_with(|| self.batch_window.clone());
+            ch.max_per_hour.get_or_insert(self.max_per_hour);
+        }
+        out
+    }
+
+    fn implicit_channels(&self) -> Vec<ChannelConfig> {
+        let mut out = Vec::new();
+        if !self.webhook_url.is_empty() {
+            let mut ch = ChannelConfig::new("webhook", ChannelKind::Webhook);
+            ch.url     = self.webhook_url.clone();
+            ch.webhook = self.webhook.clone();
+            out.push(ch);
+        }
+        if !self.escalation_webhook_url.is_empty() {
+            let mut ch = ChannelConfig::new("escalation", ChannelKind::Webhook);
+            ch.url     = self.escalation_webhook_url.clone();
+            ch.webhook = self.escalation_webhook.clone();
+            out.push(ch);
+        }
+        if self.notify_send {
+            out.push(ChannelConfig::new("desktop", ChannelKind::Desktop));
+        }
+        out
+    }
+
+    /// The configured routes, or those implied by the single-webhook settings
+    /// (`notify_warning`, escalations to `escalation_webhook_url`).
+    pub fn effective_routes(&self) -> Vec<RouteConfig> {
+        if !self.routes.is_empty() {
+            return self.routes.clone();
+        }
+        if !self.channels.is_empty() {
+            return vec![RouteConfig {
+                channels: self.channels.iter().map(|c| c.name.clone()).collect(),
+                ..Default::default()
+            }];
+        }
+        let severity = if self.notify_warning { "warn" } else { "crit" }.to_string();
+        let mut out = Vec::new();
+        if !self.webhook_url.is_empty() {
+            let mut events = vec!["fired".to_string(), "reminder".to_string()];
+            if self.escalation_webhook_url.is_empty() { events.push("escalated".into()); }
+            out.push(RouteConfig { channels: vec!["webhook".into()], severity: severity.clone(), events, ..Default::default() });
+        }
+        if !self.escalation_webhook_url.is_empty() {
+            out.push(RouteConfig {
+                channels: vec!["escalation".into()],
+                severity,
+                events:   vec!["escalated".into()],
+                ..Default::default()
+            });
+        }
+        if self.notify_send {
+            out.push(RouteConfig {
+                channels: vec!["desktop".into()],
+                severity: "info".into(),
+                events:   vec!["fired".into(), "escalated".into()],
+                ..Default::default()
+            });
+        }
+        out
+    }
+}
+
+impl Default for WebhookOptions {
+    fn default() -> Self {
+        Self {
+            format:       String::new(),
+            template:     String::new(),
+            routing_key:  String::new(),
+            headers:      HashMap::new(),
+            bearer_token: String::new(),
+            hmac_secret:  String::new(),
+            timeout_secs: 10,
+            max_attempts: 8,
         }
     }
 }
@@ -26,6 +26,7 @@ pub enum Action {
     SmartTest,     // x: schedule SMART short self-test on selected device
     FilterDevices, // f: cycle device type filter (All/NVMe/SSD/HDD)
     AckAlerts,     // a: acknowledge all current alerts
+    SilenceAlert,  // z: silence selected alert for 1h (again = lift)
     SaveBaseline,  // B: save current SMART data as baseline for selected device
     ToggleDesc,    // D: toggle SMART attribute descriptions in detail view
     ShowConfig,    // C: open config viewer overlay
@@ -77,6 +78,7 @@ pub fn handle_key(key: KeyEvent) -> Action {
         (KeyCode::Char('r'), _) => Action::SmartRefresh,
         (KeyCode::Char('f'), _) => Action::FilterDevices,
         (KeyCode::Char('a'), _) => Action::AckAlerts,
+        (KeyCode::Char('z'), _) => Action::SilenceAlert,
         (KeyCode::Char('B'), _) => Action::SaveBaseline,
         (KeyCode::Char('D'), _) => Action::ToggleDesc,
         (KeyCode::Char('C'), _) => Action::ShowConfig,
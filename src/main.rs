@@ -51,9 +51,30 @@ struct Cli {
     #[arg(long)]
     daemon: bool,
 
-    /// One-shot health check: exit 0=OK, 1=WARNING, 2=CRITICAL (nagios/cron compatible)
-    #[arg(long)]
-    check: bool,
+    /// Serve Prometheus metrics on ADDR (e.g. 127.0.0.1:9633) at /metrics — used with --daemon
+    #[arg(long, value_name = "ADDR", requires = "daemon")]
+    listen: Option<String>,
+
+    /// Write Prometheus metrics to PATH for node_exporter's textfile collector and exit (every tick with --daemon)
+    #[arg(long, value_name = "PATH")]
+    textfile: Option<String>,
+
+    /// Print one metrics snapshot in FORMAT (influx, graphite, prometheus) and exit, e.g. for telegraf's exec input
+    #[arg(long, value_name = "FORMAT")]
+    format: Option<String>,
+
+    /// Print Zabbix low-level discovery JSON for KIND (devices, filesystems, arrays) and exit
+    #[arg(long, value_name = "KIND")]
+    zabbix_discovery: Option<String>,
+
+    /// Print the value of one Zabbix item KEY (e.g. smart.attr[sda,5], fs.pct[/var]) and exit
+    #[arg(long, value_name = "KEY")]
+    zabbix_get: Option<String>,
+
+    /// One-shot health check with perfdata: exit 0=OK, 1=WARNING, 2=CRITICAL, 3=UNKNOWN (nagios/cron compatible).
+    /// SCOPE limits it to smart, fs[=MOUNT], raid or device=NAME
+    #[arg(long, value_name = "SCOPE", num_args = 0..=1, default_missing_value = "all")]
+    check: Option<String>,
 
     /// Print recent alert log entries and exit
     #[arg(long)]
@@ -63,6 +84,34 @@ struct Cli {
     #[arg(long, default_value_t = 50)]
     last: usize,
 
+    /// Silence alerts matching MATCHER (e.g. "device=sda,severity=warn") and exit
+    #[arg(long, value_name = "MATCHER")]
+    silence: Option<String>,
+
+    /// Silence length for --silence (e.g. 30m, 2h, 1d)
+    #[arg(long, value_name = "DURATION", default_value = "2h", requires = "silence")]
+    duration: String,
+
+    /// Silence start as local "YYYY-MM-DD HH:MM" (default: now) — used with --silence
+    #[arg(long, value_name = "TIME", requires = "silence")]
+    starts: Option<String>,
+
+    /// Reason recorded with --silence
+    #[arg(long, value_name = "TEXT", default_value = "", requires = "silence")]
+    comment: String,
+
+    /// Author recorded with --silence (default: $SUDO_USER or $USER)
+    #[arg(long, value_name = "NAME", requires = "silence")]
+    author: Option<String>,
+
+    /// Remove silence ID and exit
+    #[arg(long, value_name = "ID")]
+    unsilence: Option<u64>,
+
+    /// List silences and maintenance windows, then exit
+    #[arg(long)]
+    silences: bool,
+
     /// Print config file path and current values, then exit
     #[arg(long)]
     config: bool,
@@ -155,9 +204,21 @@ struct Cli {
     #[arg(long)]
     print_service: bool,
 
-    /// Send a test notification to the configured webhook URL and exit
+    /// Send a test notification to CHANNEL (default: the first webhook channel) and exit
+    #[arg(long, value_name = "CHANNEL", num_args = 0..=1, default_missing_value = "")]
+    test_webhook: Option<String>,
+
+    /// Mail scheduled report NAME now (default: the first in [[notifications.reports]]) and exit
+    #[arg(long, value_name = "NAME", num_args = 0..=1, default_missing_value = "")]
+    send_report: Option<String>,
+
+    /// Print the daily digest (alerts in the last 24 hours, per device) and exit
     #[arg(long)]
-    test_webhook: bool,
+    digest: bool,
+
+    /// Send the daily digest to the [notifications.digest] channels now and exit
+    #[arg(long)]
+    send_digest: bool,
 
     /// View or set I/O scheduler: --io-sched (all), DEVICE (one), DEVICE=SCHEDULER (set)
     #[arg(long, value_name = "DEVICE[=SCHEDULER]", num_args = 0..=1, default_missing_value = "ALL")]
@@ -364,12 +425,21 @@ fn main() -> Result<()> {
     if cli.report_html {
         return run_report_html(cli.output.as_deref());
     }
-    if cli.check {
-        return run_check(!cli.no_smart);
+    if let Some(scope) = &cli.check {
+        return run_check(scope, !cli.no_smart);
     }
     if cli.alerts {
         return run_alerts(cli.last, cli.since.as_deref());
     }
+    if let Some(matcher) = &cli.silence {
+        return run_silence(matcher, &cli.duration, cli.starts.as_deref(), &cli.comment, cli.author.as_deref());
+    }
+    if let Some(id) = cli.unsilence {
+        return run_unsilence(id);
+    }
+    if cli.silences {
+        return run_silences();
+    }
     if cli.top_io {
         return run_top_io(cli.count);
     }
@@ -398,8 +468,14 @@ fn main() -> Result<()> {
     if cli.print_service {
         return run_print_service();
     }
-    if cli.test_webhook {
-        return run_test_webhook();
+    if let Some(channel) = &cli.test_webhook {
+        return run_test_webhook(channel);
+    }
+    if let Some(name) = &cli.send_report {
+        return run_send_report(name);
+    }
+    if cli.digest || cli.send_digest {
+        return run_digest(cli.send_digest);
     }
     if let Some(arg) = &cli.io_sched {
         let target = if arg == "ALL" { None } else { Some(arg.as_str()) };
@@ -567,7 +643,19 @@ fn main() -> Result<()> {
         return run_edit_config();
     }
     if cli.daemon {
-        return run_daemon(cli.interval, !cli.no_smart);
+        return run_daemon(cli.interval, !cli.no_smart, cli.listen.as_deref(), cli.textfile.as_deref());
+    }
+    if let Some(path) = &cli.textfile {
+        return run_textfile(path, !cli.no_smart);
+    }
+    if let Some(format) = &cli.format {
+        return run_format(format, !cli.no_smart);
+    }
+    if let Some(kind) = &cli.zabbix_discovery {
+        return run_zabbix_discovery(kind);
+    }
+    if let Some(key) = &cli.zabbix_get {
+        return run_zabbix_get(key);
     }
 
     let initial_theme = ui::theme::ThemeVariant::from_name(&cli.theme);
@@ -859,8 +947,41 @@ fn run_print_config() -> Result<()> {
     let fc = if t.fill_days_crit > 0.0 { format!("{:.0}d", t.fill_days_crit) } else { "disabled".into() };
     println!("  fill_days_warn        = {}", fw);
     println!("  fill_days_crit        = {}", fc);
+    let st = if t.selftest_max_age_days > 0.0 { format!("{:.0}d", t.selftest_max_age_days) } else { "disabled".into() };
+    println!("  selftest_max_age_days = {}", st);
+    println!("  nfs_rtt_warn_ms       = {}ms", t.nfs_rtt_warn_ms);
+    println!("  nfs_rtt_crit_ms       = {}ms", t.nfs_rtt_crit_ms);
     println!("  cooldown_hours        = {}", cfg.alerts.cooldown_hours);
+    println!("  kernel_window         = {}", cfg.alerts.kernel_window);
+    println!("  nfs_stale_timeout     = {}", cfg.alerts.nfs_stale_timeout);
+    println!("  log_retention         = {}", match cfg.alerts.log_retention_secs() {
+        Some(_) => cfg.alerts.log_retention.clone(),
+        None    => "(keep everything)".into(),
+    });
     println!("");
+    let psi = &cfg.alerts.psi;
+    let pct = |v: f64| if v > 0.0 { format!("{}%", v) } else { "disabled".into() };
+    println!("[alerts.psi]  (I/O pressure, warn / crit)");
+    println!("  some avg10            = {} / {}", pct(psi.some_avg10_warn), pct(psi.some_avg10_crit));
+    println!("  some avg60            = {} / {}", pct(psi.some_avg60_warn), pct(psi.some_avg60_crit));
+    println!("  full avg10            = {} / {}", pct(psi.full_avg10_warn), pct(psi.full_avg10_crit));
+    println!("  full avg60            = {} / {}", pct(psi.full_avg60_warn), pct(psi.full_avg60_crit));
+    println!();
+    print_overrides(&cfg);
+    println!("[alerts.inhibit]  (storage-stack correlation {})", if cfg.alerts.correlate { "on" } else { "off" });
+    if cfg.alerts.correlate {
+        for (src, dst) in util::correlate::BUILTIN_INHIBIT {
+            println!("  built-in  rule={} mutes rule={}  (same stack)", src, dst);
+        }
+    }
+    for r in &cfg.alerts.inhibit {
+        let scope = if r.same_stack { "same stack" } else { "anywhere" };
+        let bad = if util::silence::Matcher::parse(&r.source).is_err() || util::silence::Matcher::parse(&r.target).is_err() {
+            "  ✗ invalid matcher"
+        } else { "" };
+        println!("  {} mutes {}  ({}){}", r.source, r.target, scope, bad);
+    }
+    println!();
     if cfg.alerts.smart_rules.is_empty() {
         println!("[alerts.smart_rules]  (none configured — all disabled)");
     } else {
@@ -871,6 +992,65 @@ fn run_print_config() -> Result<()> {
         }
     }
     println!("");
+    println!("[alerts.rate_rules]  ({} rules)", cfg.alerts.rate_rules.len());
+    for r in &cfg.alerts.rate_rules {
+        let attr = if r.attr == util::smart_history::NVME_MEDIA_ERRORS {
+            "nvme media".to_string()
+        } else {
+            format!("attr {:>3}", r.attr)
+        };
+        let bad = if r.window_secs().is_none() { "  ✗ invalid window" } else { "" };
+        println!("  {}  +{} within {}  [{}]{}", attr, r.delta, r.window, r.severity, bad);
+    }
+    println!();
+    if cfg.alerts.rules.is_empty() {
+        println!("[alerts.rules]  (no expression rules)");
+    } else {
+        println!("[alerts.rules]  ({} rules)", cfg.alerts.rules.len());
+        for r in &cfg.alerts.rules {
+            let status = match util::expr::parse(&r.expr) {
+                Ok(_)  => String::new(),
+                Err(e) => format!("  ✗ parse error: {}", e),
+            };
+            println!("  {:<20} [{}]  {}{}", r.id, r.severity, r.expr, status);
+            if r.hold_for.is_some() || r.clear.is_some() {
+                let clear_status = match r.clear.as_deref().map(util::expr::parse) {
+                    Some(Err(e)) => format!("  ✗ parse error: {}", e),
+                    _            => String::new(),
+                };
+                println!("  {:<20} for {}  clear {}{}", "",
+                    r.hold_for.as_deref().unwrap_or("0s"),
+                    r.clear.as_deref().unwrap_or("(when expr is false)"), clear_status);
+            }
+        }
+    }
+    println!();
+    if !cfg.alerts.maintenance.is_empty() {
+        println!("[alerts.maintenance]");
+        for w in &cfg.alerts.maintenance {
+            let days = if w.days.is_empty() { "daily".to_string() } else { w.days.join(",") };
+            let matcher = if w.matcher.is_empty() { "(all alerts)" } else { w.matcher.as_str() };
+            let bad = w.problem().map(|p| format!("  ✗ {}", p)).unwrap_or_default();
+            println!("  {:<20} {} {} for {}  {}{}", w.name, days, w.start, w.duration, matcher, bad);
+        }
+        println!();
+    }
+    if !cfg.alerts.conditions.is_empty() {
+        println!("[alerts.conditions]");
+        let mut keys: Vec<&String> = cfg.alerts.conditions.keys().collect();
+        keys.sort();
+        for k in keys {
+            let p = &cfg.alerts.conditions[k];
+            let fmt = |v: Option<f64>| v.map(|v| format!("{}", v)).unwrap_or_else(|| "-".into());
+            let bad = match p.hold_for.as_deref() {
+                Some(d) if util::human::parse_duration(d).is_none() => "  ✗ invalid duration",
+                _ => "",
+            };
+            println!("  {:<20} for {:<6} clear_warn {:<6} clear_crit {}{}",
+                k, p.hold_for.as_deref().unwrap_or("0s"), fmt(p.clear_warn), fmt(p.clear_crit), bad);
+        }
+        println!();
+    }
     println!("[devices]");
     println!("  exclude = {:?}", cfg.devices.exclude);
     if cfg.devices.aliases.is_empty() {
@@ -881,32 +1061,233 @@ fn run_print_config() -> Result<()> {
         }
     }
     println!("");
+    println!("[daemon]");
+    println!("  log             = {}", cfg.daemon.log.join(", "));
+    println!("  syslog_facility = {}", cfg.daemon.syslog_facility);
+    println!();
+    println!("[mqtt]");
+    if cfg.mqtt.host.is_empty() {
+        println!("  host = (not set; MQTT publishing off)");
+    } else {
+        let m = &cfg.mqtt;
+        println!("  broker    = {}:{}{}", m.host, m.port, if m.tls { " (TLS)" } else { "" });
+        if !m.username.is_empty() {
+            println!("  username  = {} (password {})", m.username, if m.password.is_empty() { "not set" } else { "set" });
+        }
+        println!("  topics    = {}/…", util::mqtt::topic_prefix(m));
+        println!("  interval  = {}  qos = {}  retain = {}", m.interval, m.qos.min(1), m.retain);
+        if util::human::parse_duration(&m.interval).is_none() {
+            println!("  ! interval {:?} is not a duration; using 30s", m.interval);
+        }
+        let disc = if m.discovery { format!("{}/…", m.discovery_prefix) } else { "off".to_string() };
+        println!("  Home Assistant discovery = {}", disc);
+    }
+    println!();
+    println!("[influxdb]");
+    let ix = &cfg.influxdb;
+    if ix.url.is_empty() && ix.udp.is_empty() {
+        println!("  url = (not set; InfluxDB push off)");
+    } else {
+        if ix.udp.is_empty() {
+            println!("  url          = {}", ix.url);
+        } else {
+            println!("  udp          = {}", ix.udp);
+        }
+        if !ix.token.is_empty() {
+            println!("  token        = (set)");
+        } else if !ix.username.is_empty() {
+            println!("  username     = {} (password {})", ix.username, if ix.password.is_empty() { "not set" } else { "set" });
+        }
+        println!("  measurements = {}device, {}filesystem, …", ix.measurement_prefix, ix.measurement_prefix);
+        println!("  device_tags  = {}", ix.device_tags.join(", "));
+        let mut tags: Vec<String> = ix.tags.iter().map(|(k, v)| format!("{}={}", k, v)).collect();
+        tags.sort();
+        if !tags.is_empty() { println!("  tags         = {}", tags.join(", ")); }
+        println!("  interval     = {}", ix.interval);
+        if util::human::parse_duration(&ix.interval).is_none() {
+            println!("  ! interval {:?} is not a duration; using 30s", ix.interval);
+        }
+    }
+    println!();
+    println!("[graphite]");
+    let gr = &cfg.graphite;
+    if gr.host.is_empty() {
+        println!("  host = (not set; Graphite push off)");
+    } else {
+        println!("  server     = {}:{}", gr.host, gr.port);
+        println!("  prefix     = {}", gr.prefix);
+        println!("  device_key = {}", gr.device_key);
+        println!("  interval   = {}", gr.interval);
+        if util::human::parse_duration(&gr.interval).is_none() {
+            println!("  ! interval {:?} is not a duration; using 30s", gr.interval);
+        }
+    }
+    println!();
     println!("[notifications]");
     let webhook = if cfg.notifications.webhook_url.is_empty() { "(not set)" } else { "(configured)" };
     println!("  webhook_url    = {}", webhook);
     println!("  notify_critical = {}", cfg.notifications.notify_critical);
     println!("  notify_warning  = {}", cfg.notifications.notify_warning);
     println!("  notify_send     = {}", cfg.notifications.notify_send);
+    println!("  renotify_hours  = {}", cfg.notifications.renotify_hours);
+    println!("  escalate_after_hours   = {}", cfg.notifications.escalate_after_hours);
+    let esc = if cfg.notifications.escalation_webhook_url.is_empty() { "(webhook_url)" } else { "(configured)" };
+    println!("  escalation_webhook_url = {}", esc);
+    println!("  batch_window    = {}", if cfg.notifications.batch_window.is_empty() { "(none)" } else { &cfg.notifications.batch_window });
+    println!("  max_per_hour    = {}", if cfg.notifications.max_per_hour == 0 { "unlimited".to_string() } else { cfg.notifications.max_per_hour.to_string() });
+    for (name, w) in [("webhook", &cfg.notifications.webhook), ("escalation_webhook", &cfg.notifications.escalation_webhook)] {
+        let template = if w.template.is_empty() { "(default)" } else { "(custom)" };
+        let yes_no = |s: &str| if s.is_empty() { "no" } else { "yes" };
+        println!("[notifications.{}]", name);
+        println!("  template = {}  headers = {}  bearer = {}  hmac = {}", template, w.headers.len(),
+            yes_no(&w.bearer_token), yes_no(&w.hmac_secret));
+        println!("  timeout  = {}s  max_attempts = {}", w.timeout_secs, w.max_attempts);
+    }
+    println!("[notifications.channels]{}", if cfg.notifications.channels.is_empty() { "  (derived from the settings above)" } else { "" });
+    for ch in cfg.notifications.effective_channels() {
+        let target = match ch.kind {
+            config::ChannelKind::Webhook => {
+                let url = if !ch.url.is_empty() { "(url configured)" }
+                    else if ch.webhook.format == "pagerduty" { "(PagerDuty events API)" }
+                    else { "(no url)" };
+                match ch.webhook.format.as_str() {
+                    ""                                           => url.to_string(),
+                    f if util::payload::FORMATS.contains(&f)     => format!("{}  format = {}", url, f),
+                    f                                            => format!("{}  format = {} (unknown; using template)", url, f),
+                }
+            }
+            config::ChannelKind::Exec    => format!("{}  (timeout {}s, max {} at once)", ch.command, ch.webhook.timeout_secs, ch.max_concurrent),
+            config::ChannelKind::Email   => ch.to.join(", "),
+            _                            => String::new(),
+        };
+        let mut limits = Vec::new();
+        if ch.batch_secs() > 0 { limits.push(format!("batch {}", ch.batch_window.as_deref().unwrap_or(""))); }
+        if let Some(n) = ch.max_per_hour.filter(|&n| n > 0) { limits.push(format!("max {}/h", n)); }
+        if !ch.send_resolved { limits.push("no resolves".to_string()); }
+        let limits = if limits.is_empty() { String::new() } else { format!("  [{}]", limits.join(", ")) };
+        println!("{}", format!("  {:<12} {:<8} {}{}", ch.name, ch.kind.label(), target, limits).trim_end());
+    }
+    let smtp = &cfg.notifications.smtp;
+    println!("[notifications.smtp]");
+    println!("  {}:{}  security = {}  auth = {}  from = {}", smtp.host, smtp.port, smtp.security,
+        if smtp.username.is_empty() { "no" } else { "yes" },
+        if smtp.from.is_empty() { "(dtop@hostname)" } else { &smtp.from });
+    if !cfg.notifications.reports.is_empty() {
+        println!("[notifications.reports]");
+        for r in &cfg.notifications.reports {
+            let when = match r.schedule.as_str() {
+                "daily" => "daily".to_string(),
+                _       => format!("weekly on {}", if r.day.is_empty() { "mon" } else { &r.day }),
+            };
+            let next = util::notifier::last_slot(r, chrono::Local::now()).map(|_| "").unwrap_or("  (invalid schedule)");
+            println!("  {:<12} {} at {}  {} → {}{}", r.name, when, if r.at.is_empty() { "08:00" } else { &r.at },
+                if r.format.is_empty() { "html" } else { &r.format }, r.channels.join(", "), next);
+        }
+    }
+    let digest = &cfg.notifications.digest;
+    if !digest.channels.is_empty() {
+        let valid = util::notifier::digest_slot(digest, chrono::Local::now()).map(|_| "").unwrap_or("  (invalid time)");
+        println!("[notifications.digest]");
+        println!("  daily at {} → {}{}", if digest.at.is_empty() { "08:00" } else { &digest.at }, digest.channels.join(", "), valid);
+    }
+    println!("[notifications.routes]");
+    let channels = cfg.notifications.effective_channels();
+    for r in cfg.notifications.effective_routes() {
+        let mut parts = vec![format!("severity ≥ {}", if r.severity.is_empty() { "warn" } else { &r.severity })];
+        for (k, v) in [("events", &r.events), ("devices", &r.devices), ("mounts", &r.mounts), ("rules", &r.rules), ("days", &r.days)] {
+            if !v.is_empty() { parts.push(format!("{} {}", k, v.join(","))); }
+        }
+        if !r.hours.is_empty() { parts.push(format!("hours {}", r.hours)); }
+        println!("  → {:<20} {}", r.channels.join(", "), parts.join("  "));
+        for p in r.problems(&channels) {
+            println!("    ✗ {}", p);
+        }
+    }
     Ok(())
 }
 
+/// `[alerts.overrides]` blocks plus the effective thresholds for every device and mount.
+fn print_overrides(cfg: &config::Config) {
+    use models::device::BlockDevice;
+
+    let o = &cfg.alerts.overrides;
+    let describe = |ov: &config::ThresholdOverride| -> String {
+        match serde_json::to_value(ov) {
+            Ok(serde_json::Value::Object(map)) => map.iter()
+                .filter(|(_, v)| !v.is_null())
+                .map(|(k, v)| format!("{}={}", k, v))
+                .collect::<Vec<_>>()
+                .join(" "),
+            _ => String::new(),
+        }
+    };
+    let n = o.mounts.len() + o.device.len() + o.serial.len();
+    if n == 0 {
+        println!("[alerts.overrides]  (none — global thresholds everywhere)");
+        println!();
+        return;
+    }
+    println!("[alerts.overrides]  ({} blocks)", n);
+    let mut blocks: Vec<(String, &config::ThresholdOverride)> = Vec::new();
+    blocks.extend(o.mounts.iter().map(|(k, v)| (format!("\"{}\"", k), v)));
+    blocks.extend(o.device.iter().map(|(k, v)| (format!("device.\"{}\"", k), v)));
+    blocks.extend(o.serial.iter().map(|(k, v)| (format!("serial.\"{}\"", k), v)));
+    blocks.sort_by(|a, b| a.0.cmp(&b.0));
+    for (label, ov) in &blocks {
+        println!("  {:<24} {}", label, describe(ov));
+    }
+    println!();
+
+    let disabled = |eff: &config::EffectiveThresholds| {
+        if eff.disabled.is_empty() { String::new() } else { format!("  disabled: {}", eff.disabled.join(",")) }
+    };
+    let from = |eff: &config::EffectiveThresholds| {
+        if eff.sources.is_empty() { "(global)".to_string() } else { eff.sources.join(" + ") }
+    };
+
+    println!("Effective device thresholds:");
+    for lb in collectors::lsblk::run_lsblk().unwrap_or_default() {
+        let mut dev = BlockDevice::new(lb.name.clone());
+        dev.serial = lb.serial.clone();
+        dev.rotational = lb.rotational;
+        dev.transport = lb.transport.clone();
+        dev.alias = cfg.devices.aliases.get(&lb.name).cloned();
+        dev.infer_type();
+        let eff = alerts::device_thresholds(&dev, &cfg.alerts);
+        let t = &eff.thresholds;
+        let (tw, tc) = if dev.rotational {
+            (t.temperature_warn_hdd, t.temperature_crit_hdd)
+        } else {
+            (t.temperature_warn_ssd, t.temperature_crit_ssd)
+        };
+        println!("  {:<10} {:<5} temp {}/{}°C  util {}%  latency {}/{}ms  {}{}",
+            dev.name, dev.dev_type.label().trim(), tw, tc, t.io_util_warn_pct,
+            t.latency_warn_ms, t.latency_crit_ms, from(&eff), disabled(&eff));
+    }
+    println!();
+
+    println!("Effective filesystem thresholds:");
+    for fs in collectors::filesystem::read_filesystems().unwrap_or_default() {
+        let eff = cfg.alerts.for_mount(&fs.mount);
+        let t = &eff.thresholds;
+        println!("  {:<24} use {}/{}%  inodes {}/{}%  fill {}/{}d  {}{}",
+            fs.mount, t.filesystem_warn_pct, t.filesystem_crit_pct, t.inode_warn_pct,
+            t.inode_crit_pct, t.fill_days_warn, t.fill_days_crit, from(&eff), disabled(&eff));
+    }
+    println!();
+}
+
 fn run_alerts(n: usize, since: Option<&str>) -> Result<()> {
     use util::alert_log;
-    use alerts::Severity;
-    use chrono::NaiveDateTime;
+    use util::human::fmt_duration_short;
 
     let entries = if let Some(since_str) = since {
         let duration = parse_since(since_str).ok_or_else(|| {
             anyhow::anyhow!("Invalid --since value '{}'. Use format like 24h, 7d, or 30m.", since_str)
         })?;
-        let cutoff = chrono::Local::now().naive_local() - duration;
-        let mut all = alert_log::load_all();  // newest-first
-        all.retain(|(ts, _)| {
-            NaiveDateTime::parse_from_str(ts, "%Y-%m-%d %H:%M:%S")
-                .map(|t| t >= cutoff)
-                .unwrap_or(false)
-        });
-        all.reverse();  // oldest-first for display
+        let cutoff = chrono::Local::now() - duration;
+        let mut all = alert_log::load_events();  // oldest-first
+        all.retain(|ev| ev.time().map(|t| t >= cutoff).unwrap_or(false));
         if all.is_empty() {
             println!("No alerts in the last {}.", since_str);
             return Ok(());
@@ -921,13 +1302,85 @@ fn run_alerts(n: usize, since: Option<&str>) -> Result<()> {
         entries
     };
 
-    for (ts, alert) in &entries {
-        let sev = match alert.severity {
-            Severity::Critical => "CRIT",
-            Severity::Warning  => "WARN",
-            Severity::Info     => "INFO",
-        };
-        println!("{} [{}] {}", ts, sev, alert.message);
+    for ev in &entries {
+        let alert = ev.alert();
+        let lasted = ev.duration_secs
+            .map(|d| format!("  (lasted {})", fmt_duration_short(d.max(0) as u64)))
+            .unwrap_or_default();
+        println!("{} [{}] {:<9} {}{}{}{}",
+            ev.ts_display(), ev.severity, ev.event.label(), alert.prefix(), alert.message, lasted, ev.relation() + &ev.hook_note());
+    }
+    Ok(())
+}
+
+fn run_silence(
+    matcher: &str,
+    duration: &str,
+    starts: Option<&str>,
+    comment: &str,
+    author: Option<&str>,
+) -> Result<()> {
+    use chrono::{Local, NaiveDateTime, TimeZone};
+    use util::silence::{self, Matcher};
+
+    let matcher = Matcher::parse(matcher).map_err(|e| anyhow::anyhow!("Invalid matcher: {}", e))?;
+    let secs = util::human::parse_duration(duration).ok_or_else(|| {
+        anyhow::anyhow!("Invalid --duration value '{}'. Use format like 30m, 2h, or 1d.", duration)
+    })?;
+    let start = match starts {
+        None    => Local::now().timestamp(),
+        Some(s) => NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M")
+            .ok()
+            .and_then(|t| Local.from_local_datetime(&t).earliest())
+            .map(|t| t.timestamp())
+            .ok_or_else(|| anyhow::anyhow!("Invalid --starts value '{}'. Use \"YYYY-MM-DD HH:MM\".", s))?,
+    };
+    let author = author.map(str::to_string).unwrap_or_else(silence::current_user);
+    let s = silence::add(matcher, start, start + secs as i64, &author, comment);
+    println!("Silence #{} created: {}  until {}",
+        s.id, s.matcher, util::silence::fmt_time(s.ends_at));
+    Ok(())
+}
+
+fn run_unsilence(id: u64) -> Result<()> {
+    if util::silence::remove(id) {
+        println!("Silence #{} removed.", id);
+        Ok(())
+    } else {
+        anyhow::bail!("No silence with ID {}. List them with: dtop --silences", id)
+    }
+}
+
+fn run_silences() -> Result<()> {
+    use util::silence::fmt_time;
+
+    let cfg = config::Config::load();
+    let now = chrono::Local::now();
+    let mut silences = util::silence::load();
+    silences.retain(|s| s.ends_at > now.timestamp());
+    silences.sort_by_key(|s| s.starts_at);
+
+    if silences.is_empty() {
+        println!("No active or scheduled silences.");
+    } else {
+        println!("{:<5} {:<8} {:<17} {:<17} {:<10} {:<30} COMMENT", "ID", "STATE", "STARTS", "ENDS", "AUTHOR", "MATCHER");
+        for s in &silences {
+            let state = if s.is_active(now.timestamp()) { "active" } else { "pending" };
+            println!("{:<5} {:<8} {:<17} {:<17} {:<10} {:<30} {}",
+                s.id, state, fmt_time(s.starts_at), fmt_time(s.ends_at),
+                s.author, s.matcher.to_string(), s.comment);
+        }
+    }
+
+    if !cfg.alerts.maintenance.is_empty() {
+        println!();
+        println!("Maintenance windows (dtop.toml):");
+        for w in &cfg.alerts.maintenance {
+            let days = if w.days.is_empty() { "daily".to_string() } else { w.days.join(",") };
+            let state = if w.is_active(now) { "  [active]" } else { "" };
+            let matcher = if w.matcher.is_empty() { "(all alerts)" } else { w.matcher.as_str() };
+            println!("  {:<20} {} {} for {}  {}{}", w.name, days, w.start, w.duration, matcher, state);
+        }
     }
     Ok(())
 }
@@ -1313,118 +1766,178 @@ fn run_print_service() -> Result<()> {
     Ok(())
 }
 
-fn run_test_webhook() -> Result<()> {
+fn run_test_webhook(channel: &str) -> Result<()> {
+    use config::ChannelKind;
+    use util::delivery::{self, Delivery, DeliveryStatus};
+
     let cfg = config::Config::load();
-    if cfg.notifications.webhook_url.is_empty() {
-        eprintln!(
-            "No webhook URL configured.\n\
-             Set notifications.webhook_url in ~/.config/dtop/dtop.toml.\n\
-             Use 'dtop --edit-config' to open the config file."
-        );
+    let channels = cfg.notifications.effective_channels();
+    let found = if channel.is_empty() {
+        channels.iter().find(|c| c.kind == ChannelKind::Webhook)
+    } else {
+        channels.iter().find(|c| c.name == channel)
+    };
+    let Some(ch) = found else {
+        if channel.is_empty() {
+            eprintln!(
+                "No webhook channel configured.\n\
+                 Set notifications.webhook_url or add a [[notifications.channels]] entry in ~/.config/dtop/dtop.toml.\n\
+                 Use 'dtop --edit-config' to open the config file."
+            );
+        } else {
+            eprintln!("No notification channel named '{}'.", channel);
+        }
+        if !channels.is_empty() {
+            let names: Vec<String> = channels.iter().map(|c| format!("{} ({})", c.name, c.kind.label())).collect();
+            eprintln!("Configured channels: {}", names.join(", "));
+        }
         std::process::exit(1);
-    }
-
-    let url = &cfg.notifications.webhook_url;
-    println!("Sending test notification to webhook…");
-    println!("URL: {}", url);
-
-    let hostname = std::process::Command::new("hostname")
-        .output()
-        .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
-        .unwrap_or_else(|_| "unknown".to_string());
+    };
 
-    let payload = format!(
-        "{{\"text\":\"[dtop] Test notification from {} — webhook integration is working correctly.\"}}",
-        hostname
-    );
+    println!("Sending test notification to channel '{}' ({})…", ch.name, ch.kind.label());
+    let test = alerts::Alert {
+        severity: alerts::Severity::Info,
+        device:   None,
+        mount:    None,
+        rule:     "test".into(),
+        value:    None,
+        message:  format!("[dtop] Test notification from {} — {} integration is working correctly.",
+            util::webhook::hostname(), ch.kind.label()),
+    };
 
-    let out = std::process::Command::new("curl")
-        .args([
-            "-s", "-i", "--max-time", "10",
-            "-X", "POST",
-            "-H", "Content-Type: application/json",
-            "-d", &payload,
-            url,
-        ])
-        .output()
-        .map_err(|e| anyhow::anyhow!("Failed to run curl: {}\nIs curl installed?", e))?;
+    if ch.kind == ChannelKind::Email {
+        let smtp = &cfg.notifications.smtp;
+        println!("SMTP: {}:{} ({})  To: {}", smtp.host, smtp.port, smtp.security, ch.to.join(", "));
+        let subject = util::notifier::subject(&[&test], "test", &util::webhook::hostname());
+        let msg = util::notifier::message(ch, &cfg.notifications, subject, "text/plain", format!("{}\n", test.message));
+        if let Err(e) = util::notifier::email(ch, &cfg.notifications, &msg) {
+            eprintln!("✗ Email delivery failed: {:#}", e);
+            std::process::exit(1);
+        }
+        println!("✓ Email accepted by the relay.");
+        return Ok(());
+    }
+    if ch.kind != ChannelKind::Webhook {
+        let sent = util::notifier::send(ch, &cfg.notifications, &[&test], "test", &Default::default(), &[], &Default::default());
+        for helper in sent {
+            let _ = helper.join();
+        }
+        if ch.kind == ChannelKind::Exec {
+            println!("✓ Command finished; its exit status and output are in the alert log (dtop --alerts).");
+        } else {
+            println!("✓ Sent (delivery is not confirmed for {} channels).", ch.kind.label());
+        }
+        return Ok(());
+    }
 
-    let response = String::from_utf8_lossy(&out.stdout);
-    let status_line = response.lines().next().unwrap_or("(no response)");
-    println!("Response: {}", status_line.trim());
+    // Formats with native resolve semantics get a resolve too, so no incident stays open
+    let mut events = vec!["test"];
+    if util::payload::resolves(&ch.webhook.format) { events.push("resolved"); }
+    if !ch.webhook.format.is_empty() { println!("Format: {}", ch.webhook.format); }
+    for event in events {
+        for req in util::payload::build(&ch.url, &ch.webhook, &[&test], event, &Default::default(), &Default::default()) {
+            println!("URL: {}{}", req.url, if event == "test" { String::new() } else { format!("  ({})", event) });
+            let mut d = Delivery::new(&ch.name, &req.url, &ch.webhook, req.body);
+            d.attempts = 1;
+            let result = d.send();
+            let status = match &result {
+                Ok(r) if r.is_success() => DeliveryStatus::Delivered,
+                _                       => DeliveryStatus::Failed,
+            };
+            delivery::record(&d, status, &result);
 
-    // HTTP 2xx = success
-    let ok = status_line.contains(" 2");
-    if ok {
-        println!("✓ Webhook delivered successfully.");
-    } else {
-        eprintln!("✗ Webhook delivery may have failed.");
-        let body: String = response.lines()
-            .skip_while(|l| !l.is_empty())
-            .skip(1)
-            .collect::<Vec<_>>()
-            .join("\n");
-        if !body.trim().is_empty() {
-            eprintln!("Body: {}", body.trim());
+            match result {
+                Ok(r) if r.is_success() => {
+                    println!("Response: HTTP {}", r.status);
+                    println!("✓ Webhook delivered successfully.");
+                }
+                Ok(r) => {
+                    println!("Response: HTTP {}", r.status);
+                    eprintln!("✗ Webhook delivery failed.");
+                    if !r.body.is_empty() {
+                        eprintln!("Body: {}", r.body);
+                    }
+                    std::process::exit(1);
+                }
+                Err(e) => {
+                    eprintln!("✗ Webhook delivery failed: {:#}", e);
+                    std::process::exit(1);
+                }
+            }
         }
-        std::process::exit(1);
     }
     Ok(())
 }
 
-/// One entry from a SMART self-test log (ATA or NVMe).
-struct SelfTestEntry {
-    test_type: String,
-    status:    String,
-    hours:     u64,
-    passed:    bool,
-}
-
-/// Run `smartctl --json=c -a /dev/{name}` and parse the self-test log table.
-/// Returns ATA or NVMe entries (whichever the drive reports), newest first.
-fn fetch_selftest_log(name: &str) -> Vec<SelfTestEntry> {
-    use serde_json::Value;
-
-    let out = match std::process::Command::new("smartctl")
-        .args(["--json=c", "-a", &format!("/dev/{}", name)])
-        .output()
-    {
-        Ok(o)  => o,
-        Err(_) => return vec![],
-    };
+fn run_send_report(name: &str) -> Result<()> {
+    use util::report;
 
-    let v: Value = match serde_json::from_slice(&out.stdout) {
-        Ok(v)  => v,
-        Err(_) => return vec![],
+    let cfg = config::Config::load();
+    let reports = &cfg.notifications.reports;
+    let found = if name.is_empty() { reports.first() } else { reports.iter().find(|r| r.name == name) };
+    let Some(r) = found else {
+        if reports.is_empty() {
+            eprintln!("No scheduled reports configured. Add a [[notifications.reports]] entry in ~/.config/dtop/dtop.toml.");
+        } else {
+            let names: Vec<&str> = reports.iter().map(|r| r.name.as_str()).collect();
+            eprintln!("No report named '{}'. Configured reports: {}", name, names.join(", "));
+        }
+        std::process::exit(1);
     };
 
-    let mut entries: Vec<SelfTestEntry> = Vec::new();
+    let (devices, filesystems) = report::collect_snapshot();
+    let raids = collectors::mdraid::read_mdstat();
+    let pools = collectors::zfs::read_zpools();
+    let mut all_alerts = alerts::evaluate(&devices, &filesystems, &cfg.alerts);
+    all_alerts.extend(alerts::evaluate_volumes(&raids, &pools));
+    all_alerts.sort_by(|a, b| b.severity.cmp(&a.severity));
 
-    // ATA drives
-    if let Some(table) = v["ata_smart_self_test_log"]["standard"]["table"].as_array() {
-        for row in table {
-            entries.push(SelfTestEntry {
-                test_type: row["type"]["string"].as_str().unwrap_or("?").to_string(),
-                status:    row["status"]["string"].as_str().unwrap_or("?").to_string(),
-                hours:     row["lifetime_hours"].as_u64().unwrap_or(0),
-                passed:    row["status"]["passed"].as_bool().unwrap_or(false),
-            });
+    let sends = util::notifier::send_report(r, &cfg.notifications, &devices, &filesystems, &all_alerts, &raids, &pools);
+    if sends.is_empty() {
+        eprintln!("Report '{}' has no email channels (channels = {:?}).", r.name, r.channels);
+        std::process::exit(1);
+    }
+    let mut failed = false;
+    for (ch, handle) in sends {
+        match handle.join() {
+            Ok(Ok(()))  => println!("✓ '{}' report mailed via {}.", r.name, ch),
+            Ok(Err(e))  => { eprintln!("✗ Mailing via {} failed: {:#}", ch, e); failed = true; }
+            Err(_)      => { eprintln!("✗ Mailing via {} failed.", ch); failed = true; }
         }
     }
+    if failed { std::process::exit(1); }
+    Ok(())
+}
 
-    // NVMe drives
-    if let Some(table) = v["nvme_self_test_log"]["table"].as_array() {
-        for row in table {
-            entries.push(SelfTestEntry {
-                test_type: row["self_test_code"]["string"].as_str().unwrap_or("?").to_string(),
-                status:    row["self_test_result"]["string"].as_str().unwrap_or("?").to_string(),
-                hours:     row["power_on_hours"].as_u64().unwrap_or(0),
-                passed:    row["self_test_result"]["value"].as_u64() == Some(0),
-            });
-        }
+fn run_digest(send: bool) -> Result<()> {
+    let cfg = config::Config::load();
+    let channels = &cfg.notifications.digest.channels;
+    if send && channels.is_empty() {
+        eprintln!("No digest channels configured. Set [notifications.digest] channels in ~/.config/dtop/dtop.toml.");
+        std::process::exit(1);
     }
+    let (devices, filesystems) = util::report::collect_snapshot();
+    let raids = collectors::mdraid::read_mdstat();
+    let pools = collectors::zfs::read_zpools();
+    let mut active = alerts::evaluate(&devices, &filesystems, &cfg.alerts);
+    active.extend(alerts::evaluate_volumes(&raids, &pools));
+    active.sort_by(|a, b| b.severity.cmp(&a.severity));
 
-    entries
+    let now = chrono::Local::now();
+    let (summary, body) = util::digest::daily(&util::alert_log::load_events(), now - chrono::Duration::hours(24),
+        &active, 0, &util::webhook::hostname());
+    if !send {
+        print!("{}", body);
+        return Ok(());
+    }
+    for handle in util::notifier::send_digest(&cfg.notifications, &summary, &body) {
+        let _ = handle.join();
+    }
+    println!("Digest ({}) sent to {}.", summary, channels.join(", "));
+    if let Some(path) = util::delivery::log_path() {
+        println!("Delivery results: {}", path.display());
+    }
+    Ok(())
 }
 
 fn run_schedule_test(device: &str, long_test: bool, wait: bool) -> Result<()> {
@@ -1434,6 +1947,7 @@ fn run_schedule_test(device: &str, long_test: bool, wait: bool) -> Result<()> {
     let eta       = if long_test { "(may take hours on large HDDs)" } else { "(~2 minutes)" };
 
     println!("Scheduling {} SMART self-test on {} {}…", test_type, dev_path, eta);
+    let before = collectors::smart::poll_device(name).and_then(|s| s.self_tests.first().cloned());
 
     let out = std::process::Command::new("smartctl")
         .args(["-t", test_type, &dev_path])
@@ -1461,50 +1975,47 @@ fn run_schedule_test(device: &str, long_test: bool, wait: bool) -> Result<()> {
     let poll_secs = if long_test { 120u64 } else { 30u64 };
     println!("Polling every {}s (Ctrl-C is safe — the test continues on-device)…", poll_secs);
 
+    let mut seen_running = false;
     loop {
         std::thread::sleep(std::time::Duration::from_secs(poll_secs));
 
-        let poll = match std::process::Command::new("smartctl")
-            .args(["-a", &dev_path])
-            .output()
-        {
-            Ok(o)  => o,
-            Err(e) => { eprintln!("Poll error: {}", e); continue; }
+        let smart = match collectors::smart::poll_device(name) {
+            Some(s) => s,
+            None    => { eprintln!("Poll error: smartctl returned no data"); continue; }
         };
 
-        let text = String::from_utf8_lossy(&poll.stdout);
-
-        if let Some(remaining) = cli_parse_smart_test_remaining(&text) {
+        if let Some(remaining) = smart.self_test_remaining {
+            seen_running = true;
             let done = 100u8.saturating_sub(remaining);
             let now  = chrono::Local::now().format("%H:%M:%S");
             println!("  [{}]  {}% complete  ({}% remaining)", now, done, remaining);
-        } else if text.contains("without error") {
-            println!("✓  Self-test completed successfully.");
-            break;
-        } else if text.contains("FAILED!") || (text.contains("# 1") && text.contains("Failed")) {
-            eprintln!("✗  Self-test FAILED — run 'dtop --device-report {}' for details.", name);
-            std::process::exit(2);
-        } else if text.contains("borted") {
-            println!("⚠  Self-test was aborted.");
-            break;
-        }
-        // else: result is ambiguous (test may not have started yet) — keep polling
-    }
-    Ok(())
-}
-
-/// Extract the "X% of test remaining" value from smartctl -a output.
-fn cli_parse_smart_test_remaining(text: &str) -> Option<u8> {
-    for line in text.lines() {
-        if line.contains("% of test remaining") {
-            for word in line.split_whitespace() {
-                if word.ends_with('%') {
-                    return word.trim_end_matches('%').parse::<u8>().ok();
-                }
+            continue;
+        }
+        // Not running: the newest log entry is our result once it differs from the
+        // entry that was on top before scheduling (or we saw the test run)
+        let Some(latest) = smart.self_tests.first() else { continue };
+        let changed = before.as_ref().is_none_or(|b| {
+            b.hours != latest.hours || b.test_type != latest.test_type || b.status != latest.status
+        });
+        if !seen_running && !changed { continue; }
+        match latest.result {
+            models::smart::SelfTestResult::Passed => {
+                println!("✓  Self-test completed successfully.");
+                break;
             }
+            models::smart::SelfTestResult::Failed => {
+                let lba = latest.failing_lba.map(|l| format!(" at LBA {}", l)).unwrap_or_default();
+                eprintln!("✗  Self-test FAILED{} — run 'dtop --device-report {}' for details.", lba, name);
+                std::process::exit(2);
+            }
+            models::smart::SelfTestResult::Aborted => {
+                println!("⚠  Self-test was aborted ({}).", latest.status);
+                break;
+            }
+            models::smart::SelfTestResult::InProgress => {}
         }
     }
-    None
+    Ok(())
 }
 
 fn run_save_baseline(device: &str) -> Result<()> {
@@ -1923,19 +2434,19 @@ fn run_device_report(device: &str) -> Result<()> {
         }
     }
 
-    // Self-test log (second smartctl call, best-effort)
-    let tests = fetch_selftest_log(name);
+    // Self-test log
+    let tests = dev.smart.as_ref().map(|s| s.self_tests.as_slice()).unwrap_or(&[]);
     if !tests.is_empty() {
         println!("\nSELF-TEST LOG  ({} entr{})", tests.len(), if tests.len() == 1 { "y" } else { "ies" });
-        println!("  {:<2}  {:>6}  {:<22}  {}",
-            "", "Hours", "Result", "Test Type");
-        println!("  {}", "─".repeat(58));
-        for t in &tests {
-            let mark = if t.passed { "✓" } else { "✗" };
+        println!("  {:<2}  {:>6}  {:<22}  {:<18}  {}",
+            "", "Hours", "Result", "Test Type", "Failing LBA");
+        println!("  {}", "─".repeat(70));
+        for t in tests {
             // Truncate long status strings for alignment
-            let status = if t.status.len() > 22 { &t.status[..22] } else { &t.status };
-            println!("  {}   {:>6}  {:<22}  {}",
-                mark, t.hours, status, t.test_type);
+            let status: String = t.status.chars().take(22).collect();
+            let lba = t.failing_lba.map(|l| l.to_string()).unwrap_or_else(|| "-".into());
+            println!("  {}   {:>6}  {:<22}  {:<18}  {}",
+                t.result.mark(), t.hours, status, t.test_type, lba);
         }
     }
 
@@ -1943,50 +2454,69 @@ fn run_device_report(device: &str) -> Result<()> {
     Ok(())
 }
 
-fn run_check(smart_enabled: bool) -> Result<()> {
-    use collectors::{filesystem, smart as smart_collector};
-    use models::device::BlockDevice;
+fn run_check(scope: &str, smart_enabled: bool) -> Result<()> {
     use alerts::Severity;
+    use util::nagios::Scope;
 
+    // Nagios reads any exit code above 2 as UNKNOWN; a bad scope must not look like a WARNING
+    let unknown = |e: anyhow::Error| -> ! {
+        println!("UNKNOWN — {:#}", e);
+        std::process::exit(3);
+    };
+    let scope = Scope::parse(scope).unwrap_or_else(|e| unknown(e));
     let cfg = config::Config::load();
-    let lsblk_devs = collectors::lsblk::run_lsblk().unwrap_or_default();
-    let raw_stats  = collectors::diskstats::read_diskstats().unwrap_or_default();
-    let fs_list    = filesystem::read_filesystems().unwrap_or_default();
-
-    let devices: Vec<BlockDevice> = lsblk_devs.iter()
-        .filter(|lb| !cfg.devices.exclude.iter().any(|pat| {
-            if let Some(p) = pat.strip_suffix('*') { lb.name.starts_with(p) }
-            else { pat == &lb.name }
-        }))
-        .filter(|lb| raw_stats.contains_key(&lb.name))
-        .map(|lb| {
-            let mut dev = BlockDevice::new(lb.name.clone());
-            dev.model = lb.model.clone(); dev.serial = lb.serial.clone();
-            dev.capacity_bytes = lb.size; dev.rotational = lb.rotational;
-            dev.transport = lb.transport.clone(); dev.partitions = lb.partitions.clone();
-            dev.infer_type();
-            if smart_enabled { dev.smart = smart_collector::poll_device(&lb.name); }
-            dev
-        })
-        .collect();
-
-    let raids = collectors::mdraid::read_mdstat();
-    let pools = collectors::zfs::read_zpools();
-    let mut active_alerts = alerts::evaluate(&devices, &fs_list, &cfg.alerts);
-    active_alerts.extend(alerts::evaluate_volumes(&raids, &pools));
+    // SMART data from the cache, polled again only when it is over an hour old
+    let shot = OneShot::collect(&cfg, smart_enabled, scope.needs_io());
+    let snapshot = shot.snapshot();
+    let scope = scope.resolve(&snapshot).unwrap_or_else(|e| unknown(e));
+
+    let mut active_alerts = alerts::evaluate(&shot.devices, &shot.fs_list, &cfg.alerts);
+    active_alerts.extend(alerts::evaluate_volumes(&shot.raids, &shot.pools));
+    active_alerts.extend(alerts::evaluate_rates(
+        &shot.devices, &util::smart_history::load(), &cfg.alerts, chrono::Local::now().timestamp(),
+    ));
+    if scope == Scope::All {
+        let nfs_names: Vec<String> = shot.nfs.iter().map(|m| m.mount.clone()).collect();
+        let stale = match cfg.alerts.nfs_stale_timeout() {
+            Some(t) => collectors::nfs::wait_stale_mounts(&nfs_names, t),
+            None    => std::collections::HashSet::new(),
+        };
+        active_alerts.extend(alerts::evaluate_system(
+            shot.pressure.as_ref(), &shot.nfs, &stale, &cfg.alerts, &std::collections::HashMap::new(),
+        ));
+    }
+    active_alerts.retain(|a| scope.matches(a));
     active_alerts.sort_by(|a, b| b.severity.cmp(&a.severity));
 
+    // Silenced alerts are listed but do not affect the exit status
+    let silences = util::silence::load();
+    let now = chrono::Local::now();
+    let mut silenced: Vec<(alerts::Alert, String)> = Vec::new();
+    active_alerts.retain(|a| match util::silence::silenced_by(a, &silences, &cfg.alerts.maintenance, now) {
+        Some(reason) => { silenced.push((a.clone(), reason)); false }
+        None         => true,
+    });
+    let perf = util::nagios::perfdata(&scope, &snapshot, &cfg.alerts);
+    let perf = if perf.is_empty() { String::new() } else { format!(" | {}", perf) };
+
     let has_crit = active_alerts.iter().any(|a| a.severity == Severity::Critical);
     let has_warn = active_alerts.iter().any(|a| a.severity == Severity::Warning);
 
     if active_alerts.is_empty() {
-        println!("OK — {} device(s), {} filesystem(s), {} array(s), no alerts",
-            devices.len(), fs_list.len(), raids.len() + pools.len());
+        let muted = if silenced.is_empty() { String::new() } else { format!(" ({} silenced)", silenced.len()) };
+        println!("OK — {}, no alerts{}{}", scope.summary(&snapshot), muted, perf);
+        for (a, reason) in &silenced {
+            println!("[{}] {}{}  (silenced: {})", a.severity.label(), a.prefix(), a.message, reason);
+        }
         std::process::exit(0);
     }
 
-    for a in &active_alerts {
-        println!("[{}] {}{}", a.severity.label(), a.prefix(), a.message);
+    // Perfdata goes on the first line, which is all some monitoring systems read
+    for (i, a) in active_alerts.iter().enumerate() {
+        println!("[{}] {}{}{}", a.severity.label(), a.prefix(), a.message, if i == 0 { perf.as_str() } else { "" });
+    }
+    for (a, reason) in &silenced {
+        println!("[{}] {}{}  (silenced: {})", a.severity.label(), a.prefix(), a.message, reason);
     }
 
     if has_crit {
@@ -1997,22 +2527,269 @@ fn run_check(smart_enabled: bool) -> Result<()> {
     Ok(())
 }
 
-fn run_daemon(interval_ms: u64, smart_enabled: bool) -> Result<()> {
+/// One collection pass for the one-shot metric outputs (`--textfile`, `--format`).
+#[derive(Default)]
+struct OneShot {
+    devices:   Vec<models::device::BlockDevice>,
+    fs_list:   Vec<models::filesystem::Filesystem>,
+    raids:     Vec<models::volume::RaidArray>,
+    pools:     Vec<models::volume::ZfsPool>,
+    lvm:       Option<models::volume::LvmState>,
+    nfs:       Vec<collectors::nfs::NfsMountStats>,
+    pressure:  Option<collectors::pressure::SystemPressure>,
+    alerts:    Vec<alerts::Alert>,
+    silenced:  usize,
+    endurance: util::write_endurance::EnduranceMap,
+}
+
+/// What a `OneShot` collects; parts left out stay empty.
+#[derive(Clone, Copy)]
+struct Parts {
+    devices:     bool,
+    /// Poll smartctl for devices the cache lacks or once it is stale;
+    /// otherwise SMART data comes from the cache alone.
+    poll_smart:  bool,
+    sample_io:   bool,
+    filesystems: bool,
+    volumes:     bool,
+    /// LVM, NFS, pressure and the active alerts (which need everything).
+    system:      bool,
+}
+
+impl Parts {
+    fn all(poll_smart: bool, sample_io: bool) -> Self {
+        Self { devices: true, poll_smart, sample_io, filesystems: true, volumes: true, system: true }
+    }
+
+    fn none() -> Self {
+        Self { devices: false, poll_smart: false, sample_io: false, filesystems: false, volumes: false, system: false }
+    }
+
+    /// Just what a Zabbix key or discovery reads. SMART data comes from the
+    /// cache alone, so items never wait for smartctl.
+    fn zabbix(source: util::zabbix::Source) -> Self {
+        use util::zabbix::Source;
+        match source {
+            Source::Devices     => Self { devices: true, ..Self::none() },
+            Source::Filesystems => Self { filesystems: true, ..Self::none() },
+            Source::Arrays      => Self { volumes: true, ..Self::none() },
+            // Includes I/O alerts, so rates are sampled over a second
+            Source::Alerts      => Self::all(false, true),
+        }
+    }
+}
+
+impl OneShot {
+    /// With `sample_io`, I/O rates and NFS RTT come from two reads a second
+    /// apart; without, they stay zero and the call returns at once.
+    fn collect(cfg: &config::Config, smart_enabled: bool, sample_io: bool) -> Self {
+        Self::collect_parts(cfg, Parts::all(smart_enabled, sample_io))
+    }
+
+    fn collect_parts(cfg: &config::Config, parts: Parts) -> Self {
+        use collectors::{diskstats, filesystem, smart_cache};
+        use models::device::BlockDevice;
+
+        let mut shot = Self::default();
+        let sample_io = parts.sample_io && parts.devices;
+        let mut nfs_rtt_prev = collectors::nfs::RttCounters::new();
+        if parts.devices {
+            let lsblk_devs = collectors::lsblk::run_lsblk().unwrap_or_default();
+            // Rates need two samples; a second apart is enough for a timer-driven export
+            let first      = if sample_io { diskstats::read_diskstats().unwrap_or_default() } else { Default::default() };
+            if sample_io && parts.system { collectors::nfs::track_interval_rtt(&mut collectors::nfs::read_nfs_mounts(), &mut nfs_rtt_prev); }
+            let started    = std::time::Instant::now();
+            if sample_io { std::thread::sleep(std::time::Duration::from_secs(1)); }
+            let raw_stats  = diskstats::read_diskstats().unwrap_or_default();
+            let elapsed    = started.elapsed().as_secs_f64();
+
+            shot.devices = lsblk_devs.iter()
+                .filter(|lb| !cfg.devices.exclude.iter().any(|pat| {
+                    if let Some(p) = pat.strip_suffix('*') { lb.name.starts_with(p) }
+                    else { pat == &lb.name }
+                }))
+                .filter(|lb| raw_stats.contains_key(&lb.name))
+                .map(|lb| {
+                    let mut dev = BlockDevice::new(lb.name.clone());
+                    dev.model = lb.model.clone(); dev.serial = lb.serial.clone();
+                    dev.capacity_bytes = lb.size; dev.rotational = lb.rotational;
+                    dev.transport = lb.transport.clone(); dev.partitions = lb.partitions.clone();
+                    dev.alias = cfg.devices.aliases.get(&lb.name).cloned();
+                    dev.infer_type();
+                    if let (Some(prev), Some(curr)) = (first.get(&lb.name), raw_stats.get(&lb.name)) {
+                        dev.set_io(&diskstats::compute_io(prev, curr, elapsed, curr.ios_in_progress));
+                    }
+                    dev
+                })
+                .collect();
+            let names: Vec<String> = shot.devices.iter().map(|d| d.name.clone()).collect();
+            let mut cache = smart_cache::load_fresh(&names, parts.poll_smart);
+            for dev in &mut shot.devices {
+                dev.smart = cache.remove(&dev.name);
+            }
+            shot.endurance = util::write_endurance::load();
+        }
+        if parts.filesystems {
+            shot.fs_list = filesystem::read_filesystems().unwrap_or_default();
+        }
+        if parts.volumes {
+            shot.raids = collectors::mdraid::read_mdstat();
+            shot.pools = collectors::zfs::read_zpools();
+        }
+        if parts.system {
+            shot.lvm = collectors::lvm::read_lvm();
+            shot.nfs = collectors::nfs::read_nfs_mounts();
+            collectors::nfs::track_interval_rtt(&mut shot.nfs, &mut nfs_rtt_prev);
+            shot.pressure = collectors::pressure::read_pressure();
+            let mut active = alerts::evaluate(&shot.devices, &shot.fs_list, &cfg.alerts);
+            active.extend(alerts::evaluate_volumes(&shot.raids, &shot.pools));
+            active.extend(alerts::evaluate_system(shot.pressure.as_ref(), &shot.nfs, &std::collections::HashSet::new(),
+                &cfg.alerts, &std::collections::HashMap::new()));
+            let silences = util::silence::load();
+            let now = chrono::Local::now();
+            shot.silenced = active.iter()
+                .filter(|a| util::silence::silenced_by(a, &silences, &cfg.alerts.maintenance, now).is_some())
+                .count();
+            shot.alerts = active;
+        }
+        shot
+    }
+
+    fn snapshot(&self) -> util::metrics::Snapshot<'_> {
+        util::metrics::Snapshot {
+            devices:     &self.devices,
+            filesystems: &self.fs_list,
+            raids:       &self.raids,
+            pools:       &self.pools,
+            lvm:         self.lvm.as_ref(),
+            nfs:         &self.nfs,
+            pressure:    self.pressure.as_ref(),
+            alerts:      &self.alerts,
+            silenced:    self.silenced,
+            endurance:   &self.endurance,
+        }
+    }
+}
+
+fn run_textfile(path: &str, smart_enabled: bool) -> Result<()> {
+    let cfg = config::Config::load();
+    let shot = OneShot::collect(&cfg, smart_enabled, true);
+    let families = util::metrics::collect(&shot.snapshot());
+    util::metrics::write_textfile(std::path::Path::new(path), &util::metrics::prometheus(&families))
+}
+
+/// Print one metrics snapshot to stdout, e.g. for telegraf's exec input.
+fn run_format(format: &str, smart_enabled: bool) -> Result<()> {
+    if !matches!(format, "influx" | "graphite" | "prometheus") {
+        anyhow::bail!("unknown format {:?} (expected influx, graphite or prometheus)", format);
+    }
+    let cfg = config::Config::load();
+    let shot = OneShot::collect(&cfg, smart_enabled, true);
+    let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default();
+    let text = match format {
+        "influx"   => util::push::influx_lines(&util::push::points(&shot.snapshot()), &cfg.influxdb, now.as_nanos() as i128),
+        "graphite" => util::push::graphite_lines(&util::push::points(&shot.snapshot()), &cfg.graphite, now.as_secs() as i64),
+        _          => util::metrics::prometheus(&util::metrics::collect(&shot.snapshot())),
+    };
+    print!("{}", text);
+    Ok(())
+}
+
+/// Print Zabbix low-level discovery JSON, collecting only what `kind` lists.
+fn run_zabbix_discovery(kind: &str) -> Result<()> {
+    let cfg = config::Config::load();
+    let shot = OneShot::collect_parts(&cfg, Parts::zabbix(util::zabbix::discovery_source(kind)?));
+    println!("{}", util::zabbix::discovery(kind, &shot.snapshot())?);
+    Ok(())
+}
+
+/// Print one Zabbix item value, collecting only what its key reads. SMART
+/// data comes from the cache, which the TUI and `--daemon` keep current.
+fn run_zabbix_get(key: &str) -> Result<()> {
+    let cfg = config::Config::load();
+    let shot = OneShot::collect_parts(&cfg, Parts::zabbix(util::zabbix::source(key)?));
+    println!("{}", util::zabbix::get(key, &shot.snapshot())?);
+    Ok(())
+}
+
+fn run_daemon(interval_ms: u64, smart_enabled: bool, listen: Option<&str>, textfile: Option<&str>) -> Result<()> {
     use collectors::{filesystem, smart as smart_collector};
     use models::device::BlockDevice;
-    use util::{alert_log, webhook};
-
-    eprintln!("dtop daemon starting (interval {}ms, SMART {})…",
-        interval_ms, if smart_enabled { "enabled" } else { "disabled" });
+    use util::{ack_store, alert_log, alert_state::AlertTracker, correlate, notifier, notify_state, silence, smart_history, syslog};
+    use util::syslog::Fields;
+    use std::collections::{HashMap, HashSet};
+    use std::time::Instant;
 
     let cfg = config::Config::load();
+    let log = syslog::Logger::new(syslog::Sink::parse_list(&cfg.daemon.log), syslog::facility(&cfg.daemon.syslog_facility));
+    let sinks: Vec<&str> = log.sinks().iter().map(|s| s.label()).collect();
+    eprintln!("dtop daemon starting (interval {}ms, SMART {}, logging to {})…",
+        interval_ms, if smart_enabled { "enabled" } else { "disabled" }, sinks.join(", "));
+
     let mut prev_alerts: Vec<alerts::Alert> = Vec::new();
+    let mut tracker = AlertTracker::new();
+    let mut history = smart_history::load();
+    let mut notify = notify_state::load();
+    let mut batcher = util::digest::Batcher::new();
+    // Acks are set from the TUI; reloaded only when the file changes
+    let (mut acked, mut acked_mtime) = (HashSet::new(), None);
+    let mut ack_migrated = HashSet::new();
+    let kernel_rx  = collectors::kmsg::spawn();
+    util::delivery::start();
+    let mut kernel_log = collectors::kmsg::KernelLog::default();
+    let rate_attrs: Vec<u32> = cfg.alerts.rate_rules.iter().map(|r| r.attr).collect();
     let tick = std::time::Duration::from_millis(interval_ms.max(500));
+    let mut prev_stats: Option<(HashMap<String, collectors::diskstats::RawDiskstat>, Instant)> = None;
+    let mut mqtt = (!cfg.mqtt.host.is_empty()).then(|| util::mqtt::Publisher::start(&cfg.mqtt));
+    let mqtt_every = std::time::Duration::from_secs(util::human::parse_duration(&cfg.mqtt.interval).unwrap_or(30).max(1));
+    let mut mqtt_last: Option<Instant> = None;
+    if mqtt.is_some() {
+        eprintln!("publishing to MQTT broker {}:{} under {}/ every {}s", cfg.mqtt.host, cfg.mqtt.port,
+            util::mqtt::topic_prefix(&cfg.mqtt), mqtt_every.as_secs());
+    }
+    let metrics_page = match listen {
+        Some(addr) => {
+            let page = util::metrics::serve(addr)?;
+            eprintln!("serving Prometheus metrics on http://{}/metrics", addr);
+            Some(page)
+        }
+        None => None,
+    };
+    let mut fs_history = filesystem::UsageHistory::new();
+    let mut nfs_rtt_prev = collectors::nfs::RttCounters::new();
+    let mut smart_saved: Option<Instant> = None;
+    let mut log_pruned: Option<Instant> = None;
+    // LVM and endurance change slowly and cost a process spawn or file read
+    let mut lvm: Option<models::volume::LvmState> = None;
+    let mut endurance = util::write_endurance::EnduranceMap::new();
+    let mut slow_read: Option<Instant> = None;
+    let mut textfile_err: Option<String> = None;
+    if let Some(path) = textfile {
+        eprintln!("writing Prometheus metrics to {} every tick", path);
+    }
+    let mut pushers: Vec<util::push::Pusher> = util::push::Target::configured(&cfg.influxdb, &cfg.graphite)
+        .into_iter().map(util::push::Pusher::start).collect();
+    for p in &pushers {
+        eprintln!("pushing metrics to {} every {}s", p.target().describe(), p.target().interval().as_secs());
+    }
+    let channels = cfg.notifications.effective_channels();
+    for r in cfg.notifications.effective_routes() {
+        for p in r.problems(&channels) {
+            eprintln!("route → {}: {}", r.channels.join(", "), p);
+        }
+    }
+    if !util::hook::desktop_session() {
+        for ch in channels.iter().filter(|c| c.kind == config::ChannelKind::Desktop) {
+            eprintln!("channel {}: no desktop session (DBUS_SESSION_BUS_ADDRESS/DISPLAY unset), desktop notifications skipped", ch.name);
+        }
+    }
 
     loop {
         let lsblk_devs = collectors::lsblk::run_lsblk().unwrap_or_default();
         let raw_stats  = collectors::diskstats::read_diskstats().unwrap_or_default();
-        let fs_list    = filesystem::read_filesystems().unwrap_or_default();
+        let sampled    = Instant::now();
+        let mut fs_list = filesystem::read_filesystems().unwrap_or_default();
+        filesystem::track_fill_rates(&mut fs_list, &mut fs_history);
 
         let devices: Vec<BlockDevice> = lsblk_devs.iter()
             .filter(|lb| !cfg.devices.exclude.iter().any(|pat| {
@@ -2025,37 +2802,195 @@ fn run_daemon(interval_ms: u64, smart_enabled: bool) -> Result<()> {
                 dev.model = lb.model.clone(); dev.serial = lb.serial.clone();
                 dev.capacity_bytes = lb.size; dev.rotational = lb.rotational;
                 dev.transport = lb.transport.clone(); dev.partitions = lb.partitions.clone();
+                dev.alias = cfg.devices.aliases.get(&lb.name).cloned();
                 dev.infer_type();
                 if smart_enabled { dev.smart = smart_collector::poll_device(&lb.name); }
+                if let (Some((prev, at)), Some(curr)) = (&prev_stats, raw_stats.get(&lb.name)) {
+                    if let Some(prev) = prev.get(&lb.name) {
+                        dev.set_io(&collectors::diskstats::compute_io(prev, curr, sampled.duration_since(*at).as_secs_f64(), curr.ios_in_progress));
+                    }
+                }
                 dev
             })
             .collect();
 
         let raids = collectors::mdraid::read_mdstat();
         let pools = collectors::zfs::read_zpools();
-        let mut new_alerts = alerts::evaluate(&devices, &fs_list, &cfg.alerts);
-        new_alerts.extend(alerts::evaluate_volumes(&raids, &pools));
-        new_alerts.sort_by(|a, b| b.severity.cmp(&a.severity));
-        let now = chrono::Local::now().format("%H:%M:%S").to_string();
-        let mut fresh: Vec<alerts::Alert> = Vec::new();
-        for alert in &new_alerts {
-            let key = format!("{}{}{}", alert.severity.label(), alert.prefix(), alert.message);
-            if !prev_alerts.iter().any(|a| {
-                format!("{}{}{}", a.severity.label(), a.prefix(), a.message) == key
-            }) {
-                fresh.push(alert.clone());
+        let mut raw = alerts::evaluate_with_state(&devices, &fs_list, &cfg.alerts, &tracker.held());
+        raw.extend(alerts::evaluate_volumes(&raids, &pools));
+        let mut history_dirty = false;
+        for dev in &devices {
+            if let Some(smart) = &dev.smart {
+                history_dirty |= smart_history::record(&mut history, dev, smart, &rate_attrs);
+            }
+        }
+        if history_dirty { smart_history::save(&history); }
+        // Keep the SMART cache current for one-shot readers such as --zabbix-get
+        if smart_enabled && smart_saved.is_none_or(|t| t.elapsed().as_secs() >= cfg.general.smart_interval_sec) {
+            let cache: collectors::smart_cache::SmartCache = devices.iter()
+                .filter_map(|d| d.smart.clone().map(|s| (d.name.clone(), s)))
+                .collect();
+            if !cache.is_empty() { collectors::smart_cache::save(&cache); }
+            smart_saved = Some(Instant::now());
+        }
+        // Prune the alert log at startup and then daily
+        if let Some(secs) = cfg.alerts.log_retention_secs().filter(|_| log_pruned.is_none_or(|t| t.elapsed().as_secs() >= 86_400)) {
+            alert_log::prune(secs);
+            log_pruned = Some(Instant::now());
+        }
+        raw.extend(alerts::evaluate_rates(&devices, &history, &cfg.alerts, chrono::Local::now().timestamp()));
+        let mut nfs_mounts = collectors::nfs::read_nfs_mounts();
+        collectors::nfs::track_interval_rtt(&mut nfs_mounts, &mut nfs_rtt_prev);
+        let nfs_names: Vec<String> = nfs_mounts.iter().map(|m| m.mount.clone()).collect();
+        let stale = match cfg.alerts.nfs_stale_timeout() {
+            Some(t) => collectors::nfs::stale_mounts(&nfs_names, t),
+            None    => HashSet::new(),
+        };
+        let pressure = collectors::pressure::read_pressure();
+        raw.extend(alerts::evaluate_system(pressure.as_ref(), &nfs_mounts, &stale, &cfg.alerts, &tracker.held()));
+        kernel_log.drain(&kernel_rx);
+        raw.extend(alerts::evaluate_kernel(&devices, &kernel_log, &cfg.alerts, chrono::Local::now().timestamp()));
+        let (mut new_alerts, changes) = tracker.update(raw, &cfg.alerts, chrono::Local::now().timestamp());
+        let corr = correlate::correlate(&new_alerts, &correlate::Topology::build(&devices, &fs_list, &raids), &cfg.alerts);
+        corr.order(&mut new_alerts);
+        let silences = silence::load();
+        let prev_keys: HashSet<String> = prev_alerts.iter().map(|a| a.key()).collect();
+        let mut silenced: HashSet<String> = HashSet::new();
+        for a in &new_alerts {
+            if let Some(reason) = silence::silenced_by(a, &silences, &cfg.alerts.maintenance, chrono::Local::now()) {
+                if !prev_keys.contains(&a.key()) {
+                    log.alert(&Fields::new("silenced", a, &devices, corr.parent_of(a)),
+                        &format!("[{}] {}{}  (silenced: {})", a.severity.label(), a.prefix(), a.message, reason));
+                }
+                silenced.insert(a.key());
+            } else if let Some(by) = corr.inhibited_by(a) {
+                if !prev_keys.contains(&a.key()) {
+                    log.alert(&Fields::new("inhibited", a, &devices, corr.parent_of(a)),
+                        &format!("[{}] {}{}  (inhibited by {})", a.severity.label(), a.prefix(), a.message, by));
+                }
+                silenced.insert(a.key());
             }
         }
-        if !fresh.is_empty() {
-            alert_log::append(&fresh);
-            if !cfg.notifications.webhook_url.is_empty() {
-                webhook::notify(&fresh, &cfg.notifications.webhook_url, cfg.notifications.notify_warning);
+        let mtime = ack_store::modified();
+        if mtime != acked_mtime {
+            acked = ack_store::load();
+            acked_mtime = mtime;
+        }
+        if ack_store::migrate(&mut acked, &new_alerts, &mut ack_migrated) {
+            ack_store::save(&acked);
+            acked_mtime = ack_store::modified();
+        }
+        let resolved = notify.resolved(&changes);
+        let dispatch = notify.process(&mut new_alerts, &prev_keys, &acked, &silenced, &cfg, chrono::Local::now().timestamp())
+            .with_resolved(&resolved);
+        let mut events: Vec<alert_log::AlertEvent> = changes.iter()
+            .map(|c| c.event(&devices).with_correlation(&corr, &c.alert))
+            .collect();
+        events.extend(dispatch.escalated.iter()
+            .map(|a| alert_log::AlertEvent::new(alert_log::EventKind::Escalated, a, &devices).with_correlation(&corr, a)));
+        alert_log::append(&events);
+        if let Some(m) = &mut mqtt {
+            m.publish_events(&events);
+            if mqtt_last.is_none_or(|t| t.elapsed() >= mqtt_every) || !events.is_empty() {
+                m.announce(&devices, &fs_list);
+                m.publish_state(&devices, &fs_list, &new_alerts);
+                mqtt_last = Some(Instant::now());
+            }
+        }
+        let push_due = pushers.iter().any(|p| p.due());
+        if metrics_page.is_some() || textfile.is_some() || push_due {
+            if slow_read.is_none_or(|t| t.elapsed() >= std::time::Duration::from_secs(60)) {
+                lvm = collectors::lvm::read_lvm();
+                endurance = util::write_endurance::load();
+                slow_read = Some(Instant::now());
+            }
+            let snapshot = util::metrics::Snapshot {
+                devices:     &devices,
+                filesystems: &fs_list,
+                raids:       &raids,
+                pools:       &pools,
+                lvm:         lvm.as_ref(),
+                nfs:         &nfs_mounts,
+                pressure:    pressure.as_ref(),
+                alerts:      &new_alerts,
+                silenced:    silenced.len(),
+                endurance:   &endurance,
+            };
+            if push_due {
+                let points = util::push::points(&snapshot);
+                for p in pushers.iter_mut().filter(|p| p.due()) {
+                    p.push(points.clone());
+                }
+            }
+            let text = util::metrics::prometheus(&util::metrics::collect(&snapshot));
+            if let Some(path) = textfile {
+                // Report a failing write once, not every tick
+                let err = util::metrics::write_textfile(std::path::Path::new(path), &text).err().map(|e| format!("{:#}", e));
+                if err.is_some() && err != textfile_err {
+                    eprintln!("{} {}", chrono::Local::now().format("%H:%M:%S"), err.as_deref().unwrap_or(""));
+                }
+                textfile_err = err;
             }
-            for a in &fresh {
-                eprintln!("{} [{}] {}{}", now, a.severity.label(), a.prefix(), a.message);
+            if let Some(page) = &metrics_page {
+                *page.lock().unwrap_or_else(|e| e.into_inner()) = text;
             }
         }
+        let sent = notifier::dispatch(&dispatch, &cfg.notifications, &corr, &devices, &fs_list, &mut batcher);
+        let went_out = !sent.keys.is_empty();
+        notify.record_sent(sent, chrono::Local::now().timestamp());
+        let new_keys: HashSet<String> = new_alerts.iter().map(|a| a.key()).collect();
+        if !dispatch.is_empty() || went_out || new_keys != prev_keys {
+            notify_state::save(&mut notify);
+        }
+        // Scheduled reports; the first tick only starts each report's clock
+        let now_local = chrono::Local::now();
+        for r in &cfg.notifications.reports {
+            let Some(slot) = notifier::last_slot(r, now_local) else { continue };
+            match notify.reports.get(&r.name) {
+                Some(&last) if last >= slot.timestamp() => continue,
+                Some(_) => {
+                    eprintln!("{} mailing '{}' report", now_local.format("%H:%M:%S"), r.name);
+                    let _ = notifier::send_report(r, &cfg.notifications, &devices, &fs_list, &new_alerts, &raids, &pools);
+                }
+                None => {}
+            }
+            notify.reports.insert(r.name.clone(), now_local.timestamp());
+            notify_state::save(&mut notify);
+        }
+        if !cfg.notifications.digest.channels.is_empty() {
+            if let Some(slot) = notifier::digest_slot(&cfg.notifications.digest, now_local) {
+                if notify.digest.is_none_or(|last| last < slot.timestamp()) {
+                    if notify.digest.is_some() {
+                        eprintln!("{} sending daily digest", now_local.format("%H:%M:%S"));
+                        let (summary, body) = util::digest::daily(&alert_log::load_events(), now_local - chrono::Duration::hours(24),
+                            &new_alerts, batcher.take_suppressed(), &util::webhook::hostname());
+                        let _ = notifier::send_digest(&cfg.notifications, &summary, &body);
+                    }
+                    notify.digest = Some(now_local.timestamp());
+                    notify_state::save(&mut notify);
+                }
+            }
+        }
+        for a in &dispatch.fresh {
+            let under = corr.parent_of(a).map(|p| format!("  (under {})", p)).unwrap_or_default();
+            log.alert(&Fields::new("fired", a, &devices, corr.parent_of(a)),
+                &format!("[{}] {}{}{}", a.severity.label(), a.prefix(), a.message, under));
+        }
+        for a in &dispatch.renotify {
+            log.alert(&Fields::new("reminder", a, &devices, corr.parent_of(a)),
+                &format!("[{}] {}{}  (reminder)", a.severity.label(), a.prefix(), a.message));
+        }
+        for a in &dispatch.escalated {
+            log.alert(&Fields::new("escalated", a, &devices, corr.parent_of(a)),
+                &format!("[{}] {}{}  (escalated)", a.severity.label(), a.prefix(), a.message));
+        }
+        for c in changes.iter().filter(|c| c.kind == alert_log::EventKind::Resolved) {
+            log.alert(&Fields::new("resolved", &c.alert, &devices, None).with_duration(c.duration_secs),
+                &format!("[OK]   {}{} resolved after {}", c.alert.prefix(), c.alert.rule,
+                    util::human::fmt_duration_short(c.duration_secs.unwrap_or(0).max(0) as u64)));
+        }
         prev_alerts = new_alerts;
+        prev_stats = Some((raw_stats, sampled));
         std::thread::sleep(tick);
     }
 }
@@ -2092,6 +3027,9 @@ fn run_summary(smart_enabled: bool) -> Result<()> {
     let mut active = alerts::evaluate(&devices, &fs_list, &cfg.alerts);
     active.extend(alerts::evaluate_volumes(&raids, &pools));
     active.sort_by(|a, b| b.severity.cmp(&a.severity));
+    let silences = util::silence::load();
+    let now = chrono::Local::now();
+    active.retain(|a| util::silence::silenced_by(a, &silences, &cfg.alerts.maintenance, now).is_none());
 
     let crit_n = active.iter().filter(|a| a.severity == Severity::Critical).count();
     let warn_n = active.iter().filter(|a| a.severity == Severity::Warning).count();
@@ -4756,8 +5694,8 @@ Schedule SMART short self-test
 .SH CLI OPTIONS
 .SS Monitoring & Reporting
 .TP
-.B \-\-check
-Exit 0=OK, 1=WARN, 2=CRIT (Nagios-compatible)
+.B \-\-check [\fISCOPE\fR]
+Exit 0=OK, 1=WARN, 2=CRIT, 3=UNKNOWN with Nagios perfdata; SCOPE is smart, fs[=MOUNT], raid or device=NAME
 .TP
 .B \-\-summary
 One-line status summary with exit code
@@ -4783,9 +5721,33 @@ Rolling status every N seconds
 .B \-\-daemon
 Run as headless alert daemon
 .TP
+.B \-\-daemon \-\-listen \fIADDR\fR
+Also serve Prometheus metrics on ADDR at /metrics
+.TP
+.B \-\-textfile \fIPATH\fR
+Write Prometheus metrics to PATH for node_exporter's textfile collector (every tick with \-\-daemon)
+.TP
+.B \-\-format \fIFORMAT\fR
+Print one metrics snapshot as influx (line protocol), graphite or prometheus and exit
+.TP
+.B \-\-zabbix\-discovery \fIKIND\fR
+Print Zabbix low-level discovery JSON for devices, filesystems or arrays
+.TP
+.B \-\-zabbix\-get \fIKEY\fR
+Print one Zabbix item value, e.g. smart.attr[sda,5], fs.pct[/var], device.health[sda]
+.TP
 .B \-\-alerts [\-\-since \fIAGE\fR]
 Show alert log (e.g. \-\-since 7d)
 .TP
+.B \-\-silence \fIMATCHER\fR [\-\-duration \fIDUR\fR] [\-\-starts \fITIME\fR] [\-\-comment \fITEXT\fR]
+Silence matching alerts (e.g. device=sd*,severity=warn)
+.TP
+.B \-\-silences
+List silences and maintenance windows
+.TP
+.B \-\-unsilence \fIID\fR
+Remove a silence
+.TP
 .B \-\-top\-io
 Top processes by disk I/O
 .TP
@@ -1,3 +1,4 @@
+use crate::collectors::diskstats::DeviceIO;
 use crate::models::smart::{SmartData, SmartStatus};
 use crate::util::ring_buffer::RingBuffer;
 use std::time::Instant;
@@ -114,6 +115,17 @@ impl BlockDevice {
         self.smart.as_ref().and_then(|s| s.temperature)
     }
 
+    /// Set the real-time I/O fields from one interval's rates.
+    pub fn set_io(&mut self, io: &DeviceIO) {
+        self.read_bytes_per_sec   = io.read_bytes_per_sec;
+        self.write_bytes_per_sec  = io.write_bytes_per_sec;
+        self.read_iops            = io.read_iops;
+        self.write_iops           = io.write_iops;
+        self.io_util_pct          = io.io_util_pct;
+        self.avg_read_latency_ms  = io.avg_read_latency_ms;
+        self.avg_write_latency_ms = io.avg_write_latency_ms;
+    }
+
     pub fn infer_type(&mut self) {
         let tran = self.transport.as_deref().unwrap_or("").to_lowercase();
         self.dev_type = if tran == "nvme" {
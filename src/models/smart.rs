@@ -62,6 +62,43 @@ impl NvmeHealth {
     pub fn bytes_written(&self) -> u64 { self.data_units_written * 512 * 1000 }
 }
 
+/// Outcome of one self-test log entry.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum SelfTestResult {
+    Passed,
+    /// Aborted by the host, interrupted by a reset, etc. — not a drive fault.
+    Aborted,
+    Failed,
+    InProgress,
+}
+
+impl SelfTestResult {
+    pub fn mark(&self) -> &'static str {
+        match self {
+            SelfTestResult::Passed     => "✓",
+            SelfTestResult::Aborted    => "⚠",
+            SelfTestResult::Failed     => "✗",
+            SelfTestResult::InProgress => "…",
+        }
+    }
+}
+
+/// One entry from the ATA or NVMe self-test log, newest first.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SelfTestEntry {
+    /// "Short offline", "Extended offline", "Short", "Extended", …
+    pub test_type:   String,
+    /// True for extended (long) tests.
+    pub extended:    bool,
+    pub result:      SelfTestResult,
+    /// smartctl's description of the outcome.
+    pub status:      String,
+    /// Power-on hours when the test ran.
+    pub hours:       u64,
+    /// First failing LBA, if the drive reported one.
+    pub failing_lba: Option<u64>,
+}
+
 /// Complete SMART snapshot for one device.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SmartData {
@@ -72,9 +109,28 @@ pub struct SmartData {
     pub attributes:     Vec<SmartAttribute>,
     /// NVMe-specific health log (NVMe only).
     pub nvme:           Option<NvmeHealth>,
+    /// Whether the drive reports a self-test log at all.
+    #[serde(default)]
+    pub self_test_supported: bool,
+    /// Self-test log, newest first.
+    #[serde(default)]
+    pub self_tests:     Vec<SelfTestEntry>,
+    /// Percent remaining of a self-test currently running.
+    #[serde(default)]
+    pub self_test_remaining: Option<u8>,
 }
 
 impl SmartData {
+    /// Power-on hours since the last completed extended test, or None if there
+    /// has never been one (or the drive doesn't report power-on hours). This is
+    /// the drive's own clock: time spent powered off doesn't count.
+    pub fn hours_since_long_test(&self) -> Option<u64> {
+        let poh = self.power_on_hours?;
+        self.self_tests.iter()
+            .find(|t| t.extended && t.result == SelfTestResult::Passed)
+            .map(|t| poh.saturating_sub(t.hours))
+    }
+
     /// Derive SmartStatus from parsed data (may downgrade from Passed → Warning).
     pub fn derive_status(&mut self) {
         if self.status == SmartStatus::Failed { return; }
@@ -1,6 +1,8 @@
-use crate::alerts::{Alert, Severity};
+use crate::alerts::Severity;
 use crate::app::AlertLogFilter;
 use crate::ui::theme::Theme;
+use crate::util::alert_log::{AlertEvent, EventKind};
+use crate::util::human::fmt_duration_short;
 use ratatui::{
     layout::Rect,
     text::{Line, Span},
@@ -11,7 +13,7 @@ use ratatui::{
 pub fn render_alert_log_view(
     f: &mut Frame,
     area: Rect,
-    entries: &[(String, Alert)],  // newest first
+    entries: &[AlertEvent],  // newest first
     scroll: usize,
     filter: AlertLogFilter,
     search: &str,
@@ -19,13 +21,15 @@ pub fn render_alert_log_view(
     theme: &Theme,
 ) {
     let needle = search.to_lowercase();
-    let filtered: Vec<&(String, Alert)> = entries.iter().filter(|(_, a)| {
+    let filtered: Vec<&AlertEvent> = entries.iter().filter(|e| {
+        let a = e.alert();
         let sev_ok = match filter {
             AlertLogFilter::All  => true,
             AlertLogFilter::Crit => a.severity == Severity::Critical,
             AlertLogFilter::Warn => a.severity == Severity::Warning,
         };
-        let txt_ok = needle.is_empty() || a.message.to_lowercase().contains(&needle);
+        let txt_ok = needle.is_empty()
+            || format!("{}{}", a.prefix(), a.message).to_lowercase().contains(&needle);
         sev_ok && txt_ok
     }).collect();
 
@@ -59,17 +63,33 @@ pub fn render_alert_log_view(
     if inner.height == 0 { return; }
 
     let mut lines: Vec<Line> = Vec::new();
-    for (ts, alert) in &filtered {
+    for ev in &filtered {
+        let alert = ev.alert();
         let (sev_str, sev_style) = match alert.severity {
             Severity::Critical => ("[CRIT]", theme.crit),
             Severity::Warning  => ("[WARN]", theme.warn),
             Severity::Info     => ("[INFO]", theme.text_dim),
         };
-        lines.push(Line::from(vec![
-            Span::styled(format!("{}  ", ts), theme.text_dim),
+        let (kind_style, text_style) = match ev.event {
+            EventKind::Resolved => (theme.ok, theme.text_dim),
+            EventKind::Acked | EventKind::Hook => (theme.text_dim, theme.text_dim),
+            _                   => (sev_style, theme.text),
+        };
+        let mut spans = vec![
+            Span::styled(format!("{}  ", ev.ts_display()), theme.text_dim),
             Span::styled(format!("{:<6}  ", sev_str), sev_style),
-            Span::styled(alert.message.clone(), theme.text),
-        ]));
+            Span::styled(format!("{:<9}  ", ev.event.label()), kind_style),
+            Span::styled(format!("{}{}", alert.prefix(), alert.message), text_style),
+        ];
+        if let Some(d) = ev.duration_secs {
+            spans.push(Span::styled(
+                format!("  (lasted {})", fmt_duration_short(d.max(0) as u64)),
+                theme.text_dim,
+            ));
+        }
+        spans.push(Span::styled(ev.relation(), theme.text_dim));
+        spans.push(Span::styled(ev.hook_note(), theme.text_dim));
+        lines.push(Line::from(spans));
     }
 
     let max_scroll = total.saturating_sub(inner.height as usize);
@@ -1,5 +1,6 @@
 use crate::alerts::{Alert, Severity};
 use crate::ui::theme::Theme;
+use crate::util::correlate::Correlation;
 use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
     text::{Line, Span},
@@ -14,13 +15,17 @@ pub fn render_alerts_panel(
     alerts: &[Alert],
     history: &VecDeque<(String, Alert)>,
     acked: &HashSet<String>,
+    silenced: &HashSet<String>,
+    correlation: &Correlation,
     focused: bool,
     theme: &Theme,
     state: &mut ListState,
 ) {
     let border_style = if focused { theme.border_focused } else { theme.border };
 
-    let unacked = alerts.iter().filter(|a| !acked.contains(&a.key())).count();
+    let unacked = alerts.iter()
+        .filter(|a| !acked.contains(&a.key()) && !silenced.contains(&a.key()) && correlation.inhibited_by(a).is_none())
+        .count();
     let title = if unacked > 0 {
         format!("5 Alerts  ({} active)", unacked)
     } else if !alerts.is_empty() {
@@ -57,17 +62,25 @@ pub fn render_alerts_panel(
     let has_overflow = alerts.len() > shown_alerts;
 
     // Build alert list items (including overflow as a plain item)
+    let present: HashSet<String> = alerts.iter().map(|a| a.condition()).collect();
     let mut items: Vec<ListItem> = alerts.iter().take(shown_alerts).map(|alert| {
-        let is_acked = acked.contains(&alert.key());
+        let inhibited = correlation.inhibited_by(alert).is_some();
+        let is_muted = silenced.contains(&alert.key()) || inhibited;
+        let is_acked = acked.contains(&alert.key()) || is_muted;
         let (badge, badge_style) = match alert.severity {
             Severity::Critical => ("CRIT", if is_acked { theme.text_dim } else { theme.crit }),
             Severity::Warning  => ("WARN", if is_acked { theme.text_dim } else { theme.warn }),
             Severity::Info     => ("INFO", theme.text_dim),
         };
         let msg_style = if is_acked { theme.text_dim } else { theme.text };
-        let ack_mark  = if is_acked { " [ack]" } else { "" };
+        let ack_mark  = if inhibited { " [inhibited]" } else if is_muted { " [silenced]" } else if is_acked { " [ack]" } else { "" };
+        // Children are listed right below their root-cause alert
+        let indent = match correlation.parent_of(alert) {
+            Some(p) if present.contains(p) => "  └ ",
+            _                              => "  ",
+        };
         ListItem::new(Line::from(vec![
-            Span::styled("  ", theme.text),
+            Span::styled(indent, theme.text_dim),
             Span::styled(badge, badge_style),
             Span::styled("  ", theme.text),
             Span::styled(alert.prefix(), theme.text_dim),
@@ -117,6 +117,15 @@ pub fn render_config_overlay(f: &mut Frame, config: &Config, scroll: usize, them
         let msg = rule.message.as_deref().unwrap_or("(auto)");
         right.push(dim(&format!("  attr {:>3}  {} {}  [{}]  {}", rule.attr, rule.op, rule.value, rule.severity, msg), theme));
     }
+    right.push(Line::from(""));
+    right.push(hdr("Expression rules", theme));
+    if config.alerts.rules.is_empty() {
+        right.push(dim("(none)", theme));
+    }
+    for rule in &config.alerts.rules {
+        let bad = if crate::util::expr::parse(&rule.expr).is_err() { "  ✗ invalid" } else { "" };
+        right.push(dim(&format!("  {}  [{}]  {}{}", rule.id, rule.severity, rule.expr, bad), theme));
+    }
 
     f.render_widget(Paragraph::new(left).wrap(Wrap { trim: false }).scroll((scroll as u16, 0)), cols[0]);
     f.render_widget(Paragraph::new(right).wrap(Wrap { trim: false }).scroll((scroll as u16, 0)), cols[1]);
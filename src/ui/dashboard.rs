@@ -40,10 +40,10 @@ pub fn render(f: &mut Frame, app: &mut App) {
     // ── Header line 1: title + alerts + clock ──────────────────────
     // Count only un-acked alerts for the badge
     let crit_count = app.alerts.iter()
-        .filter(|a| a.severity == crate::alerts::Severity::Critical && !app.acked_alerts.contains(&a.key()))
+        .filter(|a| a.severity == crate::alerts::Severity::Critical && !app.acked_alerts.contains(&a.key()) && !app.silenced_alerts.contains(&a.key()) && app.correlation.inhibited_by(a).is_none())
         .count();
     let warn_count = app.alerts.iter()
-        .filter(|a| a.severity == crate::alerts::Severity::Warning && !app.acked_alerts.contains(&a.key()))
+        .filter(|a| a.severity == crate::alerts::Severity::Warning && !app.acked_alerts.contains(&a.key()) && !app.silenced_alerts.contains(&a.key()) && app.correlation.inhibited_by(a).is_none())
         .count();
 
     let alert_badge = if crit_count > 0 {
@@ -179,7 +179,8 @@ pub fn render(f: &mut Frame, app: &mut App) {
                 let anomalies   = app.smart_anomalies.get(&dev.name);
                 let baseline    = app.smart_baselines.get(&dev.name).map(|b| b as &_);
                 let endurance   = app.write_endurance.get(&dev.name).map(|e| e as &_);
-                render_detail(f, cols[1], dev, &app.filesystems, app.detail_scroll, app.detail_history_window, test_status, anomalies, baseline, endurance, app.detail_show_desc, &theme);
+                let kernel      = app.kernel_log.for_device(&dev.name);
+                render_detail(f, cols[1], dev, &app.filesystems, app.detail_scroll, app.detail_history_window, test_status, anomalies, baseline, endurance, kernel, app.detail_show_desc, &theme);
             }
         }
     } else if area.width < 100 {
@@ -241,8 +242,8 @@ fn render_preset_full(f: &mut Frame, body: ratatui::layout::Rect, app: &mut App,
         app.active_panel == ActivePanel::SmartTemp, theme,
     );
     render_alerts_panel(
-        f, bottom[1], &app.alerts, &app.alert_history, &app.acked_alerts,
-        app.active_panel == ActivePanel::Alerts, theme,
+        f, bottom[1], &app.alerts, &app.alert_history, &app.acked_alerts, &app.silenced_alerts,
+        &app.correlation, app.active_panel == ActivePanel::Alerts, theme,
         &mut app.alerts_panel_state,
     );
 }
@@ -279,10 +280,10 @@ fn render_preset_io_focus(f: &mut Frame, body: ratatui::layout::Rect, app: &mut
 
 fn alert_badge_counts(app: &App) -> (usize, usize) {
     let nc = app.alerts.iter()
-        .filter(|a| a.severity == crate::alerts::Severity::Critical && !app.acked_alerts.contains(&a.key()))
+        .filter(|a| a.severity == crate::alerts::Severity::Critical && !app.acked_alerts.contains(&a.key()) && !app.silenced_alerts.contains(&a.key()) && app.correlation.inhibited_by(a).is_none())
         .count();
     let nw = app.alerts.iter()
-        .filter(|a| a.severity == crate::alerts::Severity::Warning && !app.acked_alerts.contains(&a.key()))
+        .filter(|a| a.severity == crate::alerts::Severity::Warning && !app.acked_alerts.contains(&a.key()) && !app.silenced_alerts.contains(&a.key()) && app.correlation.inhibited_by(a).is_none())
         .count();
     (nc, nw)
 }
@@ -1,6 +1,7 @@
+use crate::collectors::kmsg::{KernelEvent, KernelEventKind};
 use crate::models::device::BlockDevice;
 use crate::models::filesystem::Filesystem;
-use crate::models::smart::{SmartData, SmartStatus};
+use crate::models::smart::{SelfTestResult, SmartData, SmartStatus};
 use crate::ui::theme::Theme;
 use crate::util::health_score::{health_score, score_style};
 use crate::util::human::{fmt_bytes, fmt_duration_short, fmt_iops, fmt_pct, fmt_rate};
@@ -9,7 +10,7 @@ use crate::util::smart_anomaly::{self, DeviceAnomalies};
 use crate::util::smart_attr_desc;
 use crate::util::smart_baseline::Baseline;
 use crate::util::write_endurance::{DeviceEndurance, daily_avg};
-use chrono::Local;
+use chrono::{Local, TimeZone};
 use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Style},
@@ -17,6 +18,7 @@ use ratatui::{
     widgets::{Block, Borders, Paragraph, Sparkline, Wrap},
     Frame,
 };
+use std::collections::VecDeque;
 
 // History window constants
 const WINDOWS: [(usize, &str); 3] = [
@@ -36,6 +38,7 @@ pub fn render_detail(
     anomalies: Option<&DeviceAnomalies>,
     baseline: Option<&Baseline>,
     endurance: Option<&DeviceEndurance>,
+    kernel_events: Option<&VecDeque<KernelEvent>>,
     show_desc: bool,
     theme: &Theme,
 ) {
@@ -69,7 +72,7 @@ pub fn render_detail(
         .split(inner);
 
     render_sparklines(f, sections[0], device, history_window, theme);
-    render_info(f, sections[1], device, filesystems, scroll, smart_test_status, anomalies, baseline, endurance, show_desc, theme);
+    render_info(f, sections[1], device, filesystems, scroll, smart_test_status, anomalies, baseline, endurance, kernel_events, show_desc, theme);
 }
 
 fn render_sparklines(f: &mut Frame, area: Rect, device: &BlockDevice, history_window: usize, theme: &Theme) {
@@ -188,7 +191,7 @@ fn temp_sparkline(rb: &RingBuffer, width: usize) -> (String, u64, u64) {
     (spark, min, max)
 }
 
-fn render_info(f: &mut Frame, area: Rect, device: &BlockDevice, filesystems: &[Filesystem], scroll: usize, smart_test_status: Option<&str>, anomalies: Option<&DeviceAnomalies>, baseline: Option<&Baseline>, endurance: Option<&DeviceEndurance>, show_desc: bool, theme: &Theme) {
+fn render_info(f: &mut Frame, area: Rect, device: &BlockDevice, filesystems: &[Filesystem], scroll: usize, smart_test_status: Option<&str>, anomalies: Option<&DeviceAnomalies>, baseline: Option<&Baseline>, endurance: Option<&DeviceEndurance>, kernel_events: Option<&VecDeque<KernelEvent>>, show_desc: bool, theme: &Theme) {
     // Split the area: 1 line for tab bar, rest for scrollable content
     let tab_chunks = Layout::default()
         .direction(Direction::Vertical)
@@ -541,6 +544,71 @@ fn render_info(f: &mut Frame, area: Rect, device: &BlockDevice, filesystems: &[F
         lines.push(Line::from(vec![]));
     }
 
+    // ── Self-test log ─────────────────────────────────────────────────
+    if let Some(smart) = device.smart.as_ref().filter(|s| s.self_test_supported) {
+        lines.push(section_header("── Self-Test Log ", theme));
+        if let Some(remaining) = smart.self_test_remaining {
+            lines.push(kv_colored("Running", &format!("{}% remaining", remaining), theme.warn, theme));
+        }
+        if let Some(h) = smart.hours_since_long_test() {
+            lines.push(kv("Last Long Test", &format!("{} power-on hours ago", h), theme));
+        }
+        if smart.self_tests.is_empty() {
+            lines.push(Line::from(vec![
+                Span::styled("  No self-tests on record — press ", theme.text_dim),
+                Span::styled("x", theme.ok),
+                Span::styled(" to run a short test", theme.text_dim),
+            ]));
+        } else {
+            lines.push(Line::from(vec![
+                Span::styled("     Hours  Type                Result", theme.text_dim),
+            ]));
+            for t in smart.self_tests.iter().take(10) {
+                let style = match t.result {
+                    SelfTestResult::Passed     => theme.ok,
+                    SelfTestResult::Failed     => theme.crit,
+                    SelfTestResult::Aborted
+                    | SelfTestResult::InProgress => theme.warn,
+                };
+                let lba = t.failing_lba.map(|l| format!("  LBA {}", l)).unwrap_or_default();
+                lines.push(Line::from(vec![
+                    Span::styled(format!("  {} ", t.result.mark()), style),
+                    Span::styled(format!("{:>6}  ", t.hours), theme.text_dim),
+                    Span::styled(format!("{:<18}  ", t.test_type), theme.text),
+                    Span::styled(format!("{}{}", t.status, lba), style),
+                ]));
+            }
+        }
+        lines.push(Line::from(vec![]));
+    }
+
+    // ── Kernel storage events ─────────────────────────────────────────
+    if let Some(events) = kernel_events.filter(|e| !e.is_empty()) {
+        lines.push(section_header("── Kernel Events ", theme));
+        let counts: Vec<String> = KernelEventKind::ALL.iter()
+            .filter_map(|k| {
+                let n = events.iter().filter(|e| e.kind == *k).count();
+                (n > 0).then(|| format!("{} {}", n, k.label()))
+            })
+            .collect();
+        lines.push(kv("Recorded", &counts.join(", "), theme));
+        for ev in events.iter().rev().take(8) {
+            let style = match ev.kind {
+                KernelEventKind::LinkReset | KernelEventKind::Timeout => theme.warn,
+                _ => theme.crit,
+            };
+            let when = Local.timestamp_opt(ev.ts, 0).single()
+                .map(|t| t.format("%m-%d %H:%M:%S").to_string())
+                .unwrap_or_default();
+            lines.push(Line::from(vec![
+                Span::styled(format!("  {}  ", when), theme.text_dim),
+                Span::styled(format!("{:<16}", ev.kind.label()), style),
+                Span::styled(ev.message.clone(), theme.text),
+            ]));
+        }
+        lines.push(Line::from(vec![]));
+    }
+
     // ── SMART anomaly history ─────────────────────────────────────────
     if let Some(dev_anomalies) = anomalies {
         if !dev_anomalies.is_empty() {
@@ -50,6 +50,7 @@ pub fn render(f: &mut Frame, theme: &Theme, scroll: usize) {
         key_line(theme, "  s",     "Cycle sort (Natural / Util / Temp / Health)"),
         key_line(theme, "  p",     "Cycle layout (Full / IO-Focus / Storage)"),
         key_line(theme, "  a",     "Ack all alerts  (Enter = ack one)"),
+        key_line(theme, "  z",     "Silence selected alert 1h (again = lift)"),
         Line::from(""),
         key_line(theme, "Device detail pane", ""),
         key_line(theme, "  w",  "Cycle history window (60s / 5m / 1h)"),
@@ -80,7 +81,7 @@ pub fn render(f: &mut Frame, theme: &Theme, scroll: usize) {
         key_line(theme, "  ↑↓",  "Scroll mount table"),
         Line::from(""),
         key_line(theme, "CLI modes", ""),
-        key_line(theme, "  --check",       "Exit 0=OK 1=WARN 2=CRIT (nagios)"),
+        key_line(theme, "  --check [SCOPE]", "Nagios check + perfdata (smart, fs=…)"),
         key_line(theme, "  --summary",     "One-line status (exit 0/1/2)"),
         key_line(theme, "  --watch N",     "Rolling status every N seconds"),
         key_line(theme, "  --report",      "Human-readable health report"),
@@ -91,6 +92,8 @@ pub fn render(f: &mut Frame, theme: &Theme, scroll: usize) {
         key_line(theme, "  --daemon",      "Headless alert daemon"),
         key_line(theme, "  --alerts",            "Show recent alert log entries"),
         key_line(theme, "  --alerts --since Nd", "Filter alerts by age (24h, 7d…)"),
+        key_line(theme, "  --silence M --duration 2h", "Silence alerts matching M"),
+        key_line(theme, "  --silences / --unsilence ID", "List / remove silences"),
         key_line(theme, "  --top-io",            "Top processes by disk I/O"),
         key_line(theme, "  --device-report DEV", "Full SMART report for one device"),
         key_line(theme, "  --anomalies",         "Show tracked SMART anomaly log"),
@@ -144,7 +147,15 @@ pub fn render(f: &mut Frame, theme: &Theme, scroll: usize) {
         key_line(theme, "  --bench-write DEV", "Sequential write benchmark (temp file)"),
         key_line(theme, "  --diag",            "Self-diagnostic: tools, config, cache"),
         key_line(theme, "  --print-service",     "Print systemd unit for daemon"),
-        key_line(theme, "  --test-webhook",      "Send test webhook notification"),
+        key_line(theme, "  --listen ADDR",       "Daemon: Prometheus /metrics on ADDR"),
+        key_line(theme, "  --textfile PATH",     "Metrics file for node_exporter"),
+        key_line(theme, "  --format FORMAT",     "Print influx/graphite/prom metrics"),
+        key_line(theme, "  --zabbix-discovery K", "Zabbix LLD: devices|filesystems|arrays"),
+        key_line(theme, "  --zabbix-get KEY",    "One Zabbix item value from the cache"),
+        key_line(theme, "  --test-webhook [CH]", "Send test notification to a channel"),
+        key_line(theme, "  --send-report [NAME]", "Mail a scheduled report now"),
+        key_line(theme, "  --digest",            "Print the daily alert digest"),
+        key_line(theme, "  --send-digest",       "Send the daily digest now"),
         key_line(theme, "  --edit-config",       "Open config in $EDITOR"),
         key_line(theme, "  --config",            "Print current config values"),
         key_line(theme, "  --no-smart",          "Disable SMART polling"),
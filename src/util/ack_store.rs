@@ -1,6 +1,8 @@
+use crate::alerts::Alert;
 use std::collections::HashSet;
 use std::fs;
 use std::path::PathBuf;
+use std::time::SystemTime;
 
 fn ack_path() -> Option<PathBuf> {
     dirs::data_local_dir().map(|p| p.join("dtop").join("acked_alerts.json"))
@@ -14,6 +16,37 @@ pub fn load() -> HashSet<String> {
     serde_json::from_str(&text).unwrap_or_default()
 }
 
+/// Modification time of the ack file, so callers polling it can skip reloads
+/// while it is unchanged. None if it doesn't exist.
+pub fn modified() -> Option<SystemTime> {
+    fs::metadata(ack_path()?).and_then(|m| m.modified()).ok()
+}
+
+/// Re-key acks saved before alert keys were built from the rule ID (severity,
+/// target and message text) to the firing alerts they belong to. The reading
+/// in the message may have changed since, so digits are ignored when comparing.
+/// Nothing writes old keys any more, so each alert key is looked up only once:
+/// `checked` remembers those already done. Returns true if any ack was migrated.
+pub fn migrate(acked: &mut HashSet<String>, alerts: &[Alert], checked: &mut HashSet<String>) -> bool {
+    let shape = |s: &str| s.chars().filter(|c| !c.is_ascii_digit()).collect::<String>();
+    let mut changed = false;
+    for alert in alerts {
+        let key = alert.key();
+        if !checked.insert(key.clone()) || acked.contains(&key) { continue; }
+        let head = format!("{}{}", alert.severity.label(), alert.prefix());
+        let message = shape(&alert.message);
+        let old = acked.iter()
+            .find(|k| k.strip_prefix(&head).is_some_and(|rest| shape(rest) == message))
+            .cloned();
+        if let Some(old) = old {
+            acked.remove(&old);
+            acked.insert(key);
+            changed = true;
+        }
+    }
+    changed
+}
+
 /// Persist the current acked alert key set to disk (best-effort).
 pub fn save(acked: &HashSet<String>) {
     let path = match ack_path() { Some(p) => p, None => return };
@@ -1,83 +1,222 @@
 use crate::alerts::{Alert, Severity};
+use crate::models::device::BlockDevice;
+use crate::util::correlate::Correlation;
+use crate::util::lock;
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone};
+use serde::{Deserialize, Serialize};
 use std::fs::{self, OpenOptions};
 use std::io::{BufRead, BufReader, Write};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
+/// Structured alert log: one JSON object per line.
 pub fn log_path() -> Option<PathBuf> {
+    dirs::data_local_dir().map(|p| p.join("dtop").join("alerts.jsonl"))
+}
+
+/// Free-text log written by earlier versions; migrated on first read.
+fn legacy_log_path() -> Option<PathBuf> {
     dirs::data_local_dir().map(|p| p.join("dtop").join("alerts.log"))
 }
 
-/// Load the last `n` entries from the alert log for pre-populating alert history.
-/// Returns `(ts_str, Alert)` pairs, oldest first (same order as the file).
-/// Lines that can't be parsed are skipped silently.
-pub fn load_recent(n: usize) -> Vec<(String, Alert)> {
-    let path = match log_path() { Some(p) => p, None => return Vec::new() };
-    let file = match fs::File::open(&path) { Ok(f) => f, Err(_) => return Vec::new() };
+/// Lifecycle stage recorded for an alert condition.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EventKind {
+    Fired,
+    Resolved,
+    Acked,
+    Escalated,
+    /// An exec channel ran for this alert; see `AlertEvent::hook`.
+    Hook,
+}
 
-    // Read all lines, keep last n
-    let lines: Vec<String> = BufReader::new(file)
-        .lines()
-        .filter_map(|l| l.ok())
-        .collect();
-    let start = lines.len().saturating_sub(n);
+impl EventKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            EventKind::Fired     => "fired",
+            EventKind::Resolved  => "resolved",
+            EventKind::Acked     => "acked",
+            EventKind::Escalated => "escalated",
+            EventKind::Hook      => "hook",
+        }
+    }
 
-    lines[start..].iter().filter_map(|line| parse_log_line(line)).collect()
+    /// Events shown in the TUI alert history: every firing and escalation in
+    /// the log, silenced or not, as `--alerts` lists them.
+    pub fn in_history(&self) -> bool {
+        matches!(self, EventKind::Fired | EventKind::Escalated)
+    }
 }
 
-// Format: "YYYY-MM-DD HH:MM:SS [CRIT/WARN/INFO] <prefix+message>"
-fn parse_log_line(line: &str) -> Option<(String, Alert)> {
-    // Timestamp is first 19 chars: "2024-01-15 12:34:56"
-    if line.len() < 22 { return None; }
-    let ts_str = line[11..19].to_string(); // "HH:MM:SS"
-    let rest   = &line[20..];             // "[WARN] [sda] ..."
-
-    let severity = if rest.starts_with("[CRIT]") {
-        Severity::Critical
-    } else if rest.starts_with("[WARN]") {
-        Severity::Warning
-    } else {
-        Severity::Info
-    };
+/// One line of `alerts.jsonl`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlertEvent {
+    /// RFC 3339 local timestamp.
+    pub ts:       String,
+    pub event:    EventKind,
+    /// "CRIT", "WARN" or "INFO"
+    pub severity: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub device:   Option<String>,
+    /// Drive serial, so history survives device renames (sda → sdb).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub serial:   Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mount:    Option<String>,
+    /// Rule ID ("temperature", "fs.usage", "rule.<id>"); empty for migrated entries.
+    #[serde(default)]
+    pub rule:     String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value:    Option<f64>,
+    pub message:  String,
+    /// For `resolved`: how long the condition was firing, in seconds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration_secs: Option<i64>,
+    /// Condition of the root-cause alert this one was grouped under ("[md0] raid.degraded").
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent:   Option<String>,
+    /// Condition of the alert that inhibited this one's notifications.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub inhibited_by: Option<String>,
+    /// For `hook`: the command's outcome.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hook:     Option<HookRun>,
+}
 
-    // Skip "[XXXX] " (7 chars) to get prefix+message
-    let msg = rest.get(7..).unwrap_or("").trim().to_string();
+/// Outcome of one exec channel run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HookRun {
+    pub channel:   String,
+    /// Notification event that triggered it: "fired", "resolved", …
+    pub trigger:   String,
+    /// Exit status; `None` if the command could not start or was killed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+    #[serde(default)]
+    pub timed_out: bool,
+    /// Combined stdout and stderr (truncated).
+    #[serde(default)]
+    pub output:    String,
+}
 
-    Some((ts_str, Alert {
-        severity,
-        device: None,
-        mount:  None,
-        message: msg,
-    }))
+impl AlertEvent {
+    /// Build an event for `alert` stamped now; the serial is looked up in `devices`.
+    pub fn new(event: EventKind, alert: &Alert, devices: &[BlockDevice]) -> Self {
+        let serial = alert.device.as_ref().and_then(|d| {
+            devices.iter().find(|dev| &dev.name == d).and_then(|dev| dev.serial.clone())
+        });
+        Self {
+            ts:       Local::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, false),
+            event,
+            severity: alert.severity.label().to_string(),
+            device:   alert.device.clone(),
+            serial,
+            mount:    alert.mount.clone(),
+            rule:     alert.rule.clone(),
+            value:    alert.value,
+            message:  alert.message.clone(),
+            duration_secs: None,
+            parent:   None,
+            inhibited_by: None,
+            hook:     None,
+        }
+    }
+
+    pub fn with_duration(mut self, secs: i64) -> Self {
+        self.duration_secs = Some(secs);
+        self
+    }
+
+    /// "  (inhibited by …)" or "  (under …)" for display; empty when not grouped.
+    pub fn relation(&self) -> String {
+        match (&self.inhibited_by, &self.parent) {
+            (Some(by), _)   => format!("  (inhibited by {})", by),
+            (None, Some(p)) => format!("  (under {})", p),
+            (None, None)    => String::new(),
+        }
+    }
+
+    /// "  → ticket: exit 0: <first output line>" for `hook` events; empty otherwise.
+    pub fn hook_note(&self) -> String {
+        let Some(h) = &self.hook else { return String::new() };
+        let status = match (h.timed_out, h.exit_code) {
+            (true, _)        => "timed out".to_string(),
+            (false, Some(c)) => format!("exit {}", c),
+            (false, None)    => "failed to run".to_string(),
+        };
+        match h.output.lines().find(|l| !l.trim().is_empty()) {
+            Some(line) => format!("  → {} ({}): {}: {}", h.channel, h.trigger, status, line.trim()),
+            None       => format!("  → {} ({}): {}", h.channel, h.trigger, status),
+        }
+    }
+
+    /// Record where `alert` sits in the current incident grouping.
+    pub fn with_correlation(mut self, corr: &Correlation, alert: &Alert) -> Self {
+        self.parent       = corr.parent_of(alert).map(str::to_string);
+        self.inhibited_by = corr.inhibited_by(alert).map(str::to_string);
+        self
+    }
+
+    pub fn time(&self) -> Option<DateTime<Local>> {
+        DateTime::parse_from_rfc3339(&self.ts).ok().map(|t| t.with_timezone(&Local))
+    }
+
+    /// "YYYY-MM-DD HH:MM:SS" for display.
+    pub fn ts_display(&self) -> String {
+        self.time()
+            .map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string())
+            .unwrap_or_else(|| self.ts.clone())
+    }
+
+    pub fn alert(&self) -> Alert {
+        Alert {
+            severity: Severity::from_label(&self.severity),
+            device:   self.device.clone(),
+            mount:    self.mount.clone(),
+            rule:     self.rule.clone(),
+            value:    self.value,
+            message:  self.message.clone(),
+        }
+    }
 }
 
-/// Load all entries from the alert log, newest first (full date+time timestamp).
-pub fn load_all() -> Vec<(String, Alert)> {
+/// Load every event, oldest first. Migrates the legacy `alerts.log` on first use.
+/// Lines that can't be parsed are skipped silently.
+pub fn load_events() -> Vec<AlertEvent> {
+    migrate_legacy();
     let path = match log_path() { Some(p) => p, None => return Vec::new() };
-    let file = match std::fs::File::open(&path) { Ok(f) => f, Err(_) => return Vec::new() };
-    let mut entries: Vec<(String, Alert)> = BufReader::new(file)
+    let file = match fs::File::open(&path) { Ok(f) => f, Err(_) => return Vec::new() };
+    BufReader::new(file)
         .lines()
-        .filter_map(|l| l.ok())
-        .filter_map(|line| parse_log_line_full(&line))
-        .collect();
-    entries.reverse(); // newest first
-    entries
+        .map_while(Result::ok)
+        .filter_map(|line| serde_json::from_str(&line).ok())
+        .collect()
 }
 
-// Full-timestamp parser: returns "YYYY-MM-DD HH:MM:SS" instead of just HH:MM:SS
-fn parse_log_line_full(line: &str) -> Option<(String, Alert)> {
-    if line.len() < 22 { return None; }
-    let ts_str   = line[0..19].to_string(); // "YYYY-MM-DD HH:MM:SS"
-    let rest     = &line[20..];
-    let severity = if rest.starts_with("[CRIT]") { Severity::Critical }
-                   else if rest.starts_with("[WARN]") { Severity::Warning }
-                   else { Severity::Info };
-    let msg = rest.get(7..).unwrap_or("").trim().to_string();
-    Some((ts_str, Alert { severity, device: None, mount: None, message: msg }))
+/// Load the last `n` events, oldest first (same order as the file).
+pub fn load_recent(n: usize) -> Vec<AlertEvent> {
+    let mut events = load_events();
+    let start = events.len().saturating_sub(n);
+    events.drain(..start);
+    events
 }
 
-/// Append a slice of new alerts to the persistent log file.
-pub fn append(alerts: &[Alert]) {
-    if alerts.is_empty() { return; }
+/// Load all events, newest first.
+pub fn load_all() -> Vec<AlertEvent> {
+    let mut events = load_events();
+    events.reverse();
+    events
+}
+
+/// Lock file serialising appends against `prune`'s rewrite, so a line
+/// appended while the log is being rewritten isn't lost with the old file.
+fn lock_log(path: &Path) -> Option<fs::File> {
+    lock::exclusive(&path.with_extension("lock"))
+}
+
+/// Append events to the structured log.
+pub fn append(events: &[AlertEvent]) {
+    if events.is_empty() { return; }
     let path = match log_path() {
         Some(p) => p,
         None    => return,
@@ -85,14 +224,89 @@ pub fn append(alerts: &[Alert]) {
     if let Some(parent) = path.parent() {
         let _ = fs::create_dir_all(parent);
     }
-    let now = chrono::Local::now().format("%Y-%m-%d %H:%M:%S");
+    let _lock = lock_log(&path);
     if let Ok(mut file) = OpenOptions::new().create(true).append(true).open(&path) {
-        for alert in alerts {
-            let _ = writeln!(
-                file,
-                "{} [{}] {}{}",
-                now, alert.severity.label(), alert.prefix(), alert.message
-            );
+        for ev in events {
+            if let Ok(line) = serde_json::to_string(ev) {
+                // One write per line, so a line is never split across writes
+                let _ = file.write_all(format!("{}\n", line).as_bytes());
+            }
         }
     }
 }
+
+/// Drop events older than `max_age_secs` from the log, rewriting it only when
+/// something expired. Lines without a readable timestamp are kept.
+pub fn prune(max_age_secs: i64) {
+    let Some(path) = log_path() else { return };
+    let _lock = lock_log(&path);
+    let Ok(text) = fs::read_to_string(&path) else { return };
+    let cutoff = Local::now() - chrono::Duration::seconds(max_age_secs);
+    let expired = |line: &str| serde_json::from_str::<AlertEvent>(line).ok()
+        .and_then(|ev| ev.time())
+        .is_some_and(|t| t < cutoff);
+    // Oldest first, so nothing has expired unless the first line has
+    if !text.lines().next().is_some_and(expired) { return; }
+    let kept: String = text.lines().filter(|l| !expired(l)).map(|l| format!("{}\n", l)).collect();
+    let tmp = path.with_extension("jsonl.tmp");
+    if fs::write(&tmp, kept).is_ok() {
+        let _ = fs::rename(&tmp, &path);
+    }
+}
+
+/// Convert the old free-text `alerts.log` into `fired` events ahead of any
+/// existing JSONL entries, then rename it to `alerts.log.migrated`.
+fn migrate_legacy() {
+    let (Some(legacy), Some(path)) = (legacy_log_path(), log_path()) else { return };
+    let Ok(text) = fs::read_to_string(&legacy) else { return };
+
+    let mut out = String::new();
+    for ev in text.lines().filter_map(parse_legacy_line) {
+        if let Ok(line) = serde_json::to_string(&ev) {
+            out.push_str(&line);
+            out.push('\n');
+        }
+    }
+    out.push_str(&fs::read_to_string(&path).unwrap_or_default());
+    if fs::write(&path, out).is_ok() {
+        let _ = fs::rename(&legacy, legacy.with_extension("log.migrated"));
+    }
+}
+
+// Legacy format: "YYYY-MM-DD HH:MM:SS [CRIT/WARN/INFO] [target] message"
+fn parse_legacy_line(line: &str) -> Option<AlertEvent> {
+    let naive = NaiveDateTime::parse_from_str(line.get(0..19)?, "%Y-%m-%d %H:%M:%S").ok()?;
+    let ts    = Local.from_local_datetime(&naive).single()?;
+    let rest  = line.get(20..)?;
+    let severity = rest.get(1..5).unwrap_or("INFO").to_string();
+    let mut msg  = rest.get(7..).unwrap_or("").trim();
+
+    // Recover the "[sda] " / "[/home] " prefix the old reader threw away
+    let (mut device, mut mount) = (None, None);
+    if let Some(inner) = msg.strip_prefix('[') {
+        if let Some((target, tail)) = inner.split_once("] ") {
+            if target.starts_with('/') || target.starts_with("zpool:") {
+                mount = Some(target.to_string());
+            } else {
+                device = Some(target.to_string());
+            }
+            msg = tail;
+        }
+    }
+
+    Some(AlertEvent {
+        ts:       ts.to_rfc3339_opts(chrono::SecondsFormat::Secs, false),
+        event:    EventKind::Fired,
+        severity,
+        device,
+        serial:   None,
+        mount,
+        rule:     String::new(),
+        value:    None,
+        message:  msg.to_string(),
+        duration_secs: None,
+        parent:   None,
+        inhibited_by: None,
+        hook:     None,
+    })
+}
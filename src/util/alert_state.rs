@@ -0,0 +1,123 @@
+use crate::alerts::{Alert, Severity};
+use crate::config::AlertConfig;
+use crate::models::device::BlockDevice;
+use crate::util::alert_log::{AlertEvent, EventKind};
+use std::collections::{HashMap, HashSet};
+
+struct Tracked {
+    /// Alert as currently reported (firing severity while an upgrade is pending).
+    alert:         Alert,
+    /// Unix timestamp the condition was first seen in this episode.
+    pending_since: i64,
+    /// Unix timestamp the condition started firing; None while pending.
+    firing_since:  Option<i64>,
+    /// Higher-severity reading waiting out its `for` duration: (alert, since).
+    upgrade:       Option<(Alert, i64)>,
+}
+
+/// A lifecycle change produced by one `AlertTracker::update`.
+pub struct Transition {
+    /// Fired, Escalated or Resolved.
+    pub kind:  EventKind,
+    pub alert: Alert,
+    /// Seconds the condition had been firing (Resolved only).
+    pub duration_secs: Option<i64>,
+}
+
+/// Per-condition alert state carried across evaluation ticks.
+///
+/// `alerts::evaluate_with_state` reports what the current sample looks like;
+/// the tracker decides what is actually *firing*: a condition must be seen on
+/// every tick for its `for` duration before it fires, and a severity upgrade
+/// must be sustained just as long. Conditions that disappear are dropped
+/// immediately (hysteresis is applied by the evaluator via `held()`).
+#[derive(Default)]
+pub struct AlertTracker {
+    conds: HashMap<String, Tracked>,
+}
+
+impl AlertTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Severity of every condition currently firing, keyed by `Alert::condition()`.
+    pub fn held(&self) -> HashMap<String, Severity> {
+        self.conds.iter()
+            .filter(|(_, t)| t.firing_since.is_some())
+            .map(|(k, t)| (k.clone(), t.alert.severity.clone()))
+            .collect()
+    }
+
+    /// Feed one tick's raw evaluation; returns the alerts that are firing
+    /// (sorted Critical → Warning → Info) and the lifecycle changes this tick.
+    pub fn update(&mut self, raw: Vec<Alert>, cfg: &AlertConfig, now: i64) -> (Vec<Alert>, Vec<Transition>) {
+        let mut changes: Vec<Transition> = Vec::new();
+        let seen: HashSet<String> = raw.iter().map(|a| a.condition()).collect();
+        self.conds.retain(|k, t| {
+            if seen.contains(k) { return true; }
+            if let Some(since) = t.firing_since {
+                changes.push(Transition {
+                    kind:  EventKind::Resolved,
+                    alert: t.alert.clone(),
+                    duration_secs: Some(now - since),
+                });
+            }
+            false
+        });
+
+        for alert in raw {
+            let hold = cfg.hold_secs(&alert.rule) as i64;
+            let t = self.conds.entry(alert.condition()).or_insert_with(|| Tracked {
+                alert:         alert.clone(),
+                pending_since: now,
+                firing_since:  None,
+                upgrade:       None,
+            });
+            match t.firing_since {
+                None => {
+                    t.alert = alert;
+                    if now - t.pending_since >= hold {
+                        t.firing_since = Some(now);
+                        changes.push(Transition { kind: EventKind::Fired, alert: t.alert.clone(), duration_secs: None });
+                    }
+                }
+                Some(_) if alert.severity > t.alert.severity => {
+                    let since = match &t.upgrade {
+                        Some((u, since)) if u.severity == alert.severity => *since,
+                        _ => now,
+                    };
+                    if now - since >= hold {
+                        t.alert   = alert;
+                        t.upgrade = None;
+                        changes.push(Transition { kind: EventKind::Escalated, alert: t.alert.clone(), duration_secs: None });
+                    } else {
+                        t.upgrade = Some((alert, since));
+                    }
+                }
+                Some(_) => {
+                    t.alert   = alert;
+                    t.upgrade = None;
+                }
+            }
+        }
+
+        let mut firing: Vec<Alert> = self.conds.values()
+            .filter(|t| t.firing_since.is_some())
+            .map(|t| t.alert.clone())
+            .collect();
+        firing.sort_by(|a, b| b.severity.cmp(&a.severity).then_with(|| a.condition().cmp(&b.condition())));
+        (firing, changes)
+    }
+}
+
+impl Transition {
+    /// Structured log record for this change.
+    pub fn event(&self, devices: &[BlockDevice]) -> AlertEvent {
+        let ev = AlertEvent::new(self.kind, &self.alert, devices);
+        match self.duration_secs {
+            Some(d) => ev.with_duration(d),
+            None    => ev,
+        }
+    }
+}
@@ -0,0 +1,158 @@
+use crate::alerts::Alert;
+use crate::config::AlertConfig;
+use crate::models::device::BlockDevice;
+use crate::models::filesystem::Filesystem;
+use crate::models::volume::RaidArray;
+use crate::util::silence::Matcher;
+use std::collections::{HashMap, HashSet};
+
+/// Built-in inhibitions (source rule glob, target rule glob), applied along the
+/// storage stack when `alerts.correlate` is on: a degraded or failed array already
+/// explains slow member disks, and a hung NFS mount explains its RTT.
+pub const BUILTIN_INHIBIT: &[(&str, &str)] = &[
+    ("raid.*",    "io.*"),
+    ("nfs.stale", "nfs.rtt"),
+];
+
+/// How alert targets stack on each other: a filesystem joins the array or disk
+/// it lives on, and a member disk joins its array.
+#[derive(Debug, Default)]
+pub struct Topology {
+    /// Target (device name or mount point) → the target whose incident it belongs to.
+    owner: HashMap<String, String>,
+}
+
+impl Topology {
+    pub fn build(devices: &[BlockDevice], filesystems: &[Filesystem], raids: &[RaidArray]) -> Self {
+        // "sda1" → "sda"; whole disks and unknown names map to themselves
+        let disk_of = |name: &str| -> String {
+            devices.iter()
+                .find(|d| d.name == name || d.partitions.iter().any(|p| p.name == name))
+                .map(|d| d.name.clone())
+                .unwrap_or_else(|| name.to_string())
+        };
+
+        let mut owner = HashMap::new();
+        for arr in raids {
+            for m in &arr.members {
+                owner.insert(disk_of(m), arr.name.clone());
+            }
+        }
+        for fs in filesystems {
+            let short = fs.short_device();
+            let target = if raids.iter().any(|a| a.name == short) { short.to_string() } else { disk_of(short) };
+            if target != fs.mount {
+                owner.insert(fs.mount.clone(), target);
+            }
+        }
+        Self { owner }
+    }
+
+    /// Targets `target` rolls up into, nearest first.
+    fn chain(&self, target: &str) -> Vec<&str> {
+        let mut out: Vec<&str> = Vec::new();
+        let mut cur = target;
+        while let Some(next) = self.owner.get(cur) {
+            if next == target || out.contains(&next.as_str()) { break; }
+            out.push(next);
+            cur = next;
+        }
+        out
+    }
+
+    /// Whether `lower` is `upper` or sits on it.
+    fn within(&self, lower: &str, upper: &str) -> bool {
+        lower == upper || self.chain(lower).contains(&upper)
+    }
+}
+
+fn target(alert: &Alert) -> &str {
+    alert.device.as_deref().or(alert.mount.as_deref()).unwrap_or("")
+}
+
+/// Root-cause grouping and inhibition for one set of firing alerts, keyed by
+/// `Alert::condition()` so it survives escalation.
+#[derive(Debug, Default)]
+pub struct Correlation {
+    /// Condition → condition of the root-cause alert it is grouped under.
+    pub parent:    HashMap<String, String>,
+    /// Condition → condition of the alert inhibiting it.
+    pub inhibited: HashMap<String, String>,
+}
+
+impl Correlation {
+    pub fn parent_of(&self, alert: &Alert) -> Option<&str> {
+        self.parent.get(&alert.condition()).map(String::as_str)
+    }
+
+    pub fn inhibited_by(&self, alert: &Alert) -> Option<&str> {
+        self.inhibited.get(&alert.condition()).map(String::as_str)
+    }
+
+    /// Keys of the inhibited alerts among `alerts` (treated like silenced ones).
+    pub fn inhibited_keys(&self, alerts: &[Alert]) -> HashSet<String> {
+        alerts.iter()
+            .filter(|a| self.inhibited.contains_key(&a.condition()))
+            .map(|a| a.key())
+            .collect()
+    }
+
+    /// Reorder `alerts` so each root-cause alert is directly followed by its children.
+    /// Roots keep their relative (severity) order.
+    pub fn order(&self, alerts: &mut Vec<Alert>) {
+        if self.parent.is_empty() { return; }
+        let present: HashSet<String> = alerts.iter().map(|a| a.condition()).collect();
+        let is_child = |a: &Alert| self.parent.get(&a.condition()).is_some_and(|p| present.contains(p));
+        let (children, roots): (Vec<Alert>, Vec<Alert>) = alerts.drain(..).partition(is_child);
+        for root in roots {
+            let cond = root.condition();
+            alerts.push(root);
+            alerts.extend(children.iter().filter(|c| self.parent.get(&c.condition()) == Some(&cond)).cloned());
+        }
+    }
+}
+
+/// Group `alerts` under their root cause and work out which are inhibited.
+///
+/// An alert's root cause is the topmost alert further up its storage stack:
+/// member-disk and filesystem alerts hang under a firing array alert, and
+/// filesystem alerts under their disk's alert.
+pub fn correlate(alerts: &[Alert], topo: &Topology, cfg: &AlertConfig) -> Correlation {
+    let mut out = Correlation::default();
+    if cfg.correlate {
+        for a in alerts {
+            // Alerts are sorted by severity, so the first one on a target is its worst
+            let root = topo.chain(target(a)).into_iter()
+                .rev()
+                .find_map(|t| alerts.iter().find(|b| target(b) == t));
+            if let Some(root) = root {
+                out.parent.insert(a.condition(), root.condition());
+            }
+        }
+    }
+
+    let builtin = BUILTIN_INHIBIT.iter().filter(|_| cfg.correlate).map(|&(src, dst)| {
+        let src = Matcher { rule: Some(src.to_string()), ..Default::default() };
+        let dst = Matcher { rule: Some(dst.to_string()), ..Default::default() };
+        (src, dst, true)
+    });
+    let user = cfg.inhibit.iter().filter_map(|r| {
+        Some((Matcher::parse(&r.source).ok()?, Matcher::parse(&r.target).ok()?, r.same_stack))
+    });
+    let rules: Vec<(Matcher, Matcher, bool)> = builtin.chain(user).collect();
+
+    for a in alerts {
+        let cond = a.condition();
+        let source = rules.iter().find_map(|(src, dst, same_stack)| {
+            if !dst.matches(a) { return None; }
+            alerts.iter().find(|s| {
+                s.condition() != cond && src.matches(s)
+                    && (!same_stack || topo.within(target(a), target(s)))
+            })
+        });
+        if let Some(s) = source {
+            out.inhibited.insert(cond, s.condition());
+        }
+    }
+    out
+}
@@ -0,0 +1,219 @@
+use crate::config::WebhookOptions;
+use crate::util::{http, lock};
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+/// One webhook POST waiting to be delivered. Persisted in `notify_queue.json`
+/// until it succeeds or runs out of attempts, so restarts don't lose it. The
+/// TUI and the daemon share the file, taking `notify_queue.lock` around each
+/// read-modify-write.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Delivery {
+    /// Endpoint label for the delivery log ("webhook", "escalation").
+    pub endpoint:     String,
+    pub url:          String,
+    pub options:      WebhookOptions,
+    pub body:         String,
+    #[serde(default)]
+    pub attempts:     u32,
+    /// Unix timestamps
+    pub created:      i64,
+    pub next_attempt: i64,
+}
+
+impl Delivery {
+    pub fn new(endpoint: &str, url: &str, options: &WebhookOptions, body: String) -> Self {
+        let now = Local::now().timestamp();
+        Self {
+            endpoint:     endpoint.to_string(),
+            url:          url.to_string(),
+            options:      options.clone(),
+            body,
+            attempts:     0,
+            created:      now,
+            next_attempt: now,
+        }
+    }
+
+    /// Request headers: content type, auth, signature and any configured extras.
+    fn headers(&self) -> Vec<(String, String)> {
+        let opts = &self.options;
+        let mut h = vec![("Content-Type".to_string(), "application/json".to_string())];
+        if !opts.bearer_token.is_empty() {
+            h.push(("Authorization".into(), format!("Bearer {}", opts.bearer_token)));
+        }
+        if !opts.hmac_secret.is_empty() {
+            let ts = Local::now().timestamp().to_string();
+            let sig = http::hmac_sha256_hex(opts.hmac_secret.as_bytes(), format!("{}.{}", ts, self.body).as_bytes());
+            h.push(("X-Dtop-Timestamp".into(), ts));
+            h.push(("X-Dtop-Signature".into(), format!("sha256={}", sig)));
+        }
+        let mut extra: Vec<_> = opts.headers.iter().collect();
+        extra.sort();
+        h.extend(extra.into_iter().map(|(k, v)| (k.clone(), v.clone())));
+        h
+    }
+
+    /// Make one attempt right now.
+    pub fn send(&self) -> anyhow::Result<http::Response> {
+        let timeout = Duration::from_secs(self.options.timeout_secs.max(1));
+        http::post(&self.url, &self.headers(), self.body.as_bytes(), timeout)
+    }
+}
+
+/// Outcome of one attempt, as recorded in `deliveries.jsonl`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DeliveryStatus {
+    Delivered,
+    Retrying,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeliveryRecord {
+    /// RFC 3339 local timestamp.
+    pub ts:          String,
+    pub endpoint:    String,
+    pub url:         String,
+    pub attempt:     u32,
+    pub status:      DeliveryStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub http_status: Option<u16>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error:       Option<String>,
+}
+
+fn queue_path() -> Option<PathBuf> {
+    dirs::data_local_dir().map(|p| p.join("dtop").join("notify_queue.json"))
+}
+
+pub fn log_path() -> Option<PathBuf> {
+    dirs::data_local_dir().map(|p| p.join("dtop").join("deliveries.jsonl"))
+}
+
+/// Wait for an exclusive lock on `notify_queue.lock`, held until the returned
+/// file is dropped. Without a data directory there is no queue to guard.
+fn lock_queue() -> Option<fs::File> {
+    lock::exclusive(&queue_path()?.with_extension("lock"))
+}
+
+fn load_queue() -> Vec<Delivery> {
+    let path = match queue_path() { Some(p) => p, None => return Vec::new() };
+    let text = match fs::read_to_string(&path) { Ok(t) => t, Err(_) => return Vec::new() };
+    serde_json::from_str(&text).unwrap_or_default()
+}
+
+/// Persist pending deliveries (best-effort). The file holds tokens and secrets,
+/// so it is created owner-only.
+fn save_queue(queue: &[Delivery]) {
+    let path = match queue_path() { Some(p) => p, None => return };
+    if let Some(parent) = path.parent() { let _ = fs::create_dir_all(parent); }
+    let Ok(json) = serde_json::to_string(queue) else { return };
+    if let Ok(mut f) = OpenOptions::new().create(true).write(true).truncate(true).mode(0o600).open(&path) {
+        let _ = f.write_all(json.as_bytes());
+    }
+}
+
+/// Append one attempt's outcome to `deliveries.jsonl` (best-effort).
+pub fn record(d: &Delivery, status: DeliveryStatus, result: &anyhow::Result<http::Response>) {
+    let rec = DeliveryRecord {
+        ts:          Local::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, false),
+        endpoint:    d.endpoint.clone(),
+        url:         d.url.clone(),
+        attempt:     d.attempts,
+        status,
+        http_status: result.as_ref().ok().map(|r| r.status),
+        error:       match result {
+            Ok(r) if !r.is_success() => Some(r.body.chars().take(200).collect()),
+            Ok(_)  => None,
+            Err(e) => Some(e.to_string()),
+        },
+    };
+    append(&rec);
+}
+
+/// Append a record to `deliveries.jsonl` (best-effort). Also used by channels
+/// that don't go through the queue, such as email.
+pub fn append(rec: &DeliveryRecord) {
+    let path = match log_path() { Some(p) => p, None => return };
+    if let Some(parent) = path.parent() { let _ = fs::create_dir_all(parent); }
+    if let (Ok(mut file), Ok(line)) = (OpenOptions::new().create(true).append(true).open(&path), serde_json::to_string(rec)) {
+        let _ = writeln!(file, "{}", line);
+    }
+}
+
+fn sender() -> &'static mpsc::Sender<Delivery> {
+    static TX: OnceLock<mpsc::Sender<Delivery>> = OnceLock::new();
+    TX.get_or_init(|| {
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || worker(rx));
+        tx
+    })
+}
+
+/// Start the delivery thread so deliveries left over from a previous run are retried.
+pub fn start() {
+    sender();
+}
+
+/// Hand a delivery to the background thread; it is persisted before the first attempt.
+pub fn enqueue(d: Delivery) {
+    let _ = sender().send(d);
+}
+
+/// Seconds to wait after the n-th failed attempt: 30s, 1m, 2m … capped at 1h.
+fn backoff(attempts: u32) -> i64 {
+    (30i64 << attempts.saturating_sub(1).min(7)).min(3600)
+}
+
+/// The file is the queue: every pass re-reads it under the lock, so entries
+/// added or retried by another dtop process are neither lost nor sent twice.
+fn worker(rx: mpsc::Receiver<Delivery>) {
+    loop {
+        let now  = Local::now().timestamp();
+        let wait = load_queue().iter().map(|d| d.next_attempt - now).min().unwrap_or(60).clamp(0, 60);
+        let incoming: Vec<Delivery> = match rx.recv_timeout(Duration::from_secs(wait as u64)) {
+            Ok(d) => std::iter::once(d).chain(rx.try_iter()).collect(),
+            Err(RecvTimeoutError::Timeout) => Vec::new(),
+            Err(RecvTimeoutError::Disconnected) => return,
+        };
+
+        let _lock = lock_queue();
+        let mut queue = load_queue();
+        if !incoming.is_empty() {
+            queue.extend(incoming);
+            save_queue(&queue);
+        }
+        let now = Local::now().timestamp();
+        let due = queue.iter().any(|d| d.next_attempt <= now);
+        if !due { continue; }
+        queue.retain_mut(|d| d.next_attempt > now || attempt(d, now));
+        save_queue(&queue);
+    }
+}
+
+/// Try `d` once and log the outcome. Returns true if it should stay queued.
+fn attempt(d: &mut Delivery, now: i64) -> bool {
+    d.attempts += 1;
+    let result = d.send();
+    let status = match &result {
+        Ok(r) if r.is_success() => DeliveryStatus::Delivered,
+        // Other client errors (bad token, malformed template) won't fix themselves
+        Ok(r) if (400..500).contains(&r.status) && r.status != 408 && r.status != 429 => DeliveryStatus::Failed,
+        _ if d.attempts >= d.options.max_attempts.max(1) => DeliveryStatus::Failed,
+        _ => DeliveryStatus::Retrying,
+    };
+    if status == DeliveryStatus::Retrying {
+        d.next_attempt = now + backoff(d.attempts);
+    }
+    record(d, status, &result);
+    status == DeliveryStatus::Retrying
+}
@@ -0,0 +1,209 @@
+use crate::alerts::{Alert, Severity};
+use crate::config::ChannelConfig;
+use crate::util::alert_log::{AlertEvent, EventKind};
+use chrono::{DateTime, Local};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+
+/// Rule ID of the notice telling a receiver how many alerts its rate limit held back.
+pub const SUPPRESSED_RULE: &str = "dtop.suppressed";
+/// Rule ID of the daily digest when it goes to a non-email channel.
+pub const DIGEST_RULE: &str = "dtop.digest";
+
+/// Order in which one flush sends its batches.
+const EVENTS: [&str; 4] = ["fired", "reminder", "escalated", "resolved"];
+
+/// One message's worth of alerts for a channel.
+pub struct Batch {
+    pub event:     &'static str,
+    pub alerts:    Vec<Alert>,
+    /// Seconds each resolved condition had been firing, by `Alert::condition()`.
+    pub durations: HashMap<String, i64>,
+}
+
+#[derive(Default)]
+struct Queue {
+    /// (event, alert, firing duration for resolved alerts)
+    pending:    Vec<(&'static str, Alert, Option<i64>)>,
+    /// When the oldest pending alert arrived.
+    since:      i64,
+    /// Send times within the last hour, for `max_per_hour`.
+    sent:       VecDeque<i64>,
+    /// Alerts held back since the last message went out.
+    suppressed: usize,
+}
+
+/// Per-channel batching windows and rate limits. Lives as long as the TUI or
+/// daemon; `notifier::dispatch` feeds it every tick.
+#[derive(Default)]
+pub struct Batcher {
+    queues:     HashMap<String, Queue>,
+    /// Alerts held back on any channel since the last digest.
+    suppressed: usize,
+    /// When each channel last had its firing alerts re-posted.
+    refreshed:  HashMap<String, i64>,
+}
+
+impl Batcher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queue `alerts` for channel `ch`. The batch window starts with the first one.
+    pub fn add(&mut self, ch: &str, event: &'static str, alerts: &[&Alert], durations: &HashMap<String, i64>, now: i64) {
+        if alerts.is_empty() { return; }
+        let q = self.queues.entry(ch.to_string()).or_default();
+        if q.pending.is_empty() { q.since = now; }
+        q.pending.extend(alerts.iter().map(|a| (event, (*a).clone(), durations.get(&a.condition()).copied())));
+    }
+
+    /// What `ch` should send now: one batch per event once its window has passed.
+    /// Over `max_per_hour`, only criticals go out and the rest are counted; the
+    /// count rides along with the next message, or goes on its own as a
+    /// "suppressed" notice once the limit allows. Resolves are exempt: they
+    /// only reach channels that were sent the alert, and close what it opened.
+    pub fn take(&mut self, ch: &ChannelConfig, now: i64) -> Vec<Batch> {
+        let limit = ch.max_per_hour.unwrap_or(0) as usize;
+        let Some(q) = self.queues.get_mut(&ch.name) else { return Vec::new() };
+        while q.sent.front().is_some_and(|&t| now - t >= 3600) { q.sent.pop_front(); }
+
+        let mut out = Vec::new();
+        if !q.pending.is_empty() && now - q.since >= ch.batch_secs() as i64 {
+            let pending = std::mem::take(&mut q.pending);
+            for event in EVENTS {
+                let mut items: Vec<_> = pending.iter().filter(|(e, _, _)| *e == event).collect();
+                if items.is_empty() { continue; }
+                let limited = event != "resolved";
+                if limited && limit > 0 && q.sent.len() >= limit {
+                    let before = items.len();
+                    items.retain(|(_, a, _)| a.severity == Severity::Critical);
+                    q.suppressed     += before - items.len();
+                    self.suppressed  += before - items.len();
+                    if items.is_empty() { continue; }
+                }
+                if limited { q.sent.push_back(now); }
+                out.push(Batch {
+                    event,
+                    alerts:    items.iter().map(|(_, a, _)| a.clone()).collect(),
+                    durations: items.iter().filter_map(|(_, a, d)| d.map(|d| (a.condition(), d))).collect(),
+                });
+            }
+        }
+
+        if q.suppressed > 0 && (limit == 0 || q.sent.len() < limit || !out.is_empty()) {
+            let notice = notice(q.suppressed, limit);
+            q.suppressed = 0;
+            match out.iter_mut().find(|b| b.event == "fired") {
+                Some(b) => b.alerts.push(notice),
+                None => {
+                    q.sent.push_back(now);
+                    out.push(Batch { event: "suppressed", alerts: vec![notice], durations: HashMap::new() });
+                }
+            }
+        }
+        out
+    }
+
+    /// Whether channel `ch` is due to re-post its firing alerts, every `every`
+    /// seconds from the first time it is asked; marks it done if so.
+    pub fn refresh_due(&mut self, ch: &str, every: i64, now: i64) -> bool {
+        let last = self.refreshed.entry(ch.to_string()).or_insert(now);
+        if now - *last < every { return false; }
+        *last = now;
+        true
+    }
+
+    /// Alerts held back by rate limits since the last call (for the digest).
+    pub fn take_suppressed(&mut self) -> usize {
+        std::mem::take(&mut self.suppressed)
+    }
+}
+
+fn notice(n: usize, limit: usize) -> Alert {
+    Alert {
+        severity: Severity::Info,
+        device:   None,
+        mount:    None,
+        rule:     SUPPRESSED_RULE.to_string(),
+        value:    Some(n as f64),
+        message:  format!("{} more alert notification{} held back by this channel's rate limit ({} per hour)",
+            n, if n == 1 { " was" } else { "s were" }, limit),
+    }
+}
+
+#[derive(Default)]
+struct Counts {
+    fired:     usize,
+    crit:      usize,
+    warn:      usize,
+    escalated: usize,
+    resolved:  usize,
+}
+
+impl Counts {
+    fn add(&mut self, ev: &AlertEvent) {
+        match ev.event {
+            EventKind::Fired => {
+                self.fired += 1;
+                match Severity::from_label(&ev.severity) {
+                    Severity::Critical => self.crit += 1,
+                    Severity::Warning  => self.warn += 1,
+                    Severity::Info     => {}
+                }
+            }
+            EventKind::Escalated => self.escalated += 1,
+            EventKind::Resolved  => self.resolved += 1,
+            _ => {}
+        }
+    }
+
+    /// "5 fired (2 CRIT, 3 WARN), 1 escalated, 4 resolved"
+    fn describe(&self) -> String {
+        let mut fired = format!("{} fired", self.fired);
+        let sev: Vec<String> = [(self.crit, "CRIT"), (self.warn, "WARN")].iter()
+            .filter(|(n, _)| *n > 0).map(|(n, l)| format!("{} {}", n, l)).collect();
+        if !sev.is_empty() { fired.push_str(&format!(" ({})", sev.join(", "))); }
+        let mut parts = vec![fired];
+        if self.escalated > 0 { parts.push(format!("{} escalated", self.escalated)); }
+        if self.resolved > 0  { parts.push(format!("{} resolved", self.resolved)); }
+        parts.join(", ")
+    }
+}
+
+/// The daily digest: alert activity in `events` since `since`, counted per
+/// device (or mount), what is still firing, and what rate limits held back.
+/// Returns a one-line summary (for subjects) and the full text.
+pub fn daily(events: &[AlertEvent], since: DateTime<Local>, active: &[Alert], suppressed: usize, host: &str) -> (String, String) {
+    let mut total = Counts::default();
+    let mut per: BTreeMap<String, Counts> = BTreeMap::new();
+    let counted = |e: &&AlertEvent| matches!(e.event, EventKind::Fired | EventKind::Escalated | EventKind::Resolved);
+    for ev in events.iter().filter(counted).filter(|e| e.time().is_some_and(|t| t >= since)) {
+        let target = ev.device.clone().or_else(|| ev.mount.clone()).unwrap_or_else(|| "(system)".to_string());
+        total.add(ev);
+        per.entry(target).or_default().add(ev);
+    }
+
+    let summary = format!("{} alert{} fired, {} still firing", total.fired, if total.fired == 1 { "" } else { "s" }, active.len());
+    let mut out = format!("dtop daily digest for {}, {} – {}\n\n", host,
+        since.format("%Y-%m-%d %H:%M"), Local::now().format("%Y-%m-%d %H:%M"));
+    out.push_str(&format!("Total: {}\n", total.describe()));
+    if !per.is_empty() {
+        out.push('\n');
+        let mut rows: Vec<_> = per.iter().collect();
+        rows.sort_by(|a, b| b.1.fired.cmp(&a.1.fired).then(a.0.cmp(b.0)));
+        let width = rows.iter().map(|(t, _)| t.chars().count()).max().unwrap_or(0).clamp(8, 40);
+        for (target, c) in rows {
+            out.push_str(&format!("  {:<width$}  {}\n", target, c.describe(), width = width));
+        }
+    }
+    if !active.is_empty() {
+        out.push_str(&format!("\nStill firing ({}):\n", active.len()));
+        for a in active {
+            out.push_str(&format!("  [{}] {}{}\n", a.severity.label(), a.prefix(), a.message));
+        }
+    }
+    if suppressed > 0 {
+        out.push_str(&format!("\n{} alert notification{} held back by channel rate limits.\n",
+            suppressed, if suppressed == 1 { " was" } else { "s were" }));
+    }
+    (summary, out)
+}
@@ -0,0 +1,339 @@
+use regex::Regex;
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+
+/// A value produced while evaluating a rule expression.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Num(f64),
+    Str(String),
+}
+
+impl Value {
+    pub fn truthy(&self) -> bool {
+        match self {
+            Value::Null    => false,
+            Value::Bool(b) => *b,
+            Value::Num(n)  => *n != 0.0,
+            Value::Str(s)  => !s.is_empty(),
+        }
+    }
+
+    /// Render for message templates: integers without a trailing ".0", floats with one decimal.
+    pub fn display(&self) -> String {
+        match self {
+            Value::Null    => "—".to_string(),
+            Value::Bool(b) => b.to_string(),
+            Value::Num(n) if n.fract() == 0.0 && n.abs() < 1e15 => format!("{}", *n as i64),
+            Value::Num(n)  => format!("{:.1}", n),
+            Value::Str(s)  => s.clone(),
+        }
+    }
+}
+
+/// Variable bindings for one evaluation: "device.temperature" → Num(41.0), ...
+pub type Context = HashMap<String, Value>;
+
+/// Binary operators, lowest to highest precedence.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Op {
+    Or, And,
+    Eq, Ne, Lt, Le, Gt, Ge, Match, NotMatch,
+    Add, Sub, Mul, Div,
+}
+
+/// Parsed expression tree.
+#[derive(Debug, Clone)]
+pub enum Expr {
+    Lit(Value),
+    Var(String),
+    Not(Box<Expr>),
+    Neg(Box<Expr>),
+    Bin(Op, Box<Expr>, Box<Expr>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Tok {
+    Num(f64),
+    Str(String),
+    Ident(String),
+    Op(Op),
+    Not,
+    LParen,
+    RParen,
+}
+
+fn tokenize(src: &str) -> Result<Vec<Tok>, String> {
+    let chars: Vec<char> = src.chars().collect();
+    let mut toks = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() { i += 1; continue; }
+
+        let two: String = chars[i..(i + 2).min(chars.len())].iter().collect();
+        let op2 = match two.as_str() {
+            "||" => Some(Op::Or),
+            "&&" => Some(Op::And),
+            "==" => Some(Op::Eq),
+            "!=" => Some(Op::Ne),
+            "<=" => Some(Op::Le),
+            ">=" => Some(Op::Ge),
+            "=~" => Some(Op::Match),
+            "!~" => Some(Op::NotMatch),
+            _    => None,
+        };
+        if let Some(op) = op2 {
+            toks.push(Tok::Op(op));
+            i += 2;
+            continue;
+        }
+
+        match c {
+            '(' => { toks.push(Tok::LParen); i += 1; }
+            ')' => { toks.push(Tok::RParen); i += 1; }
+            '!' => { toks.push(Tok::Not); i += 1; }
+            '<' => { toks.push(Tok::Op(Op::Lt)); i += 1; }
+            '>' => { toks.push(Tok::Op(Op::Gt)); i += 1; }
+            '+' => { toks.push(Tok::Op(Op::Add)); i += 1; }
+            '-' => { toks.push(Tok::Op(Op::Sub)); i += 1; }
+            '*' => { toks.push(Tok::Op(Op::Mul)); i += 1; }
+            '/' => { toks.push(Tok::Op(Op::Div)); i += 1; }
+            '"' | '\'' => {
+                let quote = c;
+                let mut s = String::new();
+                i += 1;
+                loop {
+                    match chars.get(i) {
+                        None => return Err("unterminated string literal".into()),
+                        Some(&ch) if ch == quote => { i += 1; break; }
+                        Some('\\') => {
+                            // Keep regex escapes intact; only unescape the quote and backslash itself
+                            match chars.get(i + 1) {
+                                Some(&n) if n == quote || n == '\\' => { s.push(n); i += 2; }
+                                _ => { s.push('\\'); i += 1; }
+                            }
+                        }
+                        Some(&ch) => { s.push(ch); i += 1; }
+                    }
+                }
+                toks.push(Tok::Str(s));
+            }
+            _ if c.is_ascii_digit() || (c == '.' && chars.get(i + 1).is_some_and(|n| n.is_ascii_digit())) => {
+                let start = i;
+                while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') { i += 1; }
+                let text: String = chars[start..i].iter().collect();
+                let n = text.parse::<f64>().map_err(|_| format!("bad number '{}'", text))?;
+                toks.push(Tok::Num(n));
+            }
+            _ if c.is_ascii_alphabetic() || c == '_' => {
+                let start = i;
+                while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_' || chars[i] == '.') {
+                    i += 1;
+                }
+                toks.push(Tok::Ident(chars[start..i].iter().collect()));
+            }
+            _ => return Err(format!("unexpected character '{}'", c)),
+        }
+    }
+    Ok(toks)
+}
+
+struct Parser {
+    toks: Vec<Tok>,
+    pos:  usize,
+}
+
+impl Parser {
+    fn peek_op(&self) -> Option<Op> {
+        match self.toks.get(self.pos) { Some(Tok::Op(op)) => Some(*op), _ => None }
+    }
+
+    fn binary(&mut self, ops: &[Op], next: fn(&mut Self) -> Result<Expr, String>) -> Result<Expr, String> {
+        let mut lhs = next(self)?;
+        while let Some(op) = self.peek_op().filter(|o| ops.contains(o)) {
+            self.pos += 1;
+            let rhs = next(self)?;
+            lhs = Expr::Bin(op, Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    fn or(&mut self)   -> Result<Expr, String> { self.binary(&[Op::Or], Self::and) }
+    fn and(&mut self)  -> Result<Expr, String> { self.binary(&[Op::And], Self::not) }
+    fn sum(&mut self)  -> Result<Expr, String> { self.binary(&[Op::Add, Op::Sub], Self::prod) }
+    fn prod(&mut self) -> Result<Expr, String> { self.binary(&[Op::Mul, Op::Div], Self::unary) }
+
+    fn not(&mut self) -> Result<Expr, String> {
+        if self.toks.get(self.pos) == Some(&Tok::Not) {
+            self.pos += 1;
+            return Ok(Expr::Not(Box::new(self.not()?)));
+        }
+        self.cmp()
+    }
+
+    fn cmp(&mut self) -> Result<Expr, String> {
+        const CMP: [Op; 8] = [Op::Eq, Op::Ne, Op::Lt, Op::Le, Op::Gt, Op::Ge, Op::Match, Op::NotMatch];
+        let lhs = self.sum()?;
+        if let Some(op) = self.peek_op().filter(|o| CMP.contains(o)) {
+            self.pos += 1;
+            let rhs = self.sum()?;
+            return Ok(Expr::Bin(op, Box::new(lhs), Box::new(rhs)));
+        }
+        Ok(lhs)
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        if self.peek_op() == Some(Op::Sub) {
+            self.pos += 1;
+            return Ok(Expr::Neg(Box::new(self.unary()?)));
+        }
+        self.atom()
+    }
+
+    fn atom(&mut self) -> Result<Expr, String> {
+        let tok = self.toks.get(self.pos).cloned().ok_or("unexpected end of expression")?;
+        self.pos += 1;
+        match tok {
+            Tok::Num(n) => Ok(Expr::Lit(Value::Num(n))),
+            Tok::Str(s) => Ok(Expr::Lit(Value::Str(s))),
+            Tok::Ident(id) => Ok(match id.as_str() {
+                "true"  => Expr::Lit(Value::Bool(true)),
+                "false" => Expr::Lit(Value::Bool(false)),
+                "null"  => Expr::Lit(Value::Null),
+                _       => Expr::Var(id),
+            }),
+            Tok::LParen => {
+                let e = self.or()?;
+                if self.toks.get(self.pos) != Some(&Tok::RParen) {
+                    return Err("missing ')'".into());
+                }
+                self.pos += 1;
+                Ok(e)
+            }
+            other => Err(format!("unexpected token {:?}", other)),
+        }
+    }
+}
+
+/// `pattern` compiled, once per process (`None` if invalid). Patterns come
+/// from the config, so the cache stays small.
+pub fn regex(pattern: &str) -> Option<Regex> {
+    static COMPILED: OnceLock<Mutex<HashMap<String, Option<Regex>>>> = OnceLock::new();
+    let mut compiled = COMPILED.get_or_init(|| Mutex::new(HashMap::new())).lock().ok()?;
+    compiled.entry(pattern.to_string()).or_insert_with(|| Regex::new(pattern).ok()).clone()
+}
+
+/// Parse an expression such as `device.write_latency_ms > 30 && device.type == "HDD"`.
+pub fn parse(src: &str) -> Result<Expr, String> {
+    let toks = tokenize(src)?;
+    if toks.is_empty() { return Err("empty expression".into()); }
+    let mut p = Parser { toks, pos: 0 };
+    let e = p.or()?;
+    if p.pos < p.toks.len() {
+        return Err(format!("unexpected trailing token {:?}", p.toks[p.pos]));
+    }
+    Ok(e)
+}
+
+impl Expr {
+    /// Evaluate against `ctx`. Unknown variables are Null; comparisons involving Null are false.
+    pub fn eval(&self, ctx: &Context) -> Value {
+        match self {
+            Expr::Lit(v) => v.clone(),
+            Expr::Var(name) => ctx.get(name).cloned().unwrap_or(Value::Null),
+            Expr::Not(e) => Value::Bool(!e.eval(ctx).truthy()),
+            Expr::Neg(e) => match e.eval(ctx) {
+                Value::Num(n) => Value::Num(-n),
+                _             => Value::Null,
+            },
+            Expr::Bin(Op::Or, a, b)  => Value::Bool(a.eval(ctx).truthy() || b.eval(ctx).truthy()),
+            Expr::Bin(Op::And, a, b) => Value::Bool(a.eval(ctx).truthy() && b.eval(ctx).truthy()),
+            Expr::Bin(op, a, b) => binary(*op, a.eval(ctx), b.eval(ctx)),
+        }
+    }
+
+    /// True if any variable in the expression starts with `prefix` (e.g. "fs.").
+    pub fn references(&self, prefix: &str) -> bool {
+        match self {
+            Expr::Lit(_) => false,
+            Expr::Var(name) => name.starts_with(prefix),
+            Expr::Not(e) | Expr::Neg(e) => e.references(prefix),
+            Expr::Bin(_, a, b) => a.references(prefix) || b.references(prefix),
+        }
+    }
+}
+
+fn binary(op: Op, a: Value, b: Value) -> Value {
+    use Value::*;
+    match op {
+        Op::Eq | Op::Ne => {
+            let eq = match (&a, &b) {
+                (Null, _) | (_, Null) => return Bool(false),
+                (Num(x), Num(y))   => x == y,
+                (Str(x), Str(y))   => x == y,
+                (Bool(x), Bool(y)) => x == y,
+                // Allow `device.attr.5 == "0"` style comparisons
+                (Num(x), Str(y)) | (Str(y), Num(x)) => y.parse::<f64>().map(|y| *x == y).unwrap_or(false),
+                _ => false,
+            };
+            Bool(if op == Op::Eq { eq } else { !eq })
+        }
+        Op::Lt | Op::Le | Op::Gt | Op::Ge => {
+            let ord = match (&a, &b) {
+                (Num(x), Num(y)) => x.partial_cmp(y),
+                (Str(x), Str(y)) => Some(x.cmp(y)),
+                _ => None,
+            };
+            let Some(ord) = ord else { return Bool(false) };
+            Bool(match op {
+                Op::Lt => ord.is_lt(),
+                Op::Le => ord.is_le(),
+                Op::Gt => ord.is_gt(),
+                _      => ord.is_ge(),
+            })
+        }
+        Op::Match | Op::NotMatch => {
+            let (Str(text), Str(pat)) = (&a, &b) else { return Bool(false) };
+            let hit = regex(pat).is_some_and(|re| re.is_match(text));
+            Bool(if op == Op::Match { hit } else { !hit })
+        }
+        Op::Add | Op::Sub | Op::Mul | Op::Div => {
+            let (Num(x), Num(y)) = (a, b) else { return Null };
+            match op {
+                Op::Add => Num(x + y),
+                Op::Sub => Num(x - y),
+                Op::Mul => Num(x * y),
+                _ if y == 0.0 => Null,
+                _ => Num(x / y),
+            }
+        }
+        Op::Or | Op::And => unreachable!("short-circuit ops handled in Expr::eval"),
+    }
+}
+
+/// Expand `{var.name}` placeholders in a message template from `ctx`.
+/// Unknown placeholders are left as-is so typos are visible in the alert text.
+pub fn render_template(template: &str, ctx: &Context) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        match after.find('}') {
+            Some(end) => {
+                let name = &after[..end];
+                match ctx.get(name) {
+                    Some(v) => out.push_str(&v.display()),
+                    None    => { out.push('{'); out.push_str(name); out.push('}'); }
+                }
+                rest = &after[end + 1..];
+            }
+            None => { out.push_str(&rest[start..]); rest = ""; }
+        }
+    }
+    out.push_str(rest);
+    out
+}
@@ -0,0 +1,32 @@
+/// Shell-style wildcard match: `*` matches any run of characters, `?` exactly one.
+/// Used by rule selectors ("sd*", "/var/*") where full regexes would be overkill.
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let p: Vec<char> = pattern.chars().collect();
+    let t: Vec<char> = text.chars().collect();
+    let (mut pi, mut ti) = (0usize, 0usize);
+    let mut star: Option<(usize, usize)> = None;
+
+    while ti < t.len() {
+        if pi < p.len() && (p[pi] == '?' || p[pi] == t[ti]) {
+            pi += 1;
+            ti += 1;
+        } else if pi < p.len() && p[pi] == '*' {
+            star = Some((pi, ti));
+            pi += 1;
+        } else if let Some((sp, st)) = star {
+            // Backtrack: let the last '*' swallow one more character
+            pi = sp + 1;
+            ti = st + 1;
+            star = Some((sp, st + 1));
+        } else {
+            return false;
+        }
+    }
+    p[pi..].iter().all(|&c| c == '*')
+}
+
+/// True if `patterns` is empty (no selector = match everything) or any pattern matches one of `candidates`.
+pub fn any_match(patterns: &[String], candidates: &[&str]) -> bool {
+    patterns.is_empty()
+        || patterns.iter().any(|pat| candidates.iter().any(|c| glob_match(pat, c)))
+}
@@ -3,6 +3,10 @@ use crate::models::smart::SmartStatus;
 use ratatui::style::Style;
 use crate::ui::theme::Theme;
 
+/// Scores below these show as warning / critical (colours, `--check` perfdata).
+pub const WARN_BELOW: u8 = 80;
+pub const CRIT_BELOW: u8 = 50;
+
 /// Compute a 0-100 health score from SMART data.
 /// 100 = pristine / unscored (no SMART data), 0 = hard failure.
 /// Points are deducted for bad attributes, high temperature, and NVMe wear.
@@ -75,8 +79,8 @@ pub fn has_smart(dev: &BlockDevice) -> bool {
 
 /// Color style for a health score value.
 pub fn score_style(score: u8, theme: &Theme) -> Style {
-    if score >= 80 { theme.ok }
-    else if score >= 50 { theme.warn }
+    if score >= WARN_BELOW { theme.ok }
+    else if score >= CRIT_BELOW { theme.warn }
     else { theme.crit }
 }
 
@@ -0,0 +1,231 @@
+use crate::alerts::Alert;
+use crate::config::ChannelConfig;
+use crate::models::device::BlockDevice;
+use crate::util::alert_log::{self, AlertEvent, EventKind, HookRun};
+use crate::util::correlate::Correlation;
+use crate::util::{human, webhook};
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::os::fd::{FromRawFd, OwnedFd};
+use std::os::unix::process::CommandExt;
+use std::process::{Command, Stdio};
+use std::sync::mpsc;
+use std::sync::{Condvar, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// Command behind `type = "desktop"` channels (and the legacy `notify_send`).
+pub const DESKTOP_COMMAND: &str = r#"case "$DTOP_SEVERITY" in CRIT) u=critical ;; WARN) u=normal ;; *) u=low ;; esac
+exec notify-send --urgency "$u" --app-name dtop "dtop: $DTOP_EVENT" "$DTOP_TEXT""#;
+
+/// Whether a desktop session is reachable (a D-Bus session bus or a display),
+/// without which notify-send can only fail.
+pub fn desktop_session() -> bool {
+    ["DBUS_SESSION_BUS_ADDRESS", "DISPLAY", "WAYLAND_DISPLAY"].iter()
+        .any(|v| std::env::var_os(v).is_some_and(|v| !v.is_empty()))
+}
+
+/// Largest amount of command output kept for the alert log.
+const MAX_OUTPUT: usize = 4096;
+
+/// One exec run, prepared on the calling thread.
+pub struct Hook {
+    channel:  String,
+    command:  String,
+    trigger:  String,
+    env:      Vec<(&'static str, String)>,
+    stdin:    String,
+    event:    AlertEvent,
+    timeout:  Duration,
+    limit:    usize,
+    /// Log every run (exec channels) or only failures (desktop).
+    log_all:  bool,
+}
+
+impl Hook {
+    /// Describe `alert` for `ch`'s command: `DTOP_*` variables plus the alert-log
+    /// event (with `event`, `host` and `channel`) as JSON on stdin.
+    pub fn new(
+        ch:       &ChannelConfig,
+        command:  &str,
+        alert:    &Alert,
+        event:    &str,
+        duration: Option<i64>,
+        corr:     &Correlation,
+        devices:  &[BlockDevice],
+    ) -> Self {
+        let mut ev = AlertEvent::new(EventKind::Hook, alert, devices).with_correlation(corr, alert);
+        ev.duration_secs = duration;
+        let host = webhook::hostname();
+        let mut text = format!("[{}] {}{}", alert.severity.label(), alert.prefix(), alert.message);
+        if let Some(d) = duration {
+            text.push_str(&format!(" (after {})", human::fmt_duration_short(d.max(0) as u64)));
+        }
+
+        let mut json = serde_json::to_value(&ev).unwrap_or_default();
+        if let Some(obj) = json.as_object_mut() {
+            obj.insert("event".into(),   event.into());
+            obj.insert("host".into(),    host.clone().into());
+            obj.insert("channel".into(), ch.name.clone().into());
+        }
+        let opt = |v: &Option<String>| v.clone().unwrap_or_default();
+        let env = vec![
+            ("DTOP_EVENT",    event.to_string()),
+            ("DTOP_SEVERITY", alert.severity.label().to_string()),
+            ("DTOP_DEVICE",   opt(&alert.device)),
+            ("DTOP_SERIAL",   opt(&ev.serial)),
+            ("DTOP_MOUNT",    opt(&alert.mount)),
+            ("DTOP_RULE",     alert.rule.clone()),
+            ("DTOP_VALUE",    alert.value.map(|v| v.to_string()).unwrap_or_default()),
+            ("DTOP_MESSAGE",  alert.message.clone()),
+            ("DTOP_TEXT",     text),
+            ("DTOP_PARENT",   opt(&ev.parent)),
+            ("DTOP_DURATION", duration.map(|d| d.to_string()).unwrap_or_default()),
+            ("DTOP_HOST",     host),
+            ("DTOP_CHANNEL",  ch.name.clone()),
+        ];
+        Self {
+            channel:  ch.name.clone(),
+            command:  command.to_string(),
+            trigger:  event.to_string(),
+            env,
+            stdin:    json.to_string(),
+            event:    ev,
+            timeout:  Duration::from_secs(ch.webhook.timeout_secs.max(1)),
+            limit:    ch.max_concurrent.max(1),
+            log_all:  true,
+        }
+    }
+
+    /// Replace `DTOP_TEXT`, e.g. with a summary of a whole batch.
+    pub fn with_text(mut self, text: String) -> Self {
+        if let Some((_, v)) = self.env.iter_mut().find(|(k, _)| *k == "DTOP_TEXT") { *v = text; }
+        self
+    }
+
+    /// Only record failed runs in the alert log.
+    pub fn log_failures_only(mut self) -> Self {
+        self.log_all = false;
+        self
+    }
+
+    /// Run on a background thread once a slot on the channel is free.
+    pub fn spawn(self) -> JoinHandle<()> {
+        std::thread::spawn(move || {
+            let _slot = Slot::acquire(&self.channel, self.limit);
+            let run = self.run();
+            if self.log_all || run.timed_out || run.exit_code != Some(0) {
+                let mut ev = self.event;
+                ev.ts   = chrono::Local::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, false);
+                ev.hook = Some(run);
+                alert_log::append(&[ev]);
+            }
+        })
+    }
+
+    fn run(&self) -> HookRun {
+        let mut out = HookRun {
+            channel:   self.channel.clone(),
+            trigger:   self.trigger.clone(),
+            exit_code: None,
+            timed_out: false,
+            output:    String::new(),
+        };
+        // One pipe for stdout and stderr keeps their lines in order
+        let Some((reader, writer)) = pipe() else {
+            out.output = "cannot create pipe".into();
+            return out;
+        };
+        let writer2 = match writer.try_clone() { Ok(w) => w, Err(e) => { out.output = e.to_string(); return out; } };
+        let spawned = Command::new("sh").arg("-c").arg(&self.command)
+            .envs(self.env.iter().map(|(k, v)| (*k, v)))
+            .stdin(Stdio::piped()).stdout(Stdio::from(writer)).stderr(Stdio::from(writer2))
+            .process_group(0)
+            .spawn();
+        let mut child = match spawned {
+            Ok(c)  => c,
+            Err(e) => { out.output = e.to_string(); return out; }
+        };
+
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            let mut file = std::fs::File::from(reader);
+            let mut buf = Vec::new();
+            let mut chunk = [0u8; 4096];
+            while let Ok(n) = file.read(&mut chunk) {
+                if n == 0 { break; }
+                if buf.len() < MAX_OUTPUT { buf.extend_from_slice(&chunk[..n]); }
+            }
+            let _ = tx.send(buf);
+        });
+        if let Some(mut stdin) = child.stdin.take() {
+            let _ = stdin.write_all(self.stdin.as_bytes());
+        }
+
+        let deadline = Instant::now() + self.timeout;
+        loop {
+            match child.try_wait() {
+                Ok(Some(status)) => { out.exit_code = status.code(); break; }
+                Ok(None) if Instant::now() < deadline => std::thread::sleep(Duration::from_millis(50)),
+                _ => {
+                    // The whole group, so a script's own children go too
+                    unsafe { libc::kill(-(child.id() as i32), libc::SIGKILL) };
+                    let _ = child.wait();
+                    out.timed_out = true;
+                    break;
+                }
+            }
+        }
+        // Background children may keep the pipe open; don't wait for them
+        if let Ok(buf) = rx.recv_timeout(Duration::from_secs(1)) {
+            let mut text = String::from_utf8_lossy(&buf[..buf.len().min(MAX_OUTPUT)]).into_owned();
+            text.truncate(text.trim_end().len());
+            out.output = text;
+        }
+        out
+    }
+}
+
+/// A close-on-exec pipe as (read end, write end).
+fn pipe() -> Option<(OwnedFd, OwnedFd)> {
+    let mut fds = [0; 2];
+    if unsafe { libc::pipe2(fds.as_mut_ptr(), libc::O_CLOEXEC) } != 0 {
+        return None;
+    }
+    // SAFETY: pipe2 just returned these descriptors and nothing else owns them
+    Some(unsafe { (OwnedFd::from_raw_fd(fds[0]), OwnedFd::from_raw_fd(fds[1])) })
+}
+
+/// Running commands per channel, for `max_concurrent`.
+static RUNNING: (Mutex<Option<HashMap<String, usize>>>, Condvar) = (Mutex::new(None), Condvar::new());
+
+/// A held run slot on a channel, released on drop.
+struct Slot {
+    channel: String,
+}
+
+impl Slot {
+    fn acquire(channel: &str, limit: usize) -> Self {
+        let (lock, cvar) = &RUNNING;
+        let mut guard = lock.lock().unwrap_or_else(|e| e.into_inner());
+        loop {
+            let running = guard.get_or_insert_with(HashMap::new).entry(channel.to_string()).or_insert(0);
+            if *running < limit {
+                *running += 1;
+                return Self { channel: channel.to_string() };
+            }
+            guard = cvar.wait(guard).unwrap_or_else(|e| e.into_inner());
+        }
+    }
+}
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        let (lock, cvar) = &RUNNING;
+        let mut guard = lock.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(n) = guard.get_or_insert_with(HashMap::new).get_mut(&self.channel) {
+            *n = n.saturating_sub(1);
+        }
+        cvar.notify_all();
+    }
+}
@@ -0,0 +1,286 @@
+use anyhow::{anyhow, bail, Context, Result};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::io::{Read, Write};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+/// Largest response body kept (the rest is read and discarded).
+const MAX_BODY: usize = 64 * 1024;
+
+/// Status and (truncated) body of an HTTP response.
+#[derive(Debug)]
+pub struct Response {
+    pub status: u16,
+    pub body:   String,
+}
+
+impl Response {
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+}
+
+/// A parsed `http://` or `https://` URL.
+struct Url {
+    tls:  bool,
+    host: String,
+    port: u16,
+    /// Path and query, always starting with '/'
+    path: String,
+}
+
+fn parse_url(url: &str) -> Result<Url> {
+    let (tls, rest) = if let Some(r) = url.strip_prefix("https://") {
+        (true, r)
+    } else if let Some(r) = url.strip_prefix("http://") {
+        (false, r)
+    } else {
+        bail!("unsupported URL (expected http:// or https://): {}", url);
+    };
+    let (authority, path) = match rest.find(['/', '?']) {
+        Some(i) if rest[i..].starts_with('/') => (&rest[..i], rest[i..].to_string()),
+        Some(i) => (&rest[..i], format!("/{}", &rest[i..])),
+        None    => (rest, "/".to_string()),
+    };
+    let default_port = if tls { 443 } else { 80 };
+    // "[::1]:8080", "host:8080", "host"
+    let (host, port) = if let Some(v6) = authority.strip_prefix('[') {
+        let (h, tail) = v6.split_once(']').ok_or_else(|| anyhow!("bad IPv6 host in {}", url))?;
+        let port = match tail.strip_prefix(':') {
+            Some(p) => p.parse().with_context(|| format!("bad port in {}", url))?,
+            None    => default_port,
+        };
+        (h.to_string(), port)
+    } else {
+        match authority.rsplit_once(':') {
+            Some((h, p)) => (h.to_string(), p.parse().with_context(|| format!("bad port in {}", url))?),
+            None         => (authority.to_string(), default_port),
+        }
+    };
+    if host.is_empty() { bail!("missing host in {}", url); }
+    Ok(Url { tls, host, port, path })
+}
+
+impl Url {
+    /// The Host header value: the port only when it isn't the scheme's
+    /// default, IPv6 literals in brackets.
+    fn host_header(&self) -> String {
+        let host = if self.host.contains(':') { format!("[{}]", self.host) } else { self.host.clone() };
+        if self.port == if self.tls { 443 } else { 80 } { host } else { format!("{}:{}", host, self.port) }
+    }
+}
+
+pub fn tls_config() -> Arc<rustls::ClientConfig> {
+    static CONFIG: OnceLock<Arc<rustls::ClientConfig>> = OnceLock::new();
+    CONFIG.get_or_init(|| {
+        let roots = rustls::RootCertStore { roots: webpki_roots::TLS_SERVER_ROOTS.to_vec() };
+        let cfg = rustls::ClientConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+            .with_safe_default_protocol_versions()
+            .expect("ring supports the default protocol versions")
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+        Arc::new(cfg)
+    }).clone()
+}
+
+/// POST `body` to `url` and wait for the response. `timeout` applies to the
+/// connect and to each read/write. The connection is closed afterwards.
+pub fn post(url: &str, headers: &[(String, String)], body: &[u8], timeout: Duration) -> Result<Response> {
+    let url = parse_url(url)?;
+    let tcp = crate::util::net::connect(&url.host, url.port, timeout)?;
+
+    let mut req = format!(
+        "POST {} HTTP/1.1\r\nHost: {}\r\nUser-Agent: dtop/{}\r\nContent-Length: {}\r\nConnection: close\r\n",
+        url.path, url.host_header(), env!("CARGO_PKG_VERSION"), body.len(),
+    );
+    for (k, v) in headers {
+        if k.contains(['\r', '\n', ':']) || v.contains(['\r', '\n']) {
+            bail!("invalid header {:?}", k);
+        }
+        req.push_str(&format!("{}: {}\r\n", k, v));
+    }
+    req.push_str("\r\n");
+    let mut raw = req.into_bytes();
+    raw.extend_from_slice(body);
+
+    let response = if url.tls {
+        let name = rustls::pki_types::ServerName::try_from(url.host.clone())
+            .map_err(|_| anyhow!("invalid TLS server name {}", url.host))?;
+        let conn = rustls::ClientConnection::new(tls_config(), name)?;
+        let mut stream = rustls::StreamOwned::new(conn, tcp);
+        exchange(&mut stream, &raw)?
+    } else {
+        let mut stream = tcp;
+        exchange(&mut stream, &raw)?
+    };
+    parse_response(&response)
+}
+
+fn exchange<S: Read + Write>(stream: &mut S, request: &[u8]) -> Result<Vec<u8>> {
+    stream.write_all(request)?;
+    stream.flush()?;
+    let mut out = Vec::new();
+    let mut buf = [0u8; 8192];
+    // Stop once the framed response is in rather than waiting for the server
+    // to close, which a keep-alive server or proxy may never do
+    while !complete(&out) {
+        match stream.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => if out.len() < MAX_BODY { out.extend_from_slice(&buf[..n]) },
+            // Many servers close TLS without close_notify once the response is sent
+            Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof && !out.is_empty() => break,
+            // A status line is enough to report; don't fail on a body that never ends
+            Err(e) if matches!(e.kind(), std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut)
+                && out.windows(2).any(|w| w == b"\r\n") => break,
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(out)
+}
+
+/// Split a raw response into its header block and body, once the headers are in.
+fn split_head(raw: &[u8]) -> Option<(String, &[u8])> {
+    let end = raw.windows(4).position(|w| w == b"\r\n\r\n")?;
+    Some((String::from_utf8_lossy(&raw[..end]).to_ascii_lowercase(), &raw[end + 4..]))
+}
+
+fn header<'a>(head: &'a str, name: &str) -> Option<&'a str> {
+    head.lines().skip(1)
+        .filter_map(|l| l.split_once(':'))
+        .find(|(k, _)| k.trim() == name)
+        .map(|(_, v)| v.trim())
+}
+
+/// Whether `raw` holds the whole response, going by Content-Length or the
+/// final chunk. Responses framed only by the connection closing never are.
+fn complete(raw: &[u8]) -> bool {
+    let Some((head, body)) = split_head(raw) else { return false };
+    let status = head.split_whitespace().nth(1).unwrap_or("");
+    if status.starts_with('1') || status == "204" || status == "304" {
+        return true;
+    }
+    if header(&head, "transfer-encoding").is_some_and(|v| v.contains("chunked")) {
+        return dechunk(body).is_some();
+    }
+    match header(&head, "content-length").and_then(|v| v.parse::<usize>().ok()) {
+        Some(len) => body.len() >= len,
+        None      => false,
+    }
+}
+
+/// Decode a chunked body; None until the final zero-length chunk has arrived.
+fn dechunk(mut body: &[u8]) -> Option<Vec<u8>> {
+    let mut out = Vec::new();
+    loop {
+        let eol = body.windows(2).position(|w| w == b"\r\n")?;
+        let size = String::from_utf8_lossy(&body[..eol]);
+        let size = usize::from_str_radix(size.split(';').next()?.trim(), 16).ok()?;
+        body = &body[eol + 2..];
+        if size == 0 { return Some(out); }
+        out.extend_from_slice(body.get(..size)?);
+        body = body.get(size + 2..)?;
+    }
+}
+
+fn parse_response(raw: &[u8]) -> Result<Response> {
+    let text = String::from_utf8_lossy(raw);
+    let status_line = text.lines().next().unwrap_or("");
+    let status = status_line.split_whitespace().nth(1)
+        .and_then(|s| s.parse().ok())
+        .ok_or_else(|| anyhow!("malformed HTTP response: {:?}", status_line))?;
+    let body = match split_head(raw) {
+        Some((head, body)) if header(&head, "transfer-encoding").is_some_and(|v| v.contains("chunked")) =>
+            dechunk(body).map(|b| String::from_utf8_lossy(&b).trim().to_string()).unwrap_or_default(),
+        Some((_, body)) => String::from_utf8_lossy(body).trim().to_string(),
+        None            => String::new(),
+    };
+    Ok(Response { status, body })
+}
+
+/// Lower-case hex HMAC-SHA256 of `msg` under `key`.
+pub fn hmac_sha256_hex(key: &[u8], msg: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(msg);
+    mac.finalize().into_bytes().iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+
+    #[test]
+    fn host_header_carries_non_default_port() {
+        let host = |u: &str| parse_url(u).unwrap().host_header();
+        assert_eq!(host("http://example.com/hook"), "example.com");
+        assert_eq!(host("https://example.com:443/hook"), "example.com");
+        assert_eq!(host("http://example.com:8080/hook"), "example.com:8080");
+        assert_eq!(host("https://[::1]/hook"), "[::1]");
+        assert_eq!(host("http://[::1]:9093/api/v2/alerts"), "[::1]:9093");
+    }
+
+    #[test]
+    fn post_to_local_listener() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = std::thread::spawn(move || {
+            let (mut conn, _) = listener.accept().unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 4096];
+            while !request.ends_with(b"{\"ok\":true}") {
+                let n = conn.read(&mut buf).unwrap();
+                if n == 0 { break; }
+                request.extend_from_slice(&buf[..n]);
+            }
+            conn.write_all(b"HTTP/1.1 201 Created\r\nContent-Length: 8\r\n\r\naccepted").unwrap();
+            String::from_utf8(request).unwrap()
+        });
+
+        let headers = vec![("Content-Type".to_string(), "application/json".to_string())];
+        let url = format!("http://127.0.0.1:{}/hook?x=1", port);
+        let resp = post(&url, &headers, br#"{"ok":true}"#, Duration::from_secs(5)).unwrap();
+        assert_eq!(resp.status, 201);
+        assert!(resp.is_success());
+        assert_eq!(resp.body, "accepted");
+
+        let request = server.join().unwrap();
+        assert!(request.starts_with("POST /hook?x=1 HTTP/1.1\r\n"), "{}", request);
+        assert!(request.contains(&format!("\r\nHost: 127.0.0.1:{}\r\n", port)), "{}", request);
+        assert!(request.contains("\r\nContent-Length: 11\r\n"), "{}", request);
+        assert!(request.contains("\r\nContent-Type: application/json\r\n"), "{}", request);
+    }
+
+    #[test]
+    fn keep_alive_response_ends_at_its_framing() {
+        for reply in [
+            &b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: keep-alive\r\n\r\nok"[..],
+            &b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n2\r\nok\r\n3;x=1\r\n!!!\r\n0\r\n\r\n"[..],
+        ] {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            let url = format!("http://{}/", listener.local_addr().unwrap());
+            let server = std::thread::spawn(move || {
+                let (mut conn, _) = listener.accept().unwrap();
+                let mut buf = [0u8; 4096];
+                let _ = conn.read(&mut buf).unwrap();
+                conn.write_all(reply).unwrap();
+                // Hold the connection open until the client hangs up
+                while conn.read(&mut buf).is_ok_and(|n| n > 0) {}
+            });
+            let start = std::time::Instant::now();
+            let resp = post(&url, &[], b"", Duration::from_secs(10)).unwrap();
+            assert!(start.elapsed() < Duration::from_secs(5), "waited for the server to close");
+            assert_eq!(resp.status, 200);
+            assert!(resp.body.starts_with("ok"), "{:?}", resp.body);
+            server.join().unwrap();
+        }
+    }
+
+    #[test]
+    fn invalid_header_is_rejected() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        let headers = vec![("X-Bad".to_string(), "a\r\nInjected: 1".to_string())];
+        assert!(post(&url, &headers, b"", Duration::from_secs(5)).is_err());
+    }
+}
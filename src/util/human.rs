@@ -47,3 +47,16 @@ pub fn fmt_eta(days: f64) -> String {
     else if days > 30.0   { format!("{:.0}d", days) }
     else                  { format!("{:.1}d", days) }
 }
+
+/// Parse a compact duration ("90s", "5m", "2h", "1d"; a bare number is seconds) into seconds.
+pub fn parse_duration(s: &str) -> Option<u64> {
+    let s = s.trim().to_lowercase();
+    let (num, mult) = match s.char_indices().last()? {
+        (i, 's') => (&s[..i], 1),
+        (i, 'm') => (&s[..i], 60),
+        (i, 'h') => (&s[..i], 3600),
+        (i, 'd') => (&s[..i], 86_400),
+        _        => (s.as_str(), 1),
+    };
+    num.trim().parse::<u64>().ok().map(|n| n * mult)
+}
@@ -0,0 +1,13 @@
+use std::fs::{self, OpenOptions};
+use std::os::fd::AsRawFd;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::Path;
+
+/// Wait for an exclusive `flock` on `path` (created if missing), held until
+/// the returned file is dropped. Guards the read-modify-write cycles on files
+/// the TUI and the daemon share. None if the lock file can't be opened.
+pub fn exclusive(path: &Path) -> Option<fs::File> {
+    if let Some(parent) = path.parent() { let _ = fs::create_dir_all(parent); }
+    let file = OpenOptions::new().create(true).truncate(false).write(true).mode(0o600).open(path).ok()?;
+    (unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX) } == 0).then_some(file)
+}
@@ -0,0 +1,359 @@
+use crate::alerts::{Alert, Severity};
+use crate::collectors::nfs::NfsMountStats;
+use crate::collectors::pressure::{psi_value, PsiResource, SystemPressure};
+use crate::models::device::BlockDevice;
+use crate::models::filesystem::Filesystem;
+use crate::models::smart::SmartStatus;
+use crate::models::volume::{LvmState, RaidArray, ZfsPool};
+use crate::util::health_score::health_score;
+use crate::util::write_endurance::{self, EnduranceMap};
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Every metric name starts with this.
+pub const PREFIX: &str = "dtop_";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Gauge,
+    Counter,
+}
+
+impl Kind {
+    fn label(&self) -> &'static str {
+        match self {
+            Kind::Gauge   => "gauge",
+            Kind::Counter => "counter",
+        }
+    }
+}
+
+/// One labelled value.
+#[derive(Debug, Clone)]
+pub struct Sample {
+    pub labels: Vec<(&'static str, String)>,
+    pub value:  f64,
+}
+
+/// A metric family: name (without `PREFIX`), help text, type and its samples.
+#[derive(Debug, Clone)]
+pub struct Family {
+    pub name:    &'static str,
+    pub help:    &'static str,
+    pub kind:    Kind,
+    pub samples: Vec<Sample>,
+}
+
+/// Everything one collection pass knows about, for `collect`.
+pub struct Snapshot<'a> {
+    pub devices:     &'a [BlockDevice],
+    pub filesystems: &'a [Filesystem],
+    pub raids:       &'a [RaidArray],
+    pub pools:       &'a [ZfsPool],
+    pub lvm:         Option<&'a LvmState>,
+    pub nfs:         &'a [NfsMountStats],
+    pub pressure:    Option<&'a SystemPressure>,
+    /// Active alerts, and how many of them are silenced or inhibited.
+    pub alerts:      &'a [Alert],
+    pub silenced:    usize,
+    pub endurance:   &'a EnduranceMap,
+}
+
+impl Snapshot<'_> {
+    /// A device by kernel name, `/dev/` path, alias or serial.
+    pub fn device(&self, name: &str) -> Option<&BlockDevice> {
+        let name = name.strip_prefix("/dev/").unwrap_or(name);
+        self.devices.iter()
+            .find(|d| d.name == name)
+            .or_else(|| self.devices.iter().find(|d| d.alias.as_deref() == Some(name) || d.serial.as_deref() == Some(name)))
+    }
+}
+
+/// Families in first-added order.
+#[derive(Default)]
+struct Builder {
+    families: Vec<Family>,
+    index:    HashMap<&'static str, usize>,
+}
+
+impl Builder {
+    fn add(&mut self, name: &'static str, help: &'static str, kind: Kind, labels: &[(&'static str, String)], value: f64) {
+        let i = *self.index.entry(name).or_insert_with(|| {
+            self.families.push(Family { name, help, kind, samples: Vec::new() });
+            self.families.len() - 1
+        });
+        self.families[i].samples.push(Sample { labels: labels.to_vec(), value });
+    }
+
+    fn gauge(&mut self, name: &'static str, help: &'static str, labels: &[(&'static str, String)], value: f64) {
+        self.add(name, help, Kind::Gauge, labels, value);
+    }
+
+    fn counter(&mut self, name: &'static str, help: &'static str, labels: &[(&'static str, String)], value: f64) {
+        self.add(name, help, Kind::Counter, labels, value);
+    }
+}
+
+/// Identifying labels shared by every per-device metric.
+pub fn device_labels(dev: &BlockDevice) -> Vec<(&'static str, String)> {
+    vec![
+        ("device", dev.name.clone()),
+        ("serial", dev.serial.clone().unwrap_or_default()),
+        ("model",  dev.model.clone().unwrap_or_default()),
+        ("alias",  dev.alias.clone().unwrap_or_default()),
+        ("type",   dev.dev_type.label().trim().to_string()),
+    ]
+}
+
+fn with(base: &[(&'static str, String)], extra: &[(&'static str, String)]) -> Vec<(&'static str, String)> {
+    base.iter().chain(extra).cloned().collect()
+}
+
+/// Every metric family for `s`.
+pub fn collect(s: &Snapshot) -> Vec<Family> {
+    let mut b = Builder::default();
+    b.gauge("build_info", "dtop version", &[("version", env!("CARGO_PKG_VERSION").to_string())], 1.0);
+    for dev in s.devices { device(&mut b, dev, s.endurance); }
+    for fs in s.filesystems { filesystem(&mut b, fs); }
+    volumes(&mut b, s.raids, s.pools, s.lvm);
+    for m in s.nfs { nfs(&mut b, m); }
+    if let Some(p) = s.pressure {
+        for (resource, r) in [("io", &p.io), ("cpu", &p.cpu), ("memory", &p.mem)] {
+            pressure(&mut b, resource, r);
+        }
+    }
+    for (sev, label) in [(Severity::Critical, "critical"), (Severity::Warning, "warning"), (Severity::Info, "info")] {
+        let n = s.alerts.iter().filter(|a| a.severity == sev).count();
+        b.gauge("alerts_active", "Active alerts by severity, including silenced ones", &[("severity", label.to_string())], n as f64);
+    }
+    b.gauge("alerts_silenced", "Active alerts that are silenced or inhibited", &[], s.silenced as f64);
+    b.families
+}
+
+fn device(b: &mut Builder, dev: &BlockDevice, endurance: &EnduranceMap) {
+    let l = device_labels(dev);
+    b.gauge("device_info", "Block device identity; always 1", &l, 1.0);
+    b.gauge("device_capacity_bytes", "Device size", &l, dev.capacity_bytes as f64);
+    b.gauge("device_read_bytes_per_second", "Read throughput over the last interval", &l, dev.read_bytes_per_sec);
+    b.gauge("device_write_bytes_per_second", "Write throughput over the last interval", &l, dev.write_bytes_per_sec);
+    b.gauge("device_read_iops", "Completed reads per second", &l, dev.read_iops);
+    b.gauge("device_write_iops", "Completed writes per second", &l, dev.write_iops);
+    b.gauge("device_read_latency_seconds", "Average time per read over the last interval", &l, dev.avg_read_latency_ms / 1000.0);
+    b.gauge("device_write_latency_seconds", "Average time per write over the last interval", &l, dev.avg_write_latency_ms / 1000.0);
+    b.gauge("device_io_util_percent", "Share of the interval the device was busy", &l, dev.io_util_pct);
+    if let Some(e) = endurance.get(&dev.name) {
+        b.counter("device_tracked_written_bytes_total", "Bytes written since dtop started tracking the device", &l, e.total_bytes_written as f64);
+        b.gauge("device_daily_written_bytes", "Average bytes written per day since tracking began", &l, write_endurance::daily_avg(e).0);
+    }
+
+    let Some(smart) = &dev.smart else { return };
+    let status = match smart.status {
+        SmartStatus::Unknown => 0.0,
+        SmartStatus::Passed  => 1.0,
+        SmartStatus::Warning => 2.0,
+        SmartStatus::Failed  => 3.0,
+    };
+    b.gauge("device_smart_status", "SMART status: 0 unknown, 1 passed, 2 warning, 3 failed", &l, status);
+    b.gauge("device_health_score", "dtop health score (0-100)", &l, health_score(dev) as f64);
+    if let Some(t) = smart.temperature {
+        b.gauge("device_temperature_celsius", "Drive temperature", &l, t as f64);
+    }
+    if let Some(h) = smart.power_on_hours {
+        b.counter("device_power_on_hours_total", "Power-on hours", &l, h as f64);
+    }
+    for a in &smart.attributes {
+        let al = with(&l, &[("id", a.id.to_string()), ("attribute", a.name.clone())]);
+        b.gauge("smart_attribute_value", "Normalised ATA SMART attribute value", &al, a.value as f64);
+        b.gauge("smart_attribute_worst", "Worst normalised ATA SMART attribute value", &al, a.worst as f64);
+        b.gauge("smart_attribute_threshold", "ATA SMART attribute failure threshold", &al, a.thresh as f64);
+        b.gauge("smart_attribute_raw", "Raw ATA SMART attribute value", &al, a.raw_value as f64);
+    }
+    if let Some(n) = &smart.nvme {
+        b.gauge("nvme_critical_warning", "NVMe critical warning bit field", &l, n.critical_warning as f64);
+        b.gauge("nvme_available_spare_percent", "NVMe available spare", &l, n.available_spare_pct as f64);
+        b.gauge("nvme_available_spare_threshold_percent", "NVMe available spare threshold", &l, n.available_spare_threshold as f64);
+        b.gauge("nvme_percentage_used", "NVMe estimate of rated endurance used", &l, n.percentage_used as f64);
+        b.counter("nvme_data_read_bytes_total", "Bytes read as counted by the NVMe controller", &l, n.bytes_read() as f64);
+        b.counter("nvme_data_written_bytes_total", "Bytes written as counted by the NVMe controller", &l, n.bytes_written() as f64);
+        b.counter("nvme_unsafe_shutdowns_total", "NVMe unsafe shutdowns", &l, n.unsafe_shutdowns as f64);
+        b.counter("nvme_media_errors_total", "NVMe media and data integrity errors", &l, n.media_errors as f64);
+        b.counter("nvme_error_log_entries_total", "NVMe error information log entries", &l, n.error_log_entries as f64);
+    }
+}
+
+fn filesystem(b: &mut Builder, fs: &Filesystem) {
+    let l = vec![("mount", fs.mount.clone()), ("device", fs.device.clone()), ("fstype", fs.fs_type.clone())];
+    b.gauge("filesystem_size_bytes", "Filesystem size", &l, fs.total_bytes as f64);
+    b.gauge("filesystem_used_bytes", "Bytes in use", &l, fs.used_bytes as f64);
+    b.gauge("filesystem_avail_bytes", "Bytes available to unprivileged users", &l, fs.avail_bytes as f64);
+    b.gauge("filesystem_used_percent", "Share of the filesystem in use", &l, fs.use_pct());
+    b.gauge("filesystem_inodes", "Total inodes", &l, fs.total_inodes as f64);
+    b.gauge("filesystem_inodes_free", "Free inodes", &l, fs.free_inodes as f64);
+    b.gauge("filesystem_inodes_used_percent", "Share of inodes in use", &l, fs.inode_pct());
+    if let Some(r) = fs.fill_rate_bps {
+        b.gauge("filesystem_fill_rate_bytes_per_second", "Recent growth of used space (negative while shrinking)", &l, r);
+    }
+    if let Some(d) = fs.days_until_full {
+        b.gauge("filesystem_days_until_full", "Projected days until full at the recent fill rate", &l, d);
+    }
+}
+
+fn volumes(b: &mut Builder, raids: &[RaidArray], pools: &[ZfsPool], lvm: Option<&LvmState>) {
+    for r in raids {
+        let l = vec![("array", r.name.clone()), ("level", r.level.clone())];
+        b.gauge("raid_active", "1 if the md array is active", &l, (r.state == "active") as u8 as f64);
+        b.gauge("raid_degraded", "1 if the md array is degraded", &l, r.degraded as u8 as f64);
+        b.gauge("raid_members", "Member devices", &l, r.members.len() as f64);
+        b.gauge("raid_size_bytes", "Array size", &l, r.capacity_bytes as f64);
+        if let Some(p) = r.rebuild_pct {
+            b.gauge("raid_rebuild_percent", "Resync or recovery progress", &l, p);
+        }
+    }
+    for p in pools {
+        let l = vec![("pool", p.name.clone())];
+        b.gauge("zfs_pool_healthy", "1 if the pool is ONLINE", &l, p.is_healthy() as u8 as f64);
+        b.gauge("zfs_pool_state", "Pool health state; always 1", &with(&l, &[("state", p.health.clone())]), 1.0);
+        b.gauge("zfs_pool_size_bytes", "Pool size", &l, p.size_bytes as f64);
+        b.gauge("zfs_pool_allocated_bytes", "Allocated space", &l, p.alloc_bytes as f64);
+        b.gauge("zfs_pool_free_bytes", "Free space", &l, p.free_bytes as f64);
+    }
+    let Some(lvm) = lvm else { return };
+    for vg in &lvm.vgs {
+        let l = vec![("vg", vg.name.clone())];
+        b.gauge("lvm_vg_size_bytes", "Volume group size", &l, vg.size_bytes as f64);
+        b.gauge("lvm_vg_free_bytes", "Unallocated space in the volume group", &l, vg.free_bytes as f64);
+        b.gauge("lvm_vg_physical_volumes", "Physical volumes in the volume group", &l, vg.pv_count as f64);
+        b.gauge("lvm_vg_logical_volumes", "Logical volumes in the volume group", &l, vg.lv_count as f64);
+    }
+    for lv in &lvm.lvs {
+        let l = vec![("vg", lv.vg_name.clone()), ("lv", lv.name.clone()), ("path", lv.path.clone())];
+        b.gauge("lvm_lv_size_bytes", "Logical volume size", &l, lv.size_bytes as f64);
+    }
+}
+
+fn nfs(b: &mut Builder, m: &NfsMountStats) {
+    let l = vec![("mount", m.mount.clone()), ("export", m.device.clone())];
+    b.counter("nfs_read_ops_total", "NFS read operations", &l, m.read_ops as f64);
+    b.counter("nfs_write_ops_total", "NFS write operations", &l, m.write_ops as f64);
+    b.counter("nfs_read_bytes_total", "Bytes read from the server", &l, m.server_bytes_read as f64);
+    b.counter("nfs_written_bytes_total", "Bytes written to the server", &l, m.server_bytes_written as f64);
+    // Raw sums rather than the since-mount average: divide their rate by the ops rate
+    b.counter("nfs_read_rtt_seconds_total", "Total round-trip time of read operations", &l, m.read_rtt_total_ms / 1000.0);
+    b.counter("nfs_write_rtt_seconds_total", "Total round-trip time of write operations", &l, m.write_rtt_total_ms / 1000.0);
+}
+
+fn pressure(b: &mut Builder, resource: &str, r: &PsiResource) {
+    for (kind, m) in [("some", &r.some), ("full", &r.full)] {
+        for (window, v) in [("10s", m.avg10), ("60s", m.avg60), ("300s", m.avg300)] {
+            b.gauge("pressure_stall_percent", "Pressure stall information: share of time tasks were stalled",
+                &[("resource", resource.to_string()), ("kind", kind.to_string()), ("window", window.to_string())], psi_value(v));
+        }
+    }
+}
+
+// ── Prometheus text format ───────────────────────────────────────────
+
+fn escape_label(v: &str) -> String {
+    v.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+fn number(v: f64) -> String {
+    if v.is_nan() { "NaN".into() }
+    else if v.is_infinite() { if v > 0.0 { "+Inf".into() } else { "-Inf".into() } }
+    else { v.to_string() }
+}
+
+/// Families in the Prometheus text exposition format (0.0.4).
+pub fn prometheus(families: &[Family]) -> String {
+    let mut out = String::new();
+    for f in families {
+        out.push_str(&format!("# HELP {}{} {}\n", PREFIX, f.name, f.help));
+        out.push_str(&format!("# TYPE {}{} {}\n", PREFIX, f.name, f.kind.label()));
+        for s in &f.samples {
+            out.push_str(PREFIX);
+            out.push_str(f.name);
+            if !s.labels.is_empty() {
+                let labels: Vec<String> = s.labels.iter()
+                    .map(|(k, v)| format!("{}=\"{}\"", k, escape_label(v)))
+                    .collect();
+                out.push_str(&format!("{{{}}}", labels.join(",")));
+            }
+            out.push(' ');
+            out.push_str(&number(s.value));
+            out.push('\n');
+        }
+    }
+    out
+}
+
+// ── node_exporter textfile ───────────────────────────────────────────
+
+/// Write `text` to `path` for node_exporter's textfile collector: into a
+/// temporary file beside it, then renamed over it, so a scrape never reads
+/// a half-written file. The temporary name doesn't end in `.prom`, so the
+/// collector ignores it.
+pub fn write_textfile(path: &Path, text: &str) -> Result<()> {
+    let tmp = path.with_file_name(format!(".{}.{}.tmp",
+        path.file_name().and_then(|n| n.to_str()).unwrap_or("dtop.prom"), std::process::id()));
+    let result = (|| {
+        let mut file = OpenOptions::new().write(true).create(true).truncate(true).mode(0o644).open(&tmp)?;
+        file.write_all(text.as_bytes())?;
+        file.sync_all()?;
+        fs::rename(&tmp, path)
+    })();
+    if result.is_err() { let _ = fs::remove_file(&tmp); }
+    result.with_context(|| format!("cannot write {}", path.display()))
+}
+
+// ── /metrics endpoint ────────────────────────────────────────────────
+
+/// Serve `/metrics` on `addr` from a background thread. The daemon replaces
+/// the returned text after every collection; scrapes never trigger polling.
+pub fn serve(addr: &str) -> Result<Arc<Mutex<String>>> {
+    let listener = TcpListener::bind(addr).with_context(|| format!("cannot listen on {}", addr))?;
+    let page = Arc::new(Mutex::new(String::new()));
+    let shared = page.clone();
+    std::thread::spawn(move || {
+        for conn in listener.incoming().flatten() {
+            let page = shared.clone();
+            std::thread::spawn(move || { let _ = respond(conn, &page); });
+        }
+    });
+    Ok(page)
+}
+
+fn respond(mut conn: TcpStream, page: &Mutex<String>) -> std::io::Result<()> {
+    conn.set_read_timeout(Some(Duration::from_secs(5)))?;
+    conn.set_write_timeout(Some(Duration::from_secs(10)))?;
+    let mut reader = BufReader::new(conn.try_clone()?);
+    let mut request = String::new();
+    reader.read_line(&mut request)?;
+    // Skip the headers; nothing in them matters here
+    let mut line = String::new();
+    while reader.read_line(&mut line)? > 2 { line.clear(); }
+
+    let mut parts = request.split_whitespace();
+    let (method, path) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+    let path = path.split('?').next().unwrap_or("");
+    let (status, content_type, body) = match (method, path) {
+        ("GET" | "HEAD", "/metrics") => {
+            let text = page.lock().unwrap_or_else(|e| e.into_inner()).clone();
+            ("200 OK", "text/plain; version=0.0.4; charset=utf-8", text)
+        }
+        ("GET" | "HEAD", "/") => ("200 OK", "text/html; charset=utf-8",
+            "<html><head><title>dtop exporter</title></head><body><h1>dtop</h1><p><a href=\"/metrics\">Metrics</a></p></body></html>\n".to_string()),
+        ("GET" | "HEAD", _) => ("404 Not Found", "text/plain; charset=utf-8", "not found\n".to_string()),
+        _ => ("405 Method Not Allowed", "text/plain; charset=utf-8", "method not allowed\n".to_string()),
+    };
+    write!(conn, "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        status, content_type, body.len())?;
+    if method != "HEAD" { conn.write_all(body.as_bytes())?; }
+    conn.flush()
+}
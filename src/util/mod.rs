@@ -1,14 +1,35 @@
 pub mod ack_store;
 pub mod alert_log;
-pub mod notify;
+pub mod alert_state;
+pub mod correlate;
+pub mod delivery;
+pub mod digest;
+pub mod expr;
+pub mod glob;
+pub mod notifier;
+pub mod notify_state;
 pub mod health_history;
 pub mod health_score;
+pub mod hook;
+pub mod http;
 pub mod human;
+pub mod lock;
+pub mod metrics;
+pub mod mqtt;
+pub mod nagios;
+pub mod net;
+pub mod payload;
+pub mod push;
 pub mod report;
+pub mod silence;
+pub mod smtp;
+pub mod syslog;
 pub mod ring_buffer;
 pub mod smart_anomaly;
 pub mod smart_attr_desc;
 pub mod smart_baseline;
+pub mod smart_history;
 pub mod user_state;
 pub mod webhook;
 pub mod write_endurance;
+pub mod zabbix;
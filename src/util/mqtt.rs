@@ -0,0 +1,683 @@
+use crate::alerts::{Alert, Severity};
+use crate::config::MqttConfig;
+use crate::models::device::BlockDevice;
+use crate::models::filesystem::Filesystem;
+use crate::util::alert_log::AlertEvent;
+use crate::util::health_score::health_score;
+use crate::util::{http, net, webhook};
+use anyhow::{anyhow, bail, Result};
+use chrono::Local;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::io::{Read, Write};
+use std::sync::mpsc::{self, RecvTimeoutError, Receiver, Sender};
+use std::time::{Duration, Instant};
+
+/// Non-retained messages (alert events) kept while the broker is unreachable.
+const MAX_BACKLOG: usize = 256;
+/// Longest wait between reconnect attempts.
+const MAX_BACKOFF: Duration = Duration::from_secs(300);
+
+/// One message to publish.
+#[derive(Debug, Clone)]
+pub struct Message {
+    pub topic:   String,
+    pub payload: String,
+    pub retain:  bool,
+}
+
+// ── MQTT 3.1.1 client ────────────────────────────────────────────────
+
+trait Stream: Read + Write + Send {}
+impl<T: Read + Write + Send> Stream for T {}
+
+/// A packet: the fixed header (`first` byte, variable-length remaining length) and `body`.
+fn packet(first: u8, body: &[u8]) -> Vec<u8> {
+    let mut out = vec![first];
+    let mut len = body.len();
+    loop {
+        let mut byte = (len % 128) as u8;
+        len /= 128;
+        if len > 0 { byte |= 0x80; }
+        out.push(byte);
+        if len == 0 { break; }
+    }
+    out.extend_from_slice(body);
+    out
+}
+
+/// A length-prefixed UTF-8 string or binary field.
+fn put(out: &mut Vec<u8>, data: &[u8]) {
+    out.extend_from_slice(&(data.len().min(u16::MAX as usize) as u16).to_be_bytes());
+    out.extend_from_slice(&data[..data.len().min(u16::MAX as usize)]);
+}
+
+/// A publish-only MQTT 3.1.1 session.
+pub struct Client {
+    stream:    Box<dyn Stream>,
+    qos:       u8,
+    next_id:   u16,
+    keepalive: Duration,
+    last_sent: Instant,
+}
+
+impl Client {
+    /// Connect and log in with a clean session. The broker publishes `will`
+    /// if the connection drops without a DISCONNECT.
+    pub fn connect(cfg: &MqttConfig, will: &Message) -> Result<Client> {
+        let timeout = Duration::from_secs(10);
+        let tcp = net::connect(&cfg.host, cfg.port, timeout)?;
+        let stream: Box<dyn Stream> = if cfg.tls {
+            let name = rustls::pki_types::ServerName::try_from(cfg.host.clone())
+                .map_err(|_| anyhow!("invalid TLS server name {}", cfg.host))?;
+            let conn = rustls::ClientConnection::new(http::tls_config(), name)?;
+            Box::new(rustls::StreamOwned::new(conn, tcp))
+        } else {
+            Box::new(tcp)
+        };
+
+        let qos = cfg.qos.min(1);
+        // Clean session, will flag, will QoS and will retain
+        let mut flags = 0x02 | 0x04 | (qos << 3) | 0x20;
+        if !cfg.username.is_empty() {
+            flags |= 0x80;
+            if !cfg.password.is_empty() { flags |= 0x40; }
+        }
+        let mut body = Vec::new();
+        put(&mut body, b"MQTT");
+        body.push(4);
+        body.push(flags);
+        body.extend_from_slice(&cfg.keepalive_secs.to_be_bytes());
+        put(&mut body, client_id(cfg).as_bytes());
+        put(&mut body, will.topic.as_bytes());
+        put(&mut body, will.payload.as_bytes());
+        if !cfg.username.is_empty() {
+            put(&mut body, cfg.username.as_bytes());
+            if !cfg.password.is_empty() { put(&mut body, cfg.password.as_bytes()); }
+        }
+
+        let mut client = Client {
+            stream,
+            qos,
+            next_id:   0,
+            keepalive: Duration::from_secs(cfg.keepalive_secs as u64),
+            last_sent: Instant::now(),
+        };
+        client.write(&packet(0x10, &body))?;
+        let (kind, reply) = client.read_packet()?;
+        if kind != 2 || reply.len() < 2 {
+            bail!("expected CONNACK, got packet type {}", kind);
+        }
+        match reply[1] {
+            0 => Ok(client),
+            1 => bail!("broker refused the connection: unsupported protocol version"),
+            2 => bail!("broker refused the connection: client ID rejected"),
+            3 => bail!("broker refused the connection: server unavailable"),
+            4 => bail!("broker refused the connection: bad user name or password"),
+            5 => bail!("broker refused the connection: not authorized"),
+            n => bail!("broker refused the connection (code {})", n),
+        }
+    }
+
+    fn write(&mut self, data: &[u8]) -> Result<()> {
+        self.stream.write_all(data)?;
+        self.stream.flush()?;
+        self.last_sent = Instant::now();
+        Ok(())
+    }
+
+    /// Read one packet: its type and body.
+    fn read_packet(&mut self) -> Result<(u8, Vec<u8>)> {
+        let mut b = [0u8; 1];
+        self.stream.read_exact(&mut b)?;
+        let kind = b[0] >> 4;
+        let mut len = 0usize;
+        for shift in (0..28).step_by(7) {
+            self.stream.read_exact(&mut b)?;
+            len |= ((b[0] & 0x7f) as usize) << shift;
+            if b[0] & 0x80 == 0 {
+                let mut body = vec![0u8; len];
+                self.stream.read_exact(&mut body)?;
+                return Ok((kind, body));
+            }
+        }
+        bail!("malformed packet length")
+    }
+
+    /// Publish `msg`; at QoS 1, wait for the broker's PUBACK.
+    pub fn publish(&mut self, msg: &Message) -> Result<()> {
+        let mut body = Vec::new();
+        put(&mut body, msg.topic.as_bytes());
+        self.next_id = self.next_id.wrapping_add(1).max(1);
+        if self.qos > 0 { body.extend_from_slice(&self.next_id.to_be_bytes()); }
+        body.extend_from_slice(msg.payload.as_bytes());
+        self.write(&packet(0x30 | (self.qos << 1) | msg.retain as u8, &body))?;
+        while self.qos > 0 {
+            let (kind, reply) = self.read_packet()?;
+            if kind == 4 && reply.get(..2) == Some(&self.next_id.to_be_bytes()[..]) { break; }
+        }
+        Ok(())
+    }
+
+    /// PINGREQ once nothing was sent for half the keepalive, then wait for PINGRESP.
+    pub fn keep_alive(&mut self) -> Result<()> {
+        if self.keepalive.is_zero() || self.last_sent.elapsed() < self.keepalive / 2 {
+            return Ok(());
+        }
+        self.write(&packet(0xC0, &[]))?;
+        loop {
+            let (kind, _) = self.read_packet()?;
+            if kind == 13 { return Ok(()); }
+        }
+    }
+
+    pub fn disconnect(mut self) {
+        let _ = self.write(&packet(0xE0, &[]));
+    }
+}
+
+// ── Background publisher ─────────────────────────────────────────────
+
+/// Publishes from a background thread that owns the connection, so a slow or
+/// unreachable broker never holds up monitoring. Reconnects with backoff and
+/// republishes retained state afterwards.
+pub struct Publisher {
+    tx:        Sender<Message>,
+    cfg:       MqttConfig,
+    prefix:    String,
+    /// Discovery config topic → the config last published there.
+    announced: HashMap<String, String>,
+}
+
+impl Publisher {
+    pub fn start(cfg: &MqttConfig) -> Self {
+        let (tx, rx) = mpsc::channel();
+        let worker_cfg = cfg.clone();
+        std::thread::spawn(move || worker(worker_cfg, rx));
+        Self { tx, cfg: cfg.clone(), prefix: topic_prefix(cfg), announced: HashMap::new() }
+    }
+
+    fn send(&self, topic: String, payload: String, retain: bool) {
+        let _ = self.tx.send(Message { topic, payload, retain });
+    }
+
+    /// Current state of every device and filesystem, plus the active alerts.
+    pub fn publish_state(&self, devices: &[BlockDevice], filesystems: &[Filesystem], alerts: &[Alert]) {
+        for dev in devices {
+            self.send(format!("{}/device/{}", self.prefix, device_key(dev)), device_state(dev).to_string(), self.cfg.retain);
+        }
+        for fs in filesystems {
+            self.send(format!("{}/filesystem/{}", self.prefix, mount_slug(&fs.mount)), fs_state(fs).to_string(), self.cfg.retain);
+        }
+        self.send(format!("{}/alerts", self.prefix), alert_state(alerts).to_string(), self.cfg.retain);
+    }
+
+    /// Alert-log events as they happen, never retained.
+    pub fn publish_events(&self, events: &[AlertEvent]) {
+        for ev in events {
+            if let Ok(payload) = serde_json::to_string(ev) {
+                self.send(format!("{}/events", self.prefix), payload, false);
+            }
+        }
+    }
+
+    /// Home Assistant discovery configs for devices and filesystems that are
+    /// new or changed (a disk renamed, aliased or newly reporting a sensor).
+    pub fn announce(&mut self, devices: &[BlockDevice], filesystems: &[Filesystem]) {
+        if !self.cfg.discovery { return; }
+        let mut configs = vec![alerts_discovery(&self.cfg, &self.prefix)];
+        configs.extend(devices.iter().flat_map(|d| device_discovery(&self.cfg, &self.prefix, d)));
+        configs.extend(filesystems.iter().map(|fs| fs_discovery(&self.cfg, &self.prefix, fs)));
+        for (topic, config) in configs {
+            let config = config.to_string();
+            if self.announced.get(&topic) != Some(&config) {
+                self.announced.insert(topic.clone(), config.clone());
+                self.send(topic, config, true);
+            }
+        }
+    }
+}
+
+fn log(msg: &str) {
+    eprintln!("{} mqtt: {}", Local::now().format("%H:%M:%S"), msg);
+}
+
+fn worker(cfg: MqttConfig, rx: Receiver<Message>) {
+    let status = format!("{}/status", topic_prefix(&cfg));
+    let will = Message { topic: status.clone(), payload: "offline".into(), retain: true };
+    let online = Message { topic: status, payload: "online".into(), retain: true };
+    let server = format!("{}:{}", cfg.host, cfg.port);
+    let mut client: Option<Client> = None;
+    // Latest payload of every retained topic, republished after a reconnect
+    let mut retained: BTreeMap<String, String> = BTreeMap::new();
+    let mut backlog: VecDeque<Message> = VecDeque::new();
+    let mut backoff = Duration::from_secs(5);
+    let mut retry_at = Instant::now();
+    let idle = Duration::from_secs((cfg.keepalive_secs as u64 / 2).clamp(1, 30));
+
+    loop {
+        if client.is_none() && Instant::now() >= retry_at {
+            let replay = std::iter::once(online.clone())
+                .chain(retained.iter().map(|(t, p)| Message { topic: t.clone(), payload: p.clone(), retain: true }))
+                .chain(backlog.iter().cloned());
+            let connected = Client::connect(&cfg, &will).and_then(|mut c| {
+                for m in replay { c.publish(&m)?; }
+                Ok(c)
+            });
+            match connected {
+                Ok(c) => {
+                    log(&format!("connected to {}", server));
+                    backlog.clear();
+                    backoff = Duration::from_secs(5);
+                    client = Some(c);
+                }
+                Err(e) => {
+                    log(&format!("{}: {:#} (retrying in {}s)", server, e, backoff.as_secs()));
+                    retry_at = Instant::now() + backoff;
+                    backoff = (backoff * 2).min(MAX_BACKOFF);
+                }
+            }
+        }
+
+        let first = match rx.recv_timeout(idle) {
+            Ok(m) => Some(m),
+            Err(RecvTimeoutError::Timeout) => None,
+            Err(RecvTimeoutError::Disconnected) => {
+                if let Some(mut c) = client {
+                    let _ = c.publish(&will);
+                    c.disconnect();
+                }
+                return;
+            }
+        };
+        for msg in first.into_iter().chain(rx.try_iter()) {
+            if msg.retain {
+                retained.insert(msg.topic.clone(), msg.payload.clone());
+            }
+            if let Some(c) = &mut client {
+                if let Err(e) = c.publish(&msg) {
+                    log(&format!("{}: {:#}", server, e));
+                    client = None;
+                    retry_at = Instant::now();
+                } else {
+                    continue;
+                }
+            }
+            if !msg.retain {
+                if backlog.len() >= MAX_BACKLOG { backlog.pop_front(); }
+                backlog.push_back(msg);
+            }
+        }
+        if let Some(c) = &mut client {
+            if let Err(e) = c.keep_alive() {
+                log(&format!("{}: keepalive: {:#}", server, e));
+                client = None;
+                retry_at = Instant::now();
+            }
+        }
+    }
+}
+
+// ── Topics and payloads ──────────────────────────────────────────────
+
+/// Root of every topic: `mqtt.topic_prefix`, or dtop/<hostname>.
+pub fn topic_prefix(cfg: &MqttConfig) -> String {
+    if cfg.topic_prefix.is_empty() {
+        format!("dtop/{}", webhook::hostname())
+    } else {
+        cfg.topic_prefix.trim_end_matches('/').to_string()
+    }
+}
+
+fn client_id(cfg: &MqttConfig) -> String {
+    if cfg.client_id.is_empty() { format!("dtop-{}", webhook::hostname()) } else { cfg.client_id.clone() }
+}
+
+/// Topic- and ID-safe form of a name: "WD-1234 X" → "wd_1234_x". Not
+/// one-to-one; mount points go through `mount_slug`.
+fn slug(s: &str) -> String {
+    let out: String = s.trim_matches('/').chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '_' })
+        .collect();
+    if out.is_empty() { "root".to_string() } else { out }
+}
+
+/// One-to-one topic- and ID-safe form of a mount point: "/" → "_",
+/// "/var/lib" → "_var_lib", "/mnt/usb-disk" → "_mnt_usb--disk". Letters and
+/// digits are kept, '-' and '_' are escaped as "--" and "-_", and anything
+/// else becomes "-" and its hex bytes.
+pub fn mount_slug(mount: &str) -> String {
+    let mut out = String::new();
+    for c in mount.chars() {
+        match c {
+            '/' => out.push('_'),
+            '-' => out.push_str("--"),
+            '_' => out.push_str("-_"),
+            c if c.is_ascii_alphanumeric() => out.push(c),
+            c => {
+                let mut buf = [0u8; 4];
+                for b in c.encode_utf8(&mut buf).bytes() { out.push_str(&format!("-{:02x}", b)); }
+            }
+        }
+    }
+    out
+}
+
+/// A disk's identity in topics and discovery IDs: its serial when known, so
+/// it keeps both when its kernel name changes, otherwise its name.
+fn device_key(dev: &BlockDevice) -> String {
+    match &dev.serial {
+        Some(s) if !s.trim().is_empty() => slug(s),
+        _ => slug(&dev.name),
+    }
+}
+
+fn round1(v: f64) -> f64 {
+    (v * 10.0).round() / 10.0
+}
+
+fn device_state(dev: &BlockDevice) -> Value {
+    json!({
+        "device":               dev.name,
+        "alias":                dev.alias,
+        "model":                dev.model,
+        "serial":               dev.serial,
+        "type":                 dev.dev_type.label().trim(),
+        "capacity_bytes":       dev.capacity_bytes,
+        "smart_status":         dev.smart.as_ref().map(|_| dev.smart_status().label().trim()),
+        "health":               dev.smart.as_ref().map(|_| health_score(dev)),
+        "temperature":          dev.temperature(),
+        "util_pct":             round1(dev.io_util_pct),
+        "read_bytes_per_sec":   dev.read_bytes_per_sec.round(),
+        "write_bytes_per_sec":  dev.write_bytes_per_sec.round(),
+        "read_iops":            round1(dev.read_iops),
+        "write_iops":           round1(dev.write_iops),
+        "read_latency_ms":      round1(dev.avg_read_latency_ms),
+        "write_latency_ms":     round1(dev.avg_write_latency_ms),
+    })
+}
+
+fn fs_state(fs: &Filesystem) -> Value {
+    json!({
+        "mount":           fs.mount,
+        "device":          fs.device,
+        "fs_type":         fs.fs_type,
+        "use_pct":         round1(fs.use_pct()),
+        "used_bytes":      fs.used_bytes,
+        "avail_bytes":     fs.avail_bytes,
+        "total_bytes":     fs.total_bytes,
+        "inode_pct":       round1(fs.inode_pct()),
+    })
+}
+
+fn alert_state(alerts: &[Alert]) -> Value {
+    let count = |s: Severity| alerts.iter().filter(|a| a.severity == s).count();
+    json!({
+        "count":    alerts.len(),
+        "critical": count(Severity::Critical),
+        "warning":  count(Severity::Warning),
+        "alerts":   alerts.iter().map(|a| json!({
+            "severity": a.severity.label(),
+            "device":   a.device,
+            "mount":    a.mount,
+            "rule":     a.rule,
+            "message":  a.message,
+        })).collect::<Vec<_>>(),
+    })
+}
+
+// ── Home Assistant discovery ─────────────────────────────────────────
+
+/// Identifier of the host's device entry, which disks are linked to.
+fn host_id() -> String {
+    format!("dtop_{}", slug(&webhook::hostname()))
+}
+
+fn host_device() -> Value {
+    json!({
+        "identifiers":  [host_id()],
+        "name":         format!("{} storage", webhook::hostname()),
+        "manufacturer": "dtop",
+        "sw_version":   env!("CARGO_PKG_VERSION"),
+    })
+}
+
+/// Discovery topic and config for one sensor.
+struct Sensor<'a> {
+    object:   String,
+    name:     &'a str,
+    state:    String,
+    template: &'a str,
+    unit:     Option<&'a str>,
+    class:    Option<&'a str>,
+    device:   Value,
+}
+
+impl Sensor<'_> {
+    fn config(self, cfg: &MqttConfig, prefix: &str) -> (String, Value) {
+        let mut config = json!({
+            "name":                  self.name,
+            "unique_id":             self.object,
+            "object_id":             self.object,
+            "state_topic":           self.state,
+            "value_template":        self.template,
+            "availability_topic":    format!("{}/status", prefix),
+            "device":                self.device,
+            "state_class":           "measurement",
+            "origin":                { "name": "dtop", "sw": env!("CARGO_PKG_VERSION") },
+        });
+        if let Some(u) = self.unit  { config["unit_of_measurement"] = json!(u); }
+        if let Some(c) = self.class { config["device_class"] = json!(c); }
+        (format!("{}/sensor/{}/{}/config", cfg.discovery_prefix.trim_end_matches('/'), host_id(), self.object), config)
+    }
+}
+
+/// Sensors for one disk: health and temperature when SMART reports them,
+/// utilisation and throughput always. Keyed by `device_key`, like its state topic.
+fn device_discovery(cfg: &MqttConfig, prefix: &str, dev: &BlockDevice) -> Vec<(String, Value)> {
+    let key = device_key(dev);
+    let id = if dev.serial.as_deref().is_some_and(|s| !s.trim().is_empty()) {
+        format!("dtop_disk_{}", key)
+    } else {
+        format!("{}_{}", host_id(), key)
+    };
+    let mut device = json!({
+        "identifiers": [id],
+        "name":        dev.alias.clone().unwrap_or_else(|| format!("{} {}", webhook::hostname(), dev.name)),
+        "via_device":  host_id(),
+    });
+    // Home Assistant rejects null device fields
+    if let Some(m) = &dev.model  { device["model"] = json!(m); }
+    if let Some(s) = &dev.serial { device["serial_number"] = json!(s); }
+    let state = format!("{}/device/{}", prefix, key);
+    let mut sensors = Vec::new();
+    if dev.smart.is_some() {
+        sensors.push(("health", "Health", "{{ value_json.health }}", Some("%"), None));
+    }
+    if dev.temperature().is_some() {
+        sensors.push(("temperature", "Temperature", "{{ value_json.temperature }}", Some("°C"), Some("temperature")));
+    }
+    sensors.push(("util", "Utilisation", "{{ value_json.util_pct }}", Some("%"), None));
+    sensors.push(("read", "Read rate", "{{ value_json.read_bytes_per_sec }}", Some("B/s"), Some("data_rate")));
+    sensors.push(("write", "Write rate", "{{ value_json.write_bytes_per_sec }}", Some("B/s"), Some("data_rate")));
+    sensors.into_iter().map(|(key, name, template, unit, class)| Sensor {
+        object: format!("{}_{}", id, key),
+        name, state: state.clone(), template, unit, class,
+        device: device.clone(),
+    }.config(cfg, prefix)).collect()
+}
+
+/// A usage sensor for a filesystem, on the host's device entry.
+fn fs_discovery(cfg: &MqttConfig, prefix: &str, fs: &Filesystem) -> (String, Value) {
+    let name = format!("{} usage", fs.mount);
+    Sensor {
+        object:   format!("{}_fs{}", host_id(), mount_slug(&fs.mount)),
+        name:     &name,
+        state:    format!("{}/filesystem/{}", prefix, mount_slug(&fs.mount)),
+        template: "{{ value_json.use_pct }}",
+        unit:     Some("%"),
+        class:    None,
+        device:   host_device(),
+    }.config(cfg, prefix)
+}
+
+/// The number of active alerts, on the host's device entry.
+fn alerts_discovery(cfg: &MqttConfig, prefix: &str) -> (String, Value) {
+    Sensor {
+        object:   format!("{}_alerts", host_id()),
+        name:     "Active alerts",
+        state:    format!("{}/alerts", prefix),
+        template: "{{ value_json.count }}",
+        unit:     None,
+        class:    None,
+        device:   host_device(),
+    }.config(cfg, prefix)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use std::net::TcpListener;
+
+    /// The broker's end of a connection, read with the client's own packet reader.
+    fn peer<S: Stream + 'static>(stream: S) -> Client {
+        Client { stream: Box::new(stream), qos: 0, next_id: 0, keepalive: Duration::ZERO, last_sent: Instant::now() }
+    }
+
+    /// Accept one client, check its CONNECT and acknowledge it; then run `rest`
+    /// with the broker's end. Returns the CONNECT body.
+    fn fake_broker(
+        listener: TcpListener,
+        rest: impl FnOnce(&mut Client) + Send + 'static,
+    ) -> std::thread::JoinHandle<Vec<u8>> {
+        std::thread::spawn(move || {
+            let (conn, _) = listener.accept().unwrap();
+            let mut broker = peer(conn);
+            let (kind, connect) = broker.read_packet().unwrap();
+            assert_eq!(kind, 1, "expected CONNECT");
+            broker.write(&[0x20, 2, 0, 0]).unwrap();
+            rest(&mut broker);
+            connect
+        })
+    }
+
+    fn config(port: u16) -> MqttConfig {
+        MqttConfig { host: "127.0.0.1".into(), port, client_id: "dtop-test".into(), ..MqttConfig::default() }
+    }
+
+    fn will() -> Message {
+        Message { topic: "dtop/test/status".into(), payload: "offline".into(), retain: true }
+    }
+
+    #[test]
+    fn connect_flags_carry_will_retain_and_auth() {
+        for (username, password, qos, flags) in [("", "", 0, 0x26), ("u", "", 1, 0xAE), ("u", "p", 1, 0xEE)] {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            let mut cfg = config(listener.local_addr().unwrap().port());
+            (cfg.username, cfg.password, cfg.qos) = (username.into(), password.into(), qos);
+            let broker = fake_broker(listener, |_| {});
+            Client::connect(&cfg, &will()).unwrap().disconnect();
+
+            let connect = broker.join().unwrap();
+            assert_eq!(connect[..7], [0, 4, b'M', b'Q', b'T', b'T', 4]);
+            assert_eq!(connect[7], flags, "flags for {:?}/{:?} at QoS {}", username, password, qos);
+            assert_eq!(connect[8..10], 60u16.to_be_bytes());
+            let mut expected = Vec::new();
+            put(&mut expected, b"dtop-test");
+            put(&mut expected, b"dtop/test/status");
+            put(&mut expected, b"offline");
+            if !username.is_empty() { put(&mut expected, username.as_bytes()); }
+            if !password.is_empty() { put(&mut expected, password.as_bytes()); }
+            assert_eq!(connect[10..], expected[..]);
+        }
+    }
+
+    #[test]
+    fn remaining_length_uses_continuation_bytes() {
+        for (len, header) in [
+            (0, &[0x00][..]),
+            (127, &[0x7f][..]),
+            (128, &[0x80, 0x01][..]),
+            (16383, &[0xff, 0x7f][..]),
+            (16384, &[0x80, 0x80, 0x01][..]),
+            (200_000, &[0xc0, 0x9a, 0x0c][..]),
+        ] {
+            let body = vec![7u8; len];
+            let raw = packet(0x30, &body);
+            assert_eq!(raw[0], 0x30);
+            assert_eq!(&raw[1..1 + header.len()], header, "length {}", len);
+            assert_eq!(raw.len(), 1 + header.len() + len);
+            assert_eq!(peer(Cursor::new(raw)).read_packet().unwrap(), (3, body));
+        }
+    }
+
+    #[test]
+    fn qos1_publish_waits_for_its_puback() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut cfg = config(listener.local_addr().unwrap().port());
+        cfg.qos = 1;
+        let (tx, rx) = mpsc::channel();
+        let broker = fake_broker(listener, move |broker| {
+            let (kind, body) = broker.read_packet().unwrap();
+            tx.send((kind, body.clone())).unwrap();
+            // Unrelated packets first: a PINGRESP and another message's PUBACK
+            std::thread::sleep(Duration::from_millis(300));
+            broker.write(&[0xD0, 0]).unwrap();
+            broker.write(&[0x40, 2, 0x7f, 0x7f]).unwrap();
+            // Then its own: the packet ID sits just before the 4-byte payload
+            broker.write(&packet(0x40, &body[body.len() - 6..body.len() - 4])).unwrap();
+        });
+
+        let mut client = Client::connect(&cfg, &will()).unwrap();
+        let start = Instant::now();
+        client.publish(&Message { topic: "dtop/x".into(), payload: "test".into(), retain: false }).unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(300), "returned before the PUBACK");
+
+        let (kind, body) = rx.recv().unwrap();
+        assert_eq!(kind, 3);
+        let mut expected = Vec::new();
+        put(&mut expected, b"dtop/x");
+        expected.extend_from_slice(&1u16.to_be_bytes());
+        expected.extend_from_slice(b"test");
+        assert_eq!(body, expected);
+        client.disconnect();
+        broker.join().unwrap();
+    }
+
+    /// Inverse of `mount_slug`.
+    fn unslug(slug: &str) -> String {
+        let mut bytes = Vec::new();
+        let mut chars = slug.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '_' => bytes.push(b'/'),
+                '-' => match chars.next().unwrap() {
+                    '-' => bytes.push(b'-'),
+                    '_' => bytes.push(b'_'),
+                    hi  => {
+                        let hex: String = [hi, chars.next().unwrap()].into_iter().collect();
+                        bytes.push(u8::from_str_radix(&hex, 16).unwrap());
+                    }
+                },
+                c => bytes.push(c as u8),
+            }
+        }
+        String::from_utf8(bytes).unwrap()
+    }
+
+    #[test]
+    fn mount_slug_is_one_to_one() {
+        let mounts = [
+            "/", "/var/lib", "/var_lib", "/var-lib", "/var lib", "/var-5flib", "/var--lib",
+            "/mnt/usb-disk", "/mnt/usb_disk", "/mnt/usb.disk", "/médias", "/a/-_", "/a/_-",
+        ];
+        let slugs: HashMap<String, &str> = mounts.iter().map(|m| (mount_slug(m), *m)).collect();
+        assert_eq!(slugs.len(), mounts.len(), "{:?}", slugs);
+        for (slug, mount) in &slugs {
+            assert!(slug.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-'), "{}", slug);
+            assert_eq!(unslug(slug), *mount);
+        }
+        assert_eq!(mount_slug("/mnt/usb-disk"), "_mnt_usb--disk");
+    }
+}
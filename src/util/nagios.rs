@@ -0,0 +1,211 @@
+use crate::alerts::{device_thresholds, Alert};
+use crate::config::AlertConfig;
+use crate::models::device::BlockDevice;
+use crate::util::health_score::{self, health_score};
+use crate::util::metrics::Snapshot;
+use anyhow::{anyhow, bail, Result};
+use std::fmt;
+
+/// What one `--check` covers, so separate service checks can watch separate areas.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Scope {
+    All,
+    /// SMART health, NVMe health and temperatures of every device.
+    Smart,
+    /// Filesystem usage, of one mount or all.
+    Fs(Option<String>),
+    /// md arrays and ZFS pools.
+    Raid,
+    /// Everything about one device (kernel name after `resolve`).
+    Device(String),
+}
+
+impl Scope {
+    /// "all", "smart", "fs", "fs=/var", "raid" or "device=sdb".
+    pub fn parse(s: &str) -> Result<Self> {
+        let (name, arg) = match s.split_once('=') {
+            Some((n, a)) => (n, Some(a.to_string())),
+            None         => (s, None),
+        };
+        Ok(match (name, arg) {
+            ("all" | "", None)       => Scope::All,
+            ("smart", None)          => Scope::Smart,
+            ("fs", mount)            => Scope::Fs(mount.filter(|m| !m.is_empty())),
+            ("raid", None)           => Scope::Raid,
+            ("device", Some(d)) if !d.is_empty() => Scope::Device(d),
+            _ => bail!("unknown check scope {:?} (expected all, smart, fs[=MOUNT], raid or device=NAME)", s),
+        })
+    }
+
+    /// Check that a named mount or device exists; devices given by alias,
+    /// serial or path are replaced by their kernel name.
+    pub fn resolve(self, s: &Snapshot) -> Result<Self> {
+        match self {
+            Scope::Fs(Some(m)) if !s.filesystems.iter().any(|f| f.mount == m) => {
+                bail!("no filesystem mounted at {}", m)
+            }
+            Scope::Device(d) => {
+                let dev = s.device(&d).ok_or_else(|| anyhow!("no device {}", d))?;
+                Ok(Scope::Device(dev.name.clone()))
+            }
+            other => Ok(other),
+        }
+    }
+
+    /// Whether the check needs an I/O sample (latency and utilisation).
+    pub fn needs_io(&self) -> bool {
+        matches!(self, Scope::All | Scope::Device(_))
+    }
+
+    /// Whether `alert` belongs to this check.
+    pub fn matches(&self, alert: &Alert) -> bool {
+        let rule = alert.rule.as_str();
+        match self {
+            Scope::All       => true,
+            Scope::Smart     => alert.device.is_some()
+                && (rule.starts_with("smart.") || rule.starts_with("nvme.") || rule == "temperature"),
+            Scope::Fs(mount) => rule.starts_with("fs.")
+                && mount.as_ref().is_none_or(|m| alert.mount.as_ref() == Some(m)),
+            Scope::Raid      => rule.starts_with("raid.") || rule.starts_with("zfs."),
+            Scope::Device(d) => alert.device.as_ref() == Some(d),
+        }
+    }
+
+    fn device(&self, dev: &BlockDevice) -> bool {
+        match self {
+            Scope::All | Scope::Smart => true,
+            Scope::Device(d)          => &dev.name == d,
+            _                         => false,
+        }
+    }
+
+    fn filesystem(&self, mount: &str) -> bool {
+        match self {
+            Scope::All      => true,
+            Scope::Fs(m)    => m.as_deref().is_none_or(|m| m == mount),
+            _               => false,
+        }
+    }
+
+    /// What was checked, for the OK line: "2 device(s), 3 filesystem(s), 1 array(s)".
+    pub fn summary(&self, s: &Snapshot) -> String {
+        let devices = s.devices.iter().filter(|d| self.device(d)).count();
+        let filesystems = s.filesystems.iter().filter(|f| self.filesystem(&f.mount)).count();
+        let arrays = s.raids.len() + s.pools.len();
+        let mut parts = Vec::new();
+        if matches!(self, Scope::All | Scope::Smart | Scope::Device(_)) { parts.push(format!("{} device(s)", devices)); }
+        if matches!(self, Scope::All | Scope::Fs(_)) { parts.push(format!("{} filesystem(s)", filesystems)); }
+        if matches!(self, Scope::All | Scope::Raid)  { parts.push(format!("{} array(s)", arrays)); }
+        parts.join(", ")
+    }
+}
+
+/// One performance-data value: `'label'=value[UOM];[warn];[crit];[min];[max]`.
+struct Perf {
+    label: String,
+    value: f64,
+    uom:   &'static str,
+    warn:  Option<String>,
+    crit:  Option<String>,
+    min:   Option<f64>,
+    max:   Option<f64>,
+}
+
+impl Perf {
+    fn new(label: impl Into<String>, value: f64, uom: &'static str) -> Self {
+        Self { label: label.into(), value, uom, warn: None, crit: None, min: None, max: None }
+    }
+
+    /// Warn/crit at or above these, as the alert rules do (Nagios `@N:`; a
+    /// plain `N` would only alert above N). Values of 0 (a disabled threshold)
+    /// are left out.
+    fn above(mut self, warn: f64, crit: f64) -> Self {
+        self.warn = (warn > 0.0).then(|| format!("@{}:", number(warn)));
+        self.crit = (crit > 0.0).then(|| format!("@{}:", number(crit)));
+        self
+    }
+
+    /// Warn/crit below these (Nagios `N:` ranges).
+    fn below(mut self, warn: f64, crit: f64) -> Self {
+        self.warn = Some(format!("{}:", number(warn)));
+        self.crit = Some(format!("{}:", number(crit)));
+        self
+    }
+
+    fn range(mut self, min: f64, max: Option<f64>) -> Self {
+        self.min = Some(min);
+        self.max = max;
+        self
+    }
+}
+
+impl fmt::Display for Perf {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let plain = self.label.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-'));
+        if plain {
+            write!(f, "{}", self.label)?;
+        } else {
+            write!(f, "'{}'", self.label.replace('\'', "''"))?;
+        }
+        let fields = format!("{}{};{};{};{};{}", number(self.value), self.uom,
+            self.warn.as_deref().unwrap_or(""), self.crit.as_deref().unwrap_or(""),
+            self.min.map(number).unwrap_or_default(), self.max.map(number).unwrap_or_default());
+        write!(f, "={}", fields.trim_end_matches(';'))
+    }
+}
+
+fn number(v: f64) -> String {
+    ((v * 100.0).round() / 100.0).to_string()
+}
+
+/// Performance data for everything in `scope`, with warn/crit ranges from the
+/// alert thresholds (overrides applied). Health scores warn below 80 and are
+/// critical below 50, as in the TUI.
+pub fn perfdata(scope: &Scope, s: &Snapshot, alert_cfg: &AlertConfig) -> String {
+    let mut out: Vec<Perf> = Vec::new();
+    for dev in s.devices.iter().filter(|d| scope.device(d)) {
+        let thr = device_thresholds(dev, alert_cfg).thresholds;
+        if let Some(smart) = &dev.smart {
+            if let Some(t) = smart.temperature {
+                let (warn, crit) = if dev.rotational {
+                    (thr.temperature_warn_hdd, thr.temperature_crit_hdd)
+                } else {
+                    (thr.temperature_warn_ssd, thr.temperature_crit_ssd)
+                };
+                out.push(Perf::new(format!("{}_temp", dev.name), t as f64, "").above(warn as f64, crit as f64));
+            }
+            out.push(Perf::new(format!("{}_health", dev.name), health_score(dev) as f64, "")
+                .below(health_score::WARN_BELOW as f64, health_score::CRIT_BELOW as f64).range(0.0, Some(100.0)));
+        }
+        if scope.needs_io() {
+            for (dir, lat) in [("read", dev.avg_read_latency_ms), ("write", dev.avg_write_latency_ms)] {
+                out.push(Perf::new(format!("{}_{}_latency", dev.name, dir), lat, "ms")
+                    .above(thr.latency_warn_ms, thr.latency_crit_ms).range(0.0, None));
+            }
+            out.push(Perf::new(format!("{}_util", dev.name), dev.io_util_pct, "%")
+                .above(thr.io_util_warn_pct, 0.0).range(0.0, Some(100.0)));
+        }
+    }
+    for fs in s.filesystems.iter().filter(|f| scope.filesystem(&f.mount)) {
+        let thr = alert_cfg.for_mount(&fs.mount).thresholds;
+        out.push(Perf::new(fs.mount.clone(), fs.use_pct(), "%")
+            .above(thr.filesystem_warn_pct, thr.filesystem_crit_pct).range(0.0, Some(100.0)));
+        if fs.total_inodes > 0 {
+            out.push(Perf::new(format!("{} inodes", fs.mount), fs.inode_pct(), "%")
+                .above(thr.inode_warn_pct, thr.inode_crit_pct).range(0.0, Some(100.0)));
+        }
+    }
+    if matches!(scope, Scope::All | Scope::Raid) {
+        for r in s.raids {
+            out.push(Perf::new(format!("{}_degraded", r.name), r.degraded as u8 as f64, "").above(0.0, 1.0).range(0.0, Some(1.0)));
+            if let Some(p) = r.rebuild_pct {
+                out.push(Perf::new(format!("{}_rebuild", r.name), p, "%").range(0.0, Some(100.0)));
+            }
+        }
+        for p in s.pools {
+            out.push(Perf::new(format!("{}_degraded", p.name), !p.is_healthy() as u8 as f64, "").above(0.0, 1.0).range(0.0, Some(1.0)));
+            out.push(Perf::new(format!("{}_used", p.name), p.use_pct(), "%").range(0.0, Some(100.0)));
+        }
+    }
+    out.iter().map(|p| p.to_string()).collect::<Vec<_>>().join(" ")
+}
@@ -0,0 +1,29 @@
+use anyhow::{anyhow, bail, Context, Result};
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+/// Every address `host` resolves to, with `port`.
+pub fn resolve(host: &str, port: u16) -> Result<Vec<SocketAddr>> {
+    let addrs: Vec<_> = (host, port).to_socket_addrs()
+        .with_context(|| format!("cannot resolve {}", host))?
+        .collect();
+    if addrs.is_empty() { bail!("no addresses for {}", host); }
+    Ok(addrs)
+}
+
+/// Connect to the first reachable address of `host`. `timeout` applies to the
+/// connect and to each read and write afterwards.
+pub fn connect(host: &str, port: u16, timeout: Duration) -> Result<TcpStream> {
+    let mut last_err = anyhow!("no addresses for {}", host);
+    for addr in resolve(host, port)? {
+        match TcpStream::connect_timeout(&addr, timeout) {
+            Ok(s) => {
+                s.set_read_timeout(Some(timeout))?;
+                s.set_write_timeout(Some(timeout))?;
+                return Ok(s);
+            }
+            Err(e) => last_err = anyhow!("connect {}: {}", addr, e),
+        }
+    }
+    Err(last_err)
+}
@@ -0,0 +1,337 @@
+use crate::alerts::{Alert, Severity};
+use crate::config::{ChannelConfig, ChannelKind, DigestConfig, NotificationsConfig, ReportConfig, RouteConfig};
+use crate::models::device::BlockDevice;
+use crate::models::filesystem::Filesystem;
+use crate::models::volume::{RaidArray, ZfsPool};
+use crate::util::correlate::Correlation;
+use crate::util::delivery::{self, Delivery, DeliveryRecord, DeliveryStatus};
+use crate::util::digest::{self, Batcher};
+use crate::util::glob::glob_match;
+use crate::util::notify_state::{Dispatch, Sent};
+use crate::util::smtp::{self, Message};
+use crate::util::syslog::{self, Fields, Logger, Sink};
+use crate::util::hook::{self, Hook};
+use crate::util::{human, payload, report, webhook};
+use chrono::{DateTime, Datelike, Duration, Local, NaiveTime, Weekday};
+use std::collections::HashMap;
+use std::thread::JoinHandle;
+
+/// Route one tick's notifications into each channel's batch, then send the
+/// batches that are due. Call every tick, even with nothing new, so batch
+/// windows close on time. A resolve goes to the channels its alert was sent
+/// to (unless they set `send_resolved = false`). Services that expire alerts
+/// (Alertmanager) are sent the still-firing ones again on a timer, outside
+/// batching and rate limits. Route `devices` selectors match filesystem
+/// alerts by the disk behind the filesystem. Returns what actually went out
+/// (nothing yet while a batch is open or the rate limit holds it back), for
+/// `NotifyState::record_sent`.
+pub fn dispatch(
+    d:           &Dispatch,
+    cfg:         &NotificationsConfig,
+    corr:        &Correlation,
+    devices:     &[BlockDevice],
+    filesystems: &[Filesystem],
+    batcher:     &mut Batcher,
+) -> Sent {
+    let channels = cfg.effective_channels();
+    let routes   = cfg.effective_routes();
+    let now      = Local::now();
+    let device_of = |a: &Alert| a.device.clone().or_else(|| {
+        let fs = filesystems.iter().find(|fs| Some(&fs.mount) == a.mount.as_ref())?;
+        crate::alerts::backing_device(fs, devices).map(|d| d.name.clone())
+    });
+    let mut sent = Sent::default();
+    let events   = [("fired", &d.fresh), ("reminder", &d.renotify), ("escalated", &d.escalated), ("resolved", &d.resolved)];
+    for (event, alerts) in events {
+        for ch in &channels {
+            if event == "resolved" && !ch.send_resolved { continue; }
+            let batch: Vec<&Alert> = alerts.iter()
+                .filter(|a| match d.resolved_to.get(&a.condition()).filter(|_| event == "resolved") {
+                    Some(sent) => sent.contains(&ch.name),
+                    None       => routes.iter().any(|r| r.channels.contains(&ch.name) && r.matches(a, device_of(a).as_deref(), event, now)),
+                })
+                .collect();
+            if event != "resolved" {
+                for a in &batch { sent.channels.entry(a.condition()).or_default(); }
+            }
+            batcher.add(&ch.name, event, &batch, &d.durations, now.timestamp());
+        }
+    }
+    for ch in &channels {
+        for b in batcher.take(ch, now.timestamp()) {
+            // Incident APIs would open an incident for the notice that never closes
+            let alerts: Vec<&Alert> = b.alerts.iter()
+                .filter(|a| a.rule != digest::SUPPRESSED_RULE || !payload::resolves(&ch.webhook.format))
+                .collect();
+            if alerts.is_empty() { continue; }
+            let _ = send(ch, cfg, &alerts, b.event, corr, devices, &b.durations);
+            if b.event == "resolved" { continue; }
+            for a in alerts.iter().filter(|a| a.rule != digest::SUPPRESSED_RULE) {
+                sent.keys.insert(a.key());
+                let to = sent.channels.entry(a.condition()).or_default();
+                if !to.contains(&ch.name) { to.push(ch.name.clone()); }
+            }
+        }
+        let Some(every) = payload::refresh_secs(&ch.webhook.format).filter(|_| ch.kind == ChannelKind::Webhook) else { continue };
+        let active: Vec<&Alert> = d.active.iter().filter(|(_, to)| to.contains(&ch.name)).map(|(a, _)| a).collect();
+        if !active.is_empty() && batcher.refresh_due(&ch.name, every, now.timestamp()) {
+            webhook::post(ch, &active, "reminder", corr, &HashMap::new());
+        }
+    }
+    sent
+}
+
+/// Deliver `alerts` through one channel. Nothing here blocks for long: webhooks
+/// are queued, and commands and mail run on helper threads whose handles are
+/// returned so one-shot callers can wait for them. `durations` holds how long
+/// resolved conditions had been firing.
+pub fn send(
+    ch:        &ChannelConfig,
+    cfg:       &NotificationsConfig,
+    alerts:    &[&Alert],
+    event:     &str,
+    corr:      &Correlation,
+    devices:   &[BlockDevice],
+    durations: &HashMap<String, i64>,
+) -> Vec<JoinHandle<()>> {
+    match ch.kind {
+        ChannelKind::Webhook => { webhook::post(ch, alerts, event, corr, durations); Vec::new() }
+        ChannelKind::Syslog | ChannelKind::Journald => {
+            let sink = if ch.kind == ChannelKind::Syslog { Sink::Syslog } else { Sink::Journald };
+            let log  = Logger::new(vec![sink], syslog::facility(&ch.facility));
+            for a in alerts {
+                let fields = Fields::new(event, a, devices, corr.parent_of(a))
+                    .with_duration(durations.get(&a.condition()).copied());
+                let after = durations.get(&a.condition())
+                    .map(|d| format!(" (after {})", human::fmt_duration_short((*d).max(0) as u64)))
+                    .unwrap_or_default();
+                log.alert(&fields, &format!("[{}] {}{}{}", a.severity.label(), a.prefix(), a.message, after));
+            }
+            Vec::new()
+        }
+        // One run per alert, so scripts never have to split a batch
+        ChannelKind::Exec => alerts.iter().map(|a| {
+            let duration = durations.get(&a.condition()).copied();
+            Hook::new(ch, &ch.command, a, event, duration, corr, devices).spawn()
+        }).collect(),
+        // One popup per batch, and none at all without a session to show it in
+        ChannelKind::Desktop => {
+            let Some(worst) = alerts.iter().max_by_key(|a| a.severity.clone()).filter(|_| hook::desktop_session()) else {
+                return Vec::new();
+            };
+            let duration = durations.get(&worst.condition()).copied();
+            let mut hook = Hook::new(ch, hook::DESKTOP_COMMAND, worst, event, duration, corr, devices).log_failures_only();
+            if alerts.len() > 1 { hook = hook.with_text(desktop_summary(alerts)); }
+            vec![hook.spawn()]
+        }
+        ChannelKind::Email => {
+            let msg = message(ch, cfg, subject(alerts, event, &webhook::hostname()), "text/plain",
+                format!("{}\n", webhook::text(alerts, event, corr, durations)));
+            let (ch, cfg) = (ch.clone(), cfg.clone());
+            vec![std::thread::spawn(move || { let _ = email(&ch, &cfg, &msg); })]
+        }
+    }
+}
+
+/// "[CRIT] 3 alerts: sda: …; sdb: …; +1 more", for one desktop popup per batch.
+fn desktop_summary(alerts: &[&Alert]) -> String {
+    const SHOWN: usize = 3;
+    let worst = alerts.iter().map(|a| &a.severity).max().unwrap_or(&Severity::Info);
+    let mut lines: Vec<String> = alerts.iter().take(SHOWN).map(|a| format!("{}{}", a.prefix(), a.message)).collect();
+    if alerts.len() > SHOWN { lines.push(format!("+{} more", alerts.len() - SHOWN)); }
+    format!("[{}] {} alerts: {}", worst.label(), alerts.len(), lines.join("; "))
+}
+
+/// A mail from channel `ch` (sender falls back to `smtp.from`, then dtop@<hostname>).
+pub fn message(ch: &ChannelConfig, cfg: &NotificationsConfig, subject: String, content_type: &'static str, body: String) -> Message {
+    let from = [&ch.from, &cfg.smtp.from].into_iter().find(|f| !f.is_empty()).cloned()
+        .unwrap_or_else(|| format!("dtop@{}", webhook::hostname()));
+    Message { from, to: ch.to.clone(), subject, content_type, body }
+}
+
+/// Send `msg` over SMTP now and log the outcome to `deliveries.jsonl`.
+pub fn email(ch: &ChannelConfig, cfg: &NotificationsConfig, msg: &Message) -> anyhow::Result<()> {
+    let result = smtp::send(&cfg.smtp, msg);
+    delivery::append(&DeliveryRecord {
+        ts:          Local::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, false),
+        endpoint:    ch.name.clone(),
+        url:         format!("smtp://{}:{}", cfg.smtp.host, cfg.smtp.port),
+        attempt:     1,
+        status:      if result.is_ok() { DeliveryStatus::Delivered } else { DeliveryStatus::Failed },
+        http_status: None,
+        error:       result.as_ref().err().map(|e| format!("{:#}", e)),
+    });
+    result
+}
+
+/// The most recent time `r` was scheduled to go out, at or before `now`.
+/// `None` if its `at`, `day` or `schedule` can't be parsed.
+pub fn last_slot(r: &ReportConfig, now: DateTime<Local>) -> Option<DateTime<Local>> {
+    slot(&r.schedule, &r.day, &r.at, now)
+}
+
+/// The most recent time the daily digest was due, at or before `now`.
+pub fn digest_slot(d: &DigestConfig, now: DateTime<Local>) -> Option<DateTime<Local>> {
+    slot("daily", "", &d.at, now)
+}
+
+fn slot(schedule: &str, day: &str, at: &str, now: DateTime<Local>) -> Option<DateTime<Local>> {
+    let at = if at.is_empty() { "08:00" } else { at.trim() };
+    let at = NaiveTime::parse_from_str(at, "%H:%M").ok()?;
+    let step = match schedule {
+        "daily"         => 1,
+        "" | "weekly"   => 7,
+        _               => return None,
+    };
+    let mut date = now.date_naive();
+    if step == 7 {
+        let day: Weekday = if day.is_empty() { Weekday::Mon } else { day.trim().parse().ok()? };
+        let back = (now.weekday().num_days_from_monday() + 7 - day.num_days_from_monday()) % 7;
+        date -= Duration::days(back as i64);
+    }
+    let slot = date.and_time(at).and_local_timezone(Local).earliest()?;
+    if slot <= now { Some(slot) } else { (date - Duration::days(step)).and_time(at).and_local_timezone(Local).earliest() }
+}
+
+/// Render report `r` and mail it to each of its email channels, returning the
+/// channel names with their sending threads.
+pub fn send_report(
+    r:           &ReportConfig,
+    cfg:         &NotificationsConfig,
+    devices:     &[BlockDevice],
+    filesystems: &[Filesystem],
+    alerts:      &[Alert],
+    raids:       &[RaidArray],
+    pools:       &[ZfsPool],
+) -> Vec<(String, JoinHandle<anyhow::Result<()>>)> {
+    let (content_type, body) = match r.format.as_str() {
+        "markdown" | "md" => ("text/plain", report::generate_markdown(devices, filesystems, alerts, raids, pools)),
+        _                 => ("text/html",  report::generate_html(devices, filesystems, alerts, raids, pools)),
+    };
+    let crit = alerts.iter().filter(|a| a.severity == Severity::Critical).count();
+    let warn = alerts.iter().filter(|a| a.severity == Severity::Warning).count();
+    let subject = format!("[dtop] {}: {} report ({} critical, {} warning)", webhook::hostname(), r.name, crit, warn);
+
+    cfg.effective_channels().into_iter()
+        .filter(|ch| ch.kind == ChannelKind::Email && r.channels.contains(&ch.name))
+        .map(|ch| {
+            let msg = message(&ch, cfg, subject.clone(), content_type, body.clone());
+            let cfg = cfg.clone();
+            (ch.name.clone(), std::thread::spawn(move || email(&ch, &cfg, &msg)))
+        })
+        .collect()
+}
+
+/// Send the daily digest (`summary`, `body` from `digest::daily`) to the digest
+/// channels: email as its own mail, other channels as one informational
+/// "digest" notification. PagerDuty and Alertmanager webhooks are skipped.
+pub fn send_digest(cfg: &NotificationsConfig, summary: &str, body: &str) -> Vec<JoinHandle<()>> {
+    let host = webhook::hostname();
+    let note = Alert {
+        severity: Severity::Info,
+        device:   None,
+        mount:    None,
+        rule:     digest::DIGEST_RULE.to_string(),
+        value:    None,
+        message:  body.trim_end().to_string(),
+    };
+    let mut out = Vec::new();
+    for ch in cfg.effective_channels().iter().filter(|ch| cfg.digest.channels.contains(&ch.name)) {
+        match ch.kind {
+            ChannelKind::Email => {
+                let msg = message(ch, cfg, format!("[dtop] {}: daily digest ({})", host, summary), "text/plain", body.to_string());
+                let (ch, cfg) = (ch.clone(), cfg.clone());
+                out.push(std::thread::spawn(move || { let _ = email(&ch, &cfg, &msg); }));
+            }
+            ChannelKind::Webhook if payload::resolves(&ch.webhook.format) => {}
+            // Sent right away so `--send-digest` can wait for it; failures join the retry queue
+            ChannelKind::Webhook => {
+                let reqs = payload::build(&ch.url, &ch.webhook, &[&note], "digest", &Correlation::default(), &HashMap::new());
+                let ch = ch.clone();
+                out.push(std::thread::spawn(move || {
+                    for req in reqs.into_iter().filter(|r| !r.url.is_empty()) {
+                        let mut d = Delivery::new(&ch.name, &req.url, &ch.webhook, req.body);
+                        let result = d.send();
+                        if result.as_ref().is_ok_and(|r| r.is_success()) {
+                            d.attempts = 1;
+                            delivery::record(&d, DeliveryStatus::Delivered, &result);
+                        } else {
+                            delivery::enqueue(d);
+                        }
+                    }
+                }));
+            }
+            _ => out.extend(send(ch, cfg, &[&note], "digest", &Correlation::default(), &[], &HashMap::new())),
+        }
+    }
+    out
+}
+
+/// "[dtop] host: 2 CRIT alerts fired"
+pub fn subject(alerts: &[&Alert], event: &str, host: &str) -> String {
+    let worst = alerts.iter().map(|a| &a.severity).max().unwrap_or(&Severity::Info);
+    format!("[dtop] {}: {} {} alert{} {}", host, alerts.len(), worst.label(),
+        if alerts.len() == 1 { "" } else { "s" }, event)
+}
+
+/// Route `severity` values; empty means "warn".
+const ROUTE_SEVERITIES: &[&str] = &["", "info", "warn", "warning", "crit", "critical"];
+/// Route `events` values.
+const ROUTE_EVENTS: &[&str] = &["fired", "reminder", "escalated", "resolved"];
+
+impl RouteConfig {
+    /// Whether this route selects `alert` for `event` at time `now`. `device` is
+    /// the alert's device, or for a filesystem alert the disk it lives on.
+    pub fn matches(&self, alert: &Alert, device: Option<&str>, event: &str, now: DateTime<Local>) -> bool {
+        let min = match self.severity.as_str() {
+            "info"             => Severity::Info,
+            "crit" | "critical" => Severity::Critical,
+            _                  => Severity::Warning,
+        };
+        if alert.severity < min { return false; }
+        if !self.events.is_empty() && !self.events.iter().any(|e| e == event) { return false; }
+        let field = |pats: &[String], val: Option<&str>| {
+            pats.is_empty() || val.is_some_and(|v| pats.iter().any(|p| glob_match(p, v)))
+        };
+        if !field(&self.devices, device) || !field(&self.mounts, alert.mount.as_deref()) { return false; }
+        if !self.rules.is_empty() && !self.rules.iter().any(|p| glob_match(p, &alert.rule)) { return false; }
+        if !self.days.is_empty() {
+            let wd = now.weekday().to_string().to_lowercase();
+            if !self.days.iter().any(|d| d.to_lowercase().starts_with(&wd)) { return false; }
+        }
+        self.hours.is_empty() || in_hours(&self.hours, now.time())
+    }
+}
+
+impl RouteConfig {
+    /// What in this route refers to nothing: unknown channels, severity or
+    /// events, or unparseable hours. Such parts never match.
+    pub fn problems(&self, channels: &[ChannelConfig]) -> Vec<String> {
+        let mut out: Vec<String> = self.channels.iter()
+            .filter(|c| !channels.iter().any(|ch| &&ch.name == c))
+            .map(|c| format!("unknown channel {:?}", c))
+            .collect();
+        if !ROUTE_SEVERITIES.contains(&self.severity.as_str()) {
+            out.push(format!("unknown severity {:?} (info, warn, crit)", self.severity));
+        }
+        out.extend(self.events.iter()
+            .filter(|e| !ROUTE_EVENTS.contains(&e.as_str()))
+            .map(|e| format!("unknown event {:?} ({})", e, ROUTE_EVENTS.join(", "))));
+        if !self.hours.is_empty() && !self.hours.split_once('-').is_some_and(|(a, b)| {
+            [a, b].iter().all(|t| NaiveTime::parse_from_str(t.trim(), "%H:%M").is_ok())
+        }) {
+            out.push(format!("invalid hours {:?} (HH:MM-HH:MM)", self.hours));
+        }
+        out
+    }
+}
+
+/// "08:00-18:00" contains `t`; ranges may wrap past midnight ("22:00-06:00").
+/// Unparseable ranges never match.
+fn in_hours(range: &str, t: NaiveTime) -> bool {
+    let Some((a, b)) = range.split_once('-') else { return false };
+    let (Ok(start), Ok(end)) = (NaiveTime::parse_from_str(a.trim(), "%H:%M"), NaiveTime::parse_from_str(b.trim(), "%H:%M")) else {
+        return false;
+    };
+    if start <= end { t >= start && t < end } else { t >= start || t < end }
+}
@@ -0,0 +1,239 @@
+use crate::alerts::{Alert, Severity};
+use crate::config::Config;
+use crate::util::alert_log::EventKind;
+use crate::util::alert_state::Transition;
+use crate::util::lock;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::PathBuf;
+
+/// Notification bookkeeping that must survive restarts (cooldowns, re-notify
+/// timers, escalation age, last report mailings). Persisted next to `acked_alerts.json`;
+/// the TUI and the daemon merge their copies into it under `notify_state.lock`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NotifyState {
+    /// Alert key → Unix timestamp of the last notification sent for it.
+    #[serde(default)]
+    pub last_sent: HashMap<String, i64>,
+    /// Alert condition → Unix timestamp it was first seen firing in this episode.
+    #[serde(default)]
+    pub active_since: HashMap<String, i64>,
+    /// Conditions that have been escalated from Warning to Critical.
+    #[serde(default)]
+    pub escalated: HashSet<String>,
+    /// Scheduled report name → Unix timestamp it was last mailed.
+    #[serde(default)]
+    pub reports: HashMap<String, i64>,
+    /// Unix timestamp the daily digest was last sent.
+    #[serde(default)]
+    pub digest: Option<i64>,
+    /// Alert condition → channels notified about it in this episode, so its
+    /// resolve goes to the same places. Present (maybe empty) from the moment
+    /// it is routed, so one that was never sent is never resolved anywhere.
+    #[serde(default)]
+    pub channels: HashMap<String, Vec<String>>,
+    /// Alert keys that were silenced or inhibited on the last tick, so they are
+    /// notified as new once the silence ends.
+    #[serde(default)]
+    pub muted: HashSet<String>,
+}
+
+/// What to send this tick, produced by `NotifyState::process`.
+#[derive(Debug, Default)]
+pub struct Dispatch {
+    /// Newly firing alerts (past any cooldown).
+    pub fresh:     Vec<Alert>,
+    /// Still-active, unacknowledged alerts due for a reminder.
+    pub renotify:  Vec<Alert>,
+    /// Warnings that just crossed `escalate_after_hours` (already raised to Critical).
+    pub escalated: Vec<Alert>,
+    /// Alerts that stopped firing after a notification went out for them.
+    pub resolved:  Vec<Alert>,
+    /// Seconds each resolved condition had been firing, by `Alert::condition()`.
+    pub durations: HashMap<String, i64>,
+    /// Channels each resolved condition was notified on while firing (possibly
+    /// none). Missing for alerts notified by older versions; those are routed as usual.
+    pub resolved_to: HashMap<String, Vec<String>>,
+    /// Every firing, unsilenced alert with the channels already notified about
+    /// it, for services that expire alerts unless they are posted again.
+    pub active:    Vec<(Alert, Vec<String>)>,
+}
+
+/// What `notifier::dispatch` sent this tick.
+#[derive(Debug, Default)]
+pub struct Sent {
+    /// Condition → channels it went to; present (maybe empty) once routed.
+    pub channels: HashMap<String, Vec<String>>,
+    /// Keys of the firing alerts that went out.
+    pub keys:     HashSet<String>,
+}
+
+impl Dispatch {
+    /// Add resolved notifications (from `NotifyState::resolved`).
+    pub fn with_resolved(mut self, resolved: &[(&Transition, Option<Vec<String>>)]) -> Self {
+        for (c, channels) in resolved {
+            let cond = c.alert.condition();
+            if let Some(d) = c.duration_secs { self.durations.insert(cond.clone(), d); }
+            if let Some(channels) = channels { self.resolved_to.insert(cond, channels.clone()); }
+            self.resolved.push(c.alert.clone());
+        }
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fresh.is_empty() && self.renotify.is_empty() && self.escalated.is_empty() && self.resolved.is_empty()
+    }
+}
+
+fn state_path() -> Option<PathBuf> {
+    dirs::data_local_dir().map(|p| p.join("dtop").join("notify_state.json"))
+}
+
+pub fn load() -> NotifyState {
+    let path = match state_path() { Some(p) => p, None => return NotifyState::default() };
+    let text = match fs::read_to_string(&path) { Ok(t) => t, Err(_) => return NotifyState::default() };
+    serde_json::from_str(&text).unwrap_or_default()
+}
+
+/// Persist notification state (best-effort). What another process saved since
+/// is merged into `state` first, so neither loses the other's cooldowns,
+/// escalations or resolve channels.
+pub fn save(state: &mut NotifyState) {
+    let path = match state_path() { Some(p) => p, None => return };
+    let _lock = lock::exclusive(&path.with_extension("lock"));
+    state.merge(load());
+    if let Ok(json) = serde_json::to_string(state) { let _ = fs::write(path, json); }
+}
+
+impl NotifyState {
+    /// Fold in `other`, keeping the later send and report times, the earlier
+    /// start of each episode and the union of everything else. Entries for
+    /// conditions this process no longer sees are pruned by its next `process`.
+    fn merge(&mut self, other: NotifyState) {
+        for (k, ts) in other.last_sent {
+            let e = self.last_sent.entry(k).or_insert(ts);
+            *e = (*e).max(ts);
+        }
+        for (k, ts) in other.active_since {
+            let e = self.active_since.entry(k).or_insert(ts);
+            *e = (*e).min(ts);
+        }
+        for (k, ts) in other.reports {
+            let e = self.reports.entry(k).or_insert(ts);
+            *e = (*e).max(ts);
+        }
+        self.digest = self.digest.max(other.digest);
+        self.escalated.extend(other.escalated);
+        self.muted.extend(other.muted);
+        self.remember_channels(other.channels);
+    }
+
+    /// The resolved transitions among `changes` whose alert was notified while
+    /// firing, with the channels it went to (`None` if not recorded). Call
+    /// before `process`, which forgets what was sent for them.
+    pub fn resolved<'a>(&self, changes: &'a [Transition]) -> Vec<(&'a Transition, Option<Vec<String>>)> {
+        changes.iter()
+            .filter(|c| c.kind == EventKind::Resolved)
+            .filter(|c| {
+                let cond = c.alert.condition();
+                self.channels.contains_key(&cond) || [Severity::Info, Severity::Warning, Severity::Critical].iter()
+                    .any(|s| self.last_sent.contains_key(&format!("{}{}", s.label(), cond)))
+            })
+            .map(|c| (c, self.channels.get(&c.alert.condition()).cloned()))
+            .collect()
+    }
+
+    /// Record what `notifier::dispatch` sent. New and escalated alerts count
+    /// as notified (for cooldowns and resolves) only from here, so ones still
+    /// waiting in a batch when the process stops are sent again after a restart.
+    pub fn record_sent(&mut self, sent: Sent, now: i64) {
+        for key in sent.keys {
+            self.last_sent.insert(key, now);
+        }
+        self.remember_channels(sent.channels);
+    }
+
+    fn remember_channels(&mut self, routed: HashMap<String, Vec<String>>) {
+        for (cond, channels) in routed {
+            let known = self.channels.entry(cond).or_default();
+            for ch in channels {
+                if !known.contains(&ch) { known.push(ch); }
+            }
+        }
+    }
+
+    /// Apply the escalation policy to `alerts` in place and work out which
+    /// notifications are due. `prev_keys` are the keys that were firing on the
+    /// previous tick; acked and silenced alerts are never re-notified or escalated,
+    /// and alerts still firing when their silence ends count as new.
+    pub fn process(
+        &mut self,
+        alerts:    &mut [Alert],
+        prev_keys: &HashSet<String>,
+        acked:     &HashSet<String>,
+        silenced:  &HashSet<String>,
+        cfg:       &Config,
+        now:       i64,
+    ) -> Dispatch {
+        let cooldown  = cfg.alerts.cooldown_hours as i64 * 3600;
+        let renotify  = cfg.notifications.renotify_hours as i64 * 3600;
+        let escalate  = cfg.notifications.escalate_after_hours as i64 * 3600;
+        let mut out   = Dispatch::default();
+
+        let conds: HashSet<String> = alerts.iter().map(|a| a.condition()).collect();
+        self.active_since.retain(|c, _| conds.contains(c));
+        self.channels.retain(|c, _| conds.contains(c));
+        self.escalated.retain(|c| conds.contains(c));
+        // Resolved alerts only need their last-sent time while the cooldown runs
+        let keys: HashSet<String> = alerts.iter().map(|a| a.key()).collect();
+        self.last_sent.retain(|k, &mut ts| keys.contains(k) || now - ts < cooldown);
+        let was_muted = std::mem::take(&mut self.muted);
+
+        for alert in alerts.iter_mut() {
+            let cond  = alert.condition();
+            let since = *self.active_since.entry(cond.clone()).or_insert(now);
+            let muted = silenced.contains(&alert.key());
+
+            let mut just_escalated = false;
+            if !self.escalated.contains(&cond)
+                && escalate > 0
+                && alert.severity == Severity::Warning
+                && now - since >= escalate
+                && !acked.contains(&alert.key())
+                && !muted
+            {
+                self.escalated.insert(cond.clone());
+                just_escalated = true;
+            }
+            // The message still describes the warning that was escalated
+            if self.escalated.contains(&cond) && alert.severity != Severity::Critical {
+                alert.severity = Severity::Critical;
+                alert.message = format!("{} (escalated after {}h)", alert.message, cfg.notifications.escalate_after_hours);
+            }
+
+            if muted {
+                self.muted.insert(alert.key());
+                continue;
+            }
+            out.active.push((alert.clone(), self.channels.get(&alert.condition()).cloned().unwrap_or_default()));
+            let key = alert.key();
+            if just_escalated {
+                out.escalated.push(alert.clone());
+            } else if !prev_keys.contains(&key) || was_muted.contains(&key) {
+                if cooldown > 0 && self.last_sent.get(&key).is_some_and(|&ts| now - ts < cooldown) {
+                    continue;
+                }
+                out.fresh.push(alert.clone());
+            } else if renotify > 0 && !acked.contains(&key) {
+                // Not sent yet (still batched): no reminder is due
+                let Some(&last) = self.last_sent.get(&key) else { continue };
+                if now - last >= renotify {
+                    self.last_sent.insert(key, now);
+                    out.renotify.push(alert.clone());
+                }
+            }
+        }
+        out
+    }
+}
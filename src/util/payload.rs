@@ -0,0 +1,240 @@
+use crate::alerts::{Alert, Severity};
+use crate::config::WebhookOptions;
+use crate::util::correlate::Correlation;
+use crate::util::webhook;
+use chrono::{Duration, Local, SecondsFormat};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+/// Built-in payload formats (`format = "…"` on a webhook channel).
+pub const FORMATS: &[&str] = &["ntfy", "gotify", "pagerduty", "alertmanager", "teams"];
+
+pub const PAGERDUTY_URL: &str = "https://events.pagerduty.com/v2/enqueue";
+
+/// How long Alertmanager keeps a firing alert without hearing from us again.
+const ALERTMANAGER_TTL_HOURS: i64 = 24;
+/// How often firing alerts are re-posted to Alertmanager, well within the TTL.
+const ALERTMANAGER_REFRESH_SECS: i64 = 3600;
+
+/// One HTTP POST to make.
+pub struct Request {
+    pub url:  String,
+    pub body: String,
+}
+
+/// Whether `format` has native resolve semantics, so resolved events are sent.
+pub fn resolves(format: &str) -> bool {
+    matches!(format, "pagerduty" | "alertmanager")
+}
+
+/// How often `format` needs firing alerts posted again to keep them open.
+pub fn refresh_secs(format: &str) -> Option<i64> {
+    (format == "alertmanager").then_some(ALERTMANAGER_REFRESH_SECS)
+}
+
+/// The request(s) that deliver `alerts` for `event` to `url` in `opts.format`.
+/// Unknown or empty formats use the template. PagerDuty gets one request per
+/// alert; everything else one per batch. `durations` holds how long resolved
+/// conditions had been firing.
+pub fn build(
+    url:       &str,
+    opts:      &WebhookOptions,
+    alerts:    &[&Alert],
+    event:     &str,
+    corr:      &Correlation,
+    durations: &HashMap<String, i64>,
+) -> Vec<Request> {
+    let one = |url: String, body: Value| vec![Request { url, body: body.to_string() }];
+    let text = || webhook::text(alerts, event, corr, durations);
+    match opts.format.as_str() {
+        "ntfy"         => one(ntfy_base(url), ntfy(url, alerts, event, &text())),
+        "gotify"       => one(url.to_string(), gotify(alerts, event, &text())),
+        "teams"        => one(url.to_string(), teams(alerts, event, &text())),
+        "alertmanager" => one(alertmanager_url(url), alertmanager(alerts, event, corr, durations)),
+        "pagerduty"    => {
+            let url = if url.is_empty() { PAGERDUTY_URL } else { url };
+            alerts.iter()
+                .map(|a| Request { url: url.to_string(), body: pagerduty(a, event, &opts.routing_key, corr).to_string() })
+                .collect()
+        }
+        _ => vec![Request { url: url.to_string(), body: webhook::render(alerts, event, &opts.template, corr, durations) }],
+    }
+}
+
+fn worst(alerts: &[&Alert]) -> Severity {
+    alerts.iter().map(|a| a.severity.clone()).max().unwrap_or(Severity::Info)
+}
+
+/// "vm: 2 CRIT alerts fired"
+fn title(alerts: &[&Alert], event: &str) -> String {
+    format!("{}: {} {} alert{} {}", webhook::hostname(), alerts.len(), worst(alerts).label(),
+        if alerts.len() == 1 { "" } else { "s" }, event)
+}
+
+fn now() -> String {
+    Local::now().to_rfc3339_opts(SecondsFormat::Secs, false)
+}
+
+// ── ntfy ─────────────────────────────────────────────────────────────
+
+/// ntfy takes JSON at the server root with the topic in the body, so
+/// "https://ntfy.sh/disks" posts to "https://ntfy.sh/" with topic "disks".
+fn ntfy_base(url: &str) -> String {
+    match url.trim_end_matches('/').rsplit_once('/') {
+        Some((base, _)) if !base.ends_with('/') => format!("{}/", base),
+        _ => url.to_string(),
+    }
+}
+
+fn ntfy(url: &str, alerts: &[&Alert], event: &str, text: &str) -> Value {
+    let topic = url.trim_end_matches('/').rsplit('/').next().unwrap_or("");
+    let (priority, tag) = match (event, worst(alerts)) {
+        ("resolved", _)          => (3, "white_check_mark"),
+        (_, Severity::Critical)  => (5, "rotating_light"),
+        (_, Severity::Warning)   => (4, "warning"),
+        (_, Severity::Info)      => (3, "information_source"),
+    };
+    json!({
+        "topic":    topic,
+        "title":    title(alerts, event),
+        "message":  text,
+        "priority": priority,
+        "tags":     [tag, "dtop"],
+    })
+}
+
+// ── Gotify ───────────────────────────────────────────────────────────
+
+fn gotify(alerts: &[&Alert], event: &str, text: &str) -> Value {
+    let priority = match (event, worst(alerts)) {
+        ("resolved", _)         => 2,
+        (_, Severity::Critical) => 8,
+        (_, Severity::Warning)  => 5,
+        (_, Severity::Info)     => 2,
+    };
+    json!({
+        "title":    title(alerts, event),
+        "message":  text,
+        "priority": priority,
+    })
+}
+
+// ── PagerDuty Events v2 ──────────────────────────────────────────────
+
+/// Incident key shared by an alert's trigger, reminders, escalation and resolve.
+/// Built from the severity-independent part of `Alert::key`, so a warning that
+/// escalates updates the same incident instead of opening a second one.
+pub fn dedup_key(alert: &Alert) -> String {
+    format!("dtop/{}/{}", webhook::hostname(), alert.condition())
+}
+
+fn pagerduty(alert: &Alert, event: &str, routing_key: &str, corr: &Correlation) -> Value {
+    if event == "resolved" {
+        return json!({ "routing_key": routing_key, "event_action": "resolve", "dedup_key": dedup_key(alert) });
+    }
+    let severity = match alert.severity {
+        Severity::Critical => "critical",
+        Severity::Warning  => "warning",
+        Severity::Info     => "info",
+    };
+    let summary: String = format!("[{}] {}{}", alert.severity.label(), alert.prefix(), alert.message)
+        .chars().take(1024).collect();
+    json!({
+        "routing_key":  routing_key,
+        "event_action": "trigger",
+        "dedup_key":    dedup_key(alert),
+        "payload": {
+            "summary":   summary,
+            "source":    webhook::hostname(),
+            "severity":  severity,
+            "timestamp": now(),
+            "component": alert.device.as_ref().or(alert.mount.as_ref()),
+            "group":     corr.parent_of(alert),
+            "class":     alert.rule,
+            "custom_details": {
+                "event":  event,
+                "device": alert.device,
+                "mount":  alert.mount,
+                "rule":   alert.rule,
+                "value":  alert.value,
+                "parent": corr.parent_of(alert),
+            },
+        },
+    })
+}
+
+// ── Alertmanager ─────────────────────────────────────────────────────
+
+/// A bare Alertmanager address gets the v2 alerts path appended.
+fn alertmanager_url(url: &str) -> String {
+    if url.contains("/api/") { url.to_string() } else { format!("{}/api/v2/alerts", url.trim_end_matches('/')) }
+}
+
+fn alertmanager(alerts: &[&Alert], event: &str, corr: &Correlation, durations: &HashMap<String, i64>) -> Value {
+    let now = Local::now();
+    let ends = if event == "resolved" { now } else { now + Duration::hours(ALERTMANAGER_TTL_HOURS) };
+    let host = webhook::hostname();
+    let list: Vec<Value> = alerts.iter().map(|a| {
+        // Severity is an annotation: as a label, an escalation would open a second alert
+        let mut labels = json!({
+            "alertname": a.rule,
+            "instance":  host,
+            "job":       "dtop",
+        });
+        if let Some(d) = &a.device { labels["device"] = json!(d); }
+        if let Some(m) = &a.mount  { labels["mount"]  = json!(m); }
+        let mut annotations = json!({
+            "summary":     format!("{}{}", a.prefix(), a.message),
+            "description": a.message,
+            "severity":    a.severity.label().to_lowercase(),
+        });
+        if let Some(p) = corr.parent_of(a) { annotations["parent"] = json!(p); }
+        if let Some(v) = a.value { annotations["value"] = json!(v.to_string()); }
+        if let Some(d) = durations.get(&a.condition()) { annotations["duration"] = json!(format!("{}s", d)); }
+        json!({
+            "labels":      labels,
+            "annotations": annotations,
+            "endsAt":      ends.to_rfc3339_opts(SecondsFormat::Secs, false),
+        })
+    }).collect();
+    Value::Array(list)
+}
+
+// ── Microsoft Teams ──────────────────────────────────────────────────
+
+/// An Adaptive Card message, as accepted by Teams workflow webhooks.
+fn teams(alerts: &[&Alert], event: &str, text: &str) -> Value {
+    let color = match (event, worst(alerts)) {
+        ("resolved", _)         => "Good",
+        (_, Severity::Critical) => "Attention",
+        (_, Severity::Warning)  => "Warning",
+        (_, Severity::Info)     => "Default",
+    };
+    let mut body = vec![json!({
+        "type": "TextBlock", "text": format!("dtop: {}", title(alerts, event)),
+        "weight": "Bolder", "size": "Medium", "color": color, "wrap": true,
+    })];
+    body.extend(text.lines().map(|l| json!({
+        "type": "TextBlock", "text": l, "wrap": true, "spacing": "Small",
+    })));
+    body.push(json!({
+        "type": "FactSet",
+        "facts": [
+            { "title": "Host",  "value": webhook::hostname() },
+            { "title": "Event", "value": event },
+            { "title": "Time",  "value": now() },
+        ],
+    }));
+    json!({
+        "type": "message",
+        "attachments": [{
+            "contentType": "application/vnd.microsoft.card.adaptive",
+            "content": {
+                "$schema": "http://adaptivecards.io/schemas/adaptive-card.json",
+                "type":    "AdaptiveCard",
+                "version": "1.4",
+                "body":    body,
+            },
+        }],
+    })
+}
@@ -0,0 +1,375 @@
+use crate::alerts::Severity;
+use crate::collectors::nfs::NfsMountStats;
+use crate::collectors::pressure::{psi_value, PsiResource};
+use crate::config::{GraphiteConfig, InfluxConfig};
+use crate::models::device::BlockDevice;
+use crate::models::filesystem::Filesystem;
+use crate::util::health_score::health_score;
+use crate::util::metrics::{self, Snapshot};
+use crate::util::mqtt::mount_slug;
+use crate::util::{http, human, net, webhook};
+use anyhow::{anyhow, bail, Result};
+use base64::engine::general_purpose::STANDARD as B64;
+use base64::Engine;
+use chrono::Local;
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::net::UdpSocket;
+use std::sync::mpsc::{self, SyncSender, TrySendError};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Largest UDP datagram sent; keeps line-protocol packets under a typical MTU.
+const MAX_DATAGRAM: usize = 1400;
+
+/// Identity tags that `influxdb.device_tags` chooses from.
+const DEVICE_TAGS: &[&str] = &["alias", "serial", "model", "type"];
+
+/// A field value. Integers keep the line-protocol `i` suffix so a field's
+/// type never changes between pushes.
+#[derive(Debug, Clone, Copy)]
+pub enum Value {
+    Float(f64),
+    Int(i64),
+}
+
+/// One measurement of one thing: a device, a filesystem, a PSI resource…
+#[derive(Debug, Clone)]
+pub struct Point {
+    /// "device", "filesystem", "nfs", "pressure" or "alerts".
+    pub measurement: &'static str,
+    /// Names the thing in Graphite paths ("sda", "_var_lib"); empty for host-wide points.
+    pub key:         String,
+    pub tags:        Vec<(&'static str, String)>,
+    pub fields:      Vec<(&'static str, Value)>,
+}
+
+/// Device, filesystem, NFS, PSI and alert-count points for `s`.
+pub fn points(s: &Snapshot) -> Vec<Point> {
+    let mut out: Vec<Point> = s.devices.iter().map(device).collect();
+    out.extend(s.filesystems.iter().map(filesystem));
+    out.extend(s.nfs.iter().map(nfs));
+    if let Some(p) = s.pressure {
+        for (resource, r) in [("io", &p.io), ("cpu", &p.cpu), ("memory", &p.mem)] {
+            out.push(pressure(resource, r));
+        }
+    }
+    let count = |sev: Severity| Value::Int(s.alerts.iter().filter(|a| a.severity == sev).count() as i64);
+    out.push(Point {
+        measurement: "alerts",
+        key:         String::new(),
+        tags:        Vec::new(),
+        fields:      vec![
+            ("critical", count(Severity::Critical)),
+            ("warning",  count(Severity::Warning)),
+            ("info",     count(Severity::Info)),
+            ("silenced", Value::Int(s.silenced as i64)),
+        ],
+    });
+    out
+}
+
+fn device(dev: &BlockDevice) -> Point {
+    let mut fields = vec![
+        ("capacity_bytes",      Value::Int(dev.capacity_bytes as i64)),
+        ("read_bytes_per_sec",  Value::Float(dev.read_bytes_per_sec)),
+        ("write_bytes_per_sec", Value::Float(dev.write_bytes_per_sec)),
+        ("read_iops",           Value::Float(dev.read_iops)),
+        ("write_iops",          Value::Float(dev.write_iops)),
+        ("read_latency_ms",     Value::Float(dev.avg_read_latency_ms)),
+        ("write_latency_ms",    Value::Float(dev.avg_write_latency_ms)),
+        ("util_pct",            Value::Float(dev.io_util_pct)),
+    ];
+    if let Some(smart) = &dev.smart {
+        fields.push(("health_score", Value::Int(health_score(dev) as i64)));
+        if let Some(t) = smart.temperature    { fields.push(("temperature_c", Value::Int(t as i64))); }
+        if let Some(h) = smart.power_on_hours { fields.push(("power_on_hours", Value::Int(h as i64))); }
+        if let Some(n) = &smart.nvme {
+            fields.push(("percentage_used",   Value::Int(n.percentage_used as i64)));
+            fields.push(("available_spare",   Value::Int(n.available_spare_pct as i64)));
+            fields.push(("media_errors",      Value::Int(n.media_errors as i64)));
+        }
+    }
+    Point { measurement: "device", key: dev.name.clone(), tags: metrics::device_labels(dev), fields }
+}
+
+fn filesystem(fs: &Filesystem) -> Point {
+    let mut fields = vec![
+        ("size_bytes",  Value::Int(fs.total_bytes as i64)),
+        ("used_bytes",  Value::Int(fs.used_bytes as i64)),
+        ("avail_bytes", Value::Int(fs.avail_bytes as i64)),
+        ("used_pct",    Value::Float(fs.use_pct())),
+        ("inodes",      Value::Int(fs.total_inodes as i64)),
+        ("inodes_free", Value::Int(fs.free_inodes as i64)),
+        ("inodes_pct",  Value::Float(fs.inode_pct())),
+    ];
+    if let Some(r) = fs.fill_rate_bps   { fields.push(("fill_rate_bytes_per_sec", Value::Float(r))); }
+    if let Some(d) = fs.days_until_full { fields.push(("days_until_full", Value::Float(d))); }
+    Point {
+        measurement: "filesystem",
+        key:         mount_slug(&fs.mount),
+        tags:        vec![("mount", fs.mount.clone()), ("device", fs.device.clone()), ("fstype", fs.fs_type.clone())],
+        fields,
+    }
+}
+
+fn nfs(m: &NfsMountStats) -> Point {
+    // Cumulative RTT, not the since-mount average: its derivative over the ops
+    // count gives the RTT between any two pushes
+    let mut fields = vec![
+        ("read_ops",           Value::Int(m.read_ops as i64)),
+        ("write_ops",          Value::Int(m.write_ops as i64)),
+        ("read_bytes",         Value::Int(m.server_bytes_read as i64)),
+        ("written_bytes",      Value::Int(m.server_bytes_written as i64)),
+        ("read_rtt_total_ms",  Value::Float(m.read_rtt_total_ms)),
+        ("write_rtt_total_ms", Value::Float(m.write_rtt_total_ms)),
+    ];
+    if let Some(rtt) = m.interval_rtt_ms { fields.push(("rtt_ms", Value::Float(rtt))); }
+    Point {
+        measurement: "nfs",
+        key:         mount_slug(&m.mount),
+        tags:        vec![("mount", m.mount.clone()), ("export", m.device.clone())],
+        fields,
+    }
+}
+
+fn pressure(resource: &str, r: &PsiResource) -> Point {
+    let v = |x: f32| Value::Float(psi_value(x));
+    Point {
+        measurement: "pressure",
+        key:         resource.to_string(),
+        tags:        vec![("resource", resource.to_string())],
+        fields:      vec![
+            ("some_avg10", v(r.some.avg10)), ("some_avg60", v(r.some.avg60)), ("some_avg300", v(r.some.avg300)),
+            ("full_avg10", v(r.full.avg10)), ("full_avg60", v(r.full.avg60)), ("full_avg300", v(r.full.avg300)),
+        ],
+    }
+}
+
+// ── InfluxDB line protocol ───────────────────────────────────────────
+
+/// Backslash-escape `special` characters (and backslashes) in `s`.
+fn escape(s: &str, special: &[char]) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        if c == '\\' || special.contains(&c) { out.push('\\'); }
+        out.push(c);
+    }
+    out
+}
+
+/// `points` as line protocol, one line per point, stamped `ts_ns`. Tags are
+/// `host`, `influxdb.tags` and the point's own; device identity tags only when
+/// listed in `device_tags`, and empty values never.
+pub fn influx_lines(points: &[Point], cfg: &InfluxConfig, ts_ns: i128) -> String {
+    let host = webhook::hostname();
+    let mut out = String::new();
+    for p in points {
+        let fields: Vec<String> = p.fields.iter().filter_map(|(k, v)| match v {
+            Value::Float(f) if !f.is_finite() => None,
+            Value::Float(f) => Some(format!("{}={}", k, f)),
+            Value::Int(i)   => Some(format!("{}={}i", k, i)),
+        }).collect();
+        if fields.is_empty() { continue; }
+
+        // Sorted by key, as InfluxDB prefers; a point's own tags win over configured ones
+        let mut tags: BTreeMap<String, String> = BTreeMap::new();
+        tags.insert("host".into(), host.clone());
+        tags.extend(cfg.tags.iter().map(|(k, v)| (k.clone(), v.clone())));
+        tags.extend(p.tags.iter()
+            .filter(|(k, _)| !DEVICE_TAGS.contains(k) || cfg.device_tags.iter().any(|t| t == k))
+            .map(|(k, v)| (k.to_string(), v.clone())));
+        tags.retain(|_, v| !v.is_empty());
+
+        out.push_str(&escape(&format!("{}{}", cfg.measurement_prefix, p.measurement), &[',', ' ']));
+        for (k, v) in &tags {
+            out.push_str(&format!(",{}={}", escape(k, &[',', '=', ' ']), escape(v, &[',', '=', ' '])));
+        }
+        out.push_str(&format!(" {} {}\n", fields.join(","), ts_ns));
+    }
+    out
+}
+
+// ── Graphite plaintext ───────────────────────────────────────────────
+
+/// A Graphite path component: anything but letters, digits, '-' and '_' becomes '_'.
+fn component(s: &str) -> String {
+    s.chars().map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' }).collect()
+}
+
+/// `points` as `path value timestamp` lines under `graphite.prefix`:
+/// dtop.<host>.device.sda.read_iops, dtop.<host>.filesystem._var_lib.used_pct…
+pub fn graphite_lines(points: &[Point], cfg: &GraphiteConfig, ts_secs: i64) -> String {
+    let prefix = cfg.prefix.replace("{host}", &webhook::hostname().replace('.', "_"));
+    let prefix = prefix.trim_matches('.');
+    let mut out = String::new();
+    for p in points {
+        let mut key = p.key.clone();
+        if p.measurement == "device" && cfg.device_key != "device" {
+            if let Some((_, v)) = p.tags.iter().find(|(k, v)| *k == cfg.device_key && !v.is_empty()) {
+                key = v.clone();
+            }
+        }
+        let mut path = format!("{}.{}", prefix, p.measurement);
+        if !key.is_empty() { path = format!("{}.{}", path, component(&key)); }
+        for (field, v) in &p.fields {
+            let value = match v {
+                Value::Float(f) if !f.is_finite() => continue,
+                Value::Float(f) => f.to_string(),
+                Value::Int(i)   => i.to_string(),
+            };
+            out.push_str(&format!("{}.{} {} {}\n", path, field, value, ts_secs));
+        }
+    }
+    out
+}
+
+// ── Transports ───────────────────────────────────────────────────────
+
+/// A configured push destination.
+#[derive(Debug, Clone)]
+pub enum Target {
+    Influx(InfluxConfig),
+    Graphite(GraphiteConfig),
+}
+
+impl Target {
+    /// The targets enabled in the config.
+    pub fn configured(influx: &InfluxConfig, graphite: &GraphiteConfig) -> Vec<Target> {
+        let mut out = Vec::new();
+        if !influx.url.is_empty() || !influx.udp.is_empty() { out.push(Target::Influx(influx.clone())); }
+        if !graphite.host.is_empty() { out.push(Target::Graphite(graphite.clone())); }
+        out
+    }
+
+    /// "influxdb http://…", "influxdb udp://…" or "graphite host:port".
+    pub fn describe(&self) -> String {
+        match self {
+            Target::Influx(c) if !c.udp.is_empty() => format!("influxdb udp://{}", c.udp),
+            Target::Influx(c)   => format!("influxdb {}", c.url.split('?').next().unwrap_or("")),
+            Target::Graphite(c) => format!("graphite {}:{}", c.host, c.port),
+        }
+    }
+
+    pub fn interval(&self) -> Duration {
+        let s = match self {
+            Target::Influx(c)   => &c.interval,
+            Target::Graphite(c) => &c.interval,
+        };
+        Duration::from_secs(human::parse_duration(s).unwrap_or(30).max(1))
+    }
+
+    fn timeout(&self) -> Duration {
+        let secs = match self {
+            Target::Influx(c)   => c.timeout_secs,
+            Target::Graphite(c) => c.timeout_secs,
+        };
+        Duration::from_secs(secs.max(1))
+    }
+
+    /// Deliver `points`, collected at `at`.
+    pub fn send(&self, points: &[Point], at: SystemTime) -> Result<()> {
+        let since = at.duration_since(UNIX_EPOCH).unwrap_or_default();
+        match self {
+            Target::Influx(c) if !c.udp.is_empty() => send_udp(&c.udp, &influx_lines(points, c, since.as_nanos() as i128)),
+            Target::Influx(c) => {
+                let body = influx_lines(points, c, since.as_nanos() as i128);
+                let mut headers = vec![("Content-Type".to_string(), "text/plain; charset=utf-8".to_string())];
+                if !c.token.is_empty() {
+                    headers.push(("Authorization".into(), format!("Token {}", c.token)));
+                } else if !c.username.is_empty() {
+                    headers.push(("Authorization".into(),
+                        format!("Basic {}", B64.encode(format!("{}:{}", c.username, c.password)))));
+                }
+                let r = http::post(&c.url, &headers, body.as_bytes(), self.timeout())?;
+                if !r.is_success() {
+                    bail!("HTTP {}: {}", r.status, r.body.chars().take(200).collect::<String>());
+                }
+                Ok(())
+            }
+            Target::Graphite(c) => {
+                let text = graphite_lines(points, c, since.as_secs() as i64);
+                let mut tcp = net::connect(&c.host, c.port, self.timeout())?;
+                tcp.write_all(text.as_bytes())?;
+                tcp.flush()?;
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Send `lines` to the "host:port" UDP listener, packed into datagrams that
+/// end on line boundaries.
+fn send_udp(server: &str, lines: &str) -> Result<()> {
+    let (host, port) = server.rsplit_once(':').ok_or_else(|| anyhow!("udp {:?}: expected host:port", server))?;
+    let port: u16 = port.parse().map_err(|_| anyhow!("udp {:?}: invalid port", server))?;
+    let addr = net::resolve(host.trim_start_matches('[').trim_end_matches(']'), port)?[0];
+    let socket = UdpSocket::bind(if addr.is_ipv4() { "0.0.0.0:0" } else { "[::]:0" })?;
+    let mut packet = String::new();
+    for line in lines.split_inclusive('\n') {
+        if !packet.is_empty() && packet.len() + line.len() > MAX_DATAGRAM {
+            socket.send_to(packet.as_bytes(), addr)?;
+            packet.clear();
+        }
+        packet.push_str(line);
+    }
+    if !packet.is_empty() { socket.send_to(packet.as_bytes(), addr)?; }
+    Ok(())
+}
+
+/// Pushes to one target from a background thread, so a slow or unreachable
+/// server never holds up monitoring. At most one batch waits behind a running
+/// send; newer points are dropped until it drains. Failures are logged when
+/// they start and stop.
+pub struct Pusher {
+    target:   Target,
+    every:    Duration,
+    last:     Option<Instant>,
+    tx:       SyncSender<(Vec<Point>, SystemTime)>,
+    dropping: bool,
+}
+
+impl Pusher {
+    pub fn start(target: Target) -> Self {
+        let (tx, rx) = mpsc::sync_channel::<(Vec<Point>, SystemTime)>(1);
+        let worker = target.clone();
+        std::thread::spawn(move || {
+            let name = worker.describe();
+            let mut failing: Option<String> = None;
+            for (points, at) in rx {
+                match worker.send(&points, at) {
+                    Ok(()) => if failing.take().is_some() { log(&format!("{}: recovered", name)) },
+                    Err(e) => {
+                        let msg = format!("{:#}", e);
+                        if failing.as_ref() != Some(&msg) { log(&format!("{}: {}", name, msg)); }
+                        failing = Some(msg);
+                    }
+                }
+            }
+        });
+        Self { every: target.interval(), target, last: None, tx, dropping: false }
+    }
+
+    pub fn target(&self) -> &Target {
+        &self.target
+    }
+
+    /// Whether a push interval has passed since the last push.
+    pub fn due(&self) -> bool {
+        self.last.is_none_or(|t| t.elapsed() >= self.every)
+    }
+
+    pub fn push(&mut self, points: Vec<Point>) {
+        self.last = Some(Instant::now());
+        match self.tx.try_send((points, SystemTime::now())) {
+            Err(TrySendError::Full(_)) => {
+                if !self.dropping { log(&format!("{}: still sending, dropping newer points", self.target.describe())); }
+                self.dropping = true;
+            }
+            _ => self.dropping = false,
+        }
+    }
+}
+
+fn log(msg: &str) {
+    eprintln!("{} push: {}", Local::now().format("%H:%M:%S"), msg);
+}
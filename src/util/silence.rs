@@ -0,0 +1,236 @@
+use crate::alerts::{Alert, Severity};
+use crate::config::MaintenanceWindow;
+use crate::util::glob::glob_match;
+use chrono::{DateTime, Datelike, Duration, Local, NaiveTime, TimeZone};
+use serde::{Deserialize, Serialize};
+use crate::util::expr;
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+
+/// Which alerts a silence or maintenance window applies to. Empty fields match anything.
+///
+/// Written as comma-separated `key=value` pairs:
+/// `device=sd*,severity=warn`, `mount=/srv/*,rule=fs.*`, `message=temperature|latency`.
+/// `device`, `mount` and `rule` are globs; `message` is a regex; `severity` is
+/// "warn" or "crit".
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Matcher {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub device:   Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mount:    Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub severity: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rule:     Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message:  Option<String>,
+}
+
+impl Matcher {
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let mut m = Matcher::default();
+        for part in s.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            let (key, value) = part.split_once('=')
+                .ok_or_else(|| format!("expected key=value, got '{}'", part))?;
+            let value = value.trim().to_string();
+            match key.trim() {
+                "device"   => m.device = Some(value),
+                "mount"    => m.mount = Some(value),
+                "rule"     => m.rule = Some(value),
+                "severity" => match value.as_str() {
+                    "warn" | "warning"  => m.severity = Some("warn".into()),
+                    "crit" | "critical" => m.severity = Some("crit".into()),
+                    _ => return Err(format!("severity must be warn or crit, got '{}'", value)),
+                },
+                "message" => {
+                    regex::Regex::new(&value).map_err(|e| format!("bad message regex: {}", e))?;
+                    m.message = Some(value);
+                }
+                other => return Err(format!("unknown matcher key '{}' (device, mount, severity, rule, message)", other)),
+            }
+        }
+        Ok(m)
+    }
+
+    /// Matcher scoped to exactly one alert condition (used by the TUI).
+    pub fn for_alert(alert: &Alert) -> Self {
+        Matcher {
+            device: alert.device.clone(),
+            mount:  alert.mount.clone(),
+            rule:   Some(alert.rule.clone()).filter(|r| !r.is_empty()),
+            ..Default::default()
+        }
+    }
+
+    pub fn matches(&self, alert: &Alert) -> bool {
+        let field = |pat: &Option<String>, val: &Option<String>| match pat {
+            None    => true,
+            Some(p) => val.as_deref().is_some_and(|v| glob_match(p, v)),
+        };
+        if !field(&self.device, &alert.device) || !field(&self.mount, &alert.mount) {
+            return false;
+        }
+        if let Some(r) = &self.rule {
+            if !glob_match(r, &alert.rule) { return false; }
+        }
+        if let Some(sev) = &self.severity {
+            let want = if sev == "crit" { Severity::Critical } else { Severity::Warning };
+            if alert.severity != want { return false; }
+        }
+        if let Some(re) = &self.message {
+            if !expr::regex(re).is_some_and(|re| re.is_match(&alert.message)) { return false; }
+        }
+        true
+    }
+}
+
+
+impl fmt::Display for Matcher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let parts: Vec<String> = [
+            ("device", &self.device), ("mount", &self.mount), ("severity", &self.severity),
+            ("rule", &self.rule), ("message", &self.message),
+        ].iter()
+            .filter_map(|(k, v)| v.as_ref().map(|v| format!("{}={}", k, v)))
+            .collect();
+        if parts.is_empty() { write!(f, "(all alerts)") } else { write!(f, "{}", parts.join(",")) }
+    }
+}
+
+/// A time-boxed mute for matching alerts.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Silence {
+    pub id:         u64,
+    pub matcher:    Matcher,
+    /// Unix timestamps
+    pub starts_at:  i64,
+    pub ends_at:    i64,
+    pub author:     String,
+    #[serde(default)]
+    pub comment:    String,
+    pub created_at: i64,
+}
+
+impl Silence {
+    pub fn is_active(&self, now: i64) -> bool {
+        now >= self.starts_at && now < self.ends_at
+    }
+}
+
+fn silence_path() -> Option<PathBuf> {
+    dirs::data_local_dir().map(|p| p.join("dtop").join("silences.json"))
+}
+
+/// Load all stored silences (including expired ones not yet pruned).
+pub fn load() -> Vec<Silence> {
+    let path = match silence_path() { Some(p) => p, None => return Vec::new() };
+    let text = match fs::read_to_string(&path) { Ok(t) => t, Err(_) => return Vec::new() };
+    serde_json::from_str(&text).unwrap_or_default()
+}
+
+/// Persist silences, dropping any that ended more than a day ago (best-effort).
+pub fn save(silences: &[Silence]) {
+    let path = match silence_path() { Some(p) => p, None => return };
+    if let Some(parent) = path.parent() { let _ = fs::create_dir_all(parent); }
+    let cutoff = Local::now().timestamp() - 86_400;
+    let keep: Vec<&Silence> = silences.iter().filter(|s| s.ends_at > cutoff).collect();
+    if let Ok(json) = serde_json::to_string_pretty(&keep) { let _ = fs::write(path, json); }
+}
+
+/// Store a new silence and return it.
+pub fn add(matcher: Matcher, starts_at: i64, ends_at: i64, author: &str, comment: &str) -> Silence {
+    let mut all = load();
+    let silence = Silence {
+        id:         all.iter().map(|s| s.id).max().unwrap_or(0) + 1,
+        matcher,
+        starts_at,
+        ends_at,
+        author:     author.to_string(),
+        comment:    comment.to_string(),
+        created_at: Local::now().timestamp(),
+    };
+    all.push(silence.clone());
+    save(&all);
+    silence
+}
+
+/// Remove a silence by ID. Returns false if no such silence exists.
+pub fn remove(id: u64) -> bool {
+    let mut all = load();
+    let before = all.len();
+    all.retain(|s| s.id != id);
+    if all.len() == before { return false; }
+    save(&all);
+    true
+}
+
+/// Format a Unix timestamp as local "YYYY-MM-DD HH:MM".
+pub fn fmt_time(ts: i64) -> String {
+    Local.timestamp_opt(ts, 0)
+        .single()
+        .map(|dt| dt.format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// User name recorded as a silence's author.
+pub fn current_user() -> String {
+    std::env::var("SUDO_USER")
+        .or_else(|_| std::env::var("USER"))
+        .unwrap_or_else(|_| "unknown".into())
+}
+
+impl MaintenanceWindow {
+    /// The parsed `match`, or why it is invalid. Parsed once.
+    pub fn parsed(&self) -> Result<&Matcher, &str> {
+        self.parsed.get_or_init(|| Matcher::parse(&self.matcher)).as_ref().map_err(|e| e.as_str())
+    }
+
+    /// Why this window can never apply, if its `start`, `duration` or `match` is invalid.
+    pub fn problem(&self) -> Option<String> {
+        if NaiveTime::parse_from_str(&self.start, "%H:%M").is_err() {
+            return Some(format!("invalid start {:?} (expected HH:MM)", self.start));
+        }
+        if crate::util::human::parse_duration(&self.duration).is_none() {
+            return Some(format!("invalid duration {:?}", self.duration));
+        }
+        // Regex errors span several lines; keep the marker on one
+        self.parsed().err().map(|e| format!("invalid match: {}", e.split_whitespace().collect::<Vec<_>>().join(" ")))
+    }
+
+    /// Whether `now` falls inside this window. Windows may run past midnight.
+    pub fn is_active(&self, now: DateTime<Local>) -> bool {
+        let Ok(start) = NaiveTime::parse_from_str(&self.start, "%H:%M") else { return false };
+        let Some(len) = crate::util::human::parse_duration(&self.duration) else { return false };
+        // A window that started yesterday may still be running
+        for back in 0..=((len / 86_400) as i64 + 1) {
+            let day = now.date_naive() - Duration::days(back);
+            if !self.days.is_empty() {
+                let wd = day.weekday().to_string().to_lowercase(); // "mon", "tue", …
+                if !self.days.iter().any(|d| d.to_lowercase().starts_with(&wd)) { continue; }
+            }
+            let Some(begin) = Local.from_local_datetime(&day.and_time(start)).earliest() else { continue };
+            if now >= begin && now < begin + Duration::seconds(len as i64) {
+                return true;
+            }
+        }
+        false
+    }
+}
+
+/// Reason an alert is muted right now: an active silence or maintenance window.
+pub fn silenced_by(
+    alert:    &Alert,
+    silences: &[Silence],
+    windows:  &[MaintenanceWindow],
+    now:      DateTime<Local>,
+) -> Option<String> {
+    let ts = now.timestamp();
+    if let Some(s) = silences.iter().find(|s| s.is_active(ts) && s.matcher.matches(alert)) {
+        return Some(format!("silence #{}", s.id));
+    }
+    windows.iter()
+        .find(|w| w.is_active(now) && w.parsed().is_ok_and(|m| m.matches(alert)))
+        .map(|w| format!("maintenance '{}'", w.name))
+}
@@ -0,0 +1,119 @@
+use crate::models::device::BlockDevice;
+use crate::models::smart::SmartData;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+/// Pseudo attribute ID for NVMe media errors (same sentinel as the anomaly log).
+pub const NVME_MEDIA_ERRORS: u32 = 9999;
+
+/// Change points older than this are pruned (one is kept as the window baseline).
+const MAX_AGE_SECS: i64 = 90 * 86_400;
+
+/// Timestamped raw values of the SMART counters used by rate rules.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DeviceHistory {
+    /// attr ID → (timestamp, raw value) change points, oldest first.
+    pub series: HashMap<u32, Vec<(i64, u64)>>,
+}
+
+/// Maps device key (see `key`) → DeviceHistory; persisted to disk.
+pub type SmartHistory = HashMap<String, DeviceHistory>;
+
+/// History key of `dev`: its serial number, so history follows the disk when
+/// its kernel name changes, falling back to the name.
+pub fn key(dev: &BlockDevice) -> String {
+    dev.serial.clone().filter(|s| !s.trim().is_empty()).unwrap_or_else(|| dev.name.clone())
+}
+
+/// The history of `dev`, if any was recorded.
+pub fn get<'a>(hist: &'a SmartHistory, dev: &BlockDevice) -> Option<&'a DeviceHistory> {
+    hist.get(&key(dev))
+}
+
+pub fn history_path() -> Option<PathBuf> {
+    dirs::data_local_dir().map(|p| p.join("dtop").join("smart_history.json"))
+}
+
+pub fn load() -> SmartHistory {
+    let path = match history_path() {
+        Some(p) => p,
+        None    => return SmartHistory::new(),
+    };
+    let text = match fs::read_to_string(&path) {
+        Ok(t)  => t,
+        Err(_) => return SmartHistory::new(),
+    };
+    serde_json::from_str(&text).unwrap_or_default()
+}
+
+pub fn save(hist: &SmartHistory) {
+    let path = match history_path() {
+        Some(p) => p,
+        None    => return,
+    };
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    if let Ok(text) = serde_json::to_string(hist) {
+        let _ = fs::write(&path, text);
+    }
+}
+
+/// Raw value of a tracked counter in a SMART snapshot.
+fn raw_value(smart: &SmartData, attr: u32) -> Option<u64> {
+    if attr == NVME_MEDIA_ERRORS {
+        return smart.nvme.as_ref().map(|n| n.media_errors);
+    }
+    smart.attributes.iter().find(|a| a.id == attr).map(|a| a.raw_value)
+}
+
+/// Record the current raw value of each attribute in `attrs` for a device.
+/// A sample is only stored when the value changes, so static counters cost nothing.
+/// History recorded under the device name by older versions moves to its serial.
+/// Returns true if anything was added.
+pub fn record(hist: &mut SmartHistory, device: &BlockDevice, smart: &SmartData, attrs: &[u32]) -> bool {
+    let now = chrono::Local::now().timestamp();
+    let key = key(device);
+    let mut changed = false;
+    if key != device.name && !hist.contains_key(&key) {
+        if let Some(old) = hist.remove(&device.name) {
+            hist.insert(key.clone(), old);
+            changed = true;
+        }
+    }
+    let dev = hist.entry(key).or_default();
+
+    for &attr in attrs {
+        let Some(value) = raw_value(smart, attr) else { continue };
+        let series = dev.series.entry(attr).or_default();
+        if series.last().map(|&(_, v)| v) != Some(value) {
+            series.push((now, value));
+            changed = true;
+        }
+        // Prune old change points, keeping the newest one before the cutoff as baseline
+        let cutoff = now - MAX_AGE_SECS;
+        let old = series.iter().take_while(|&&(ts, _)| ts < cutoff).count();
+        if old > 1 {
+            series.drain(..old - 1);
+            changed = true;
+        }
+    }
+    changed
+}
+
+/// Growth of a counter over the last `window_secs`: current value minus the value
+/// at the start of the window. Windows reaching back before the first poll compare
+/// against the first recorded value, so long-standing counts read as zero growth.
+pub fn delta(dev: &DeviceHistory, attr: u32, window_secs: i64, now: i64) -> Option<u64> {
+    let series = dev.series.get(&attr)?;
+    let &(_, current) = series.last()?;
+    let start = now - window_secs;
+    let base = series.iter()
+        .rev()
+        .find(|&&(ts, _)| ts <= start)
+        .or_else(|| series.first())
+        .map(|&(_, v)| v)?;
+    Some(current.saturating_sub(base))
+}
@@ -0,0 +1,337 @@
+use crate::config::SmtpConfig;
+use crate::util::{http, net};
+use anyhow::{anyhow, bail, Result};
+use base64::engine::general_purpose::STANDARD as B64;
+use base64::Engine;
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::time::Duration;
+
+/// One mail to send.
+#[derive(Debug, Clone)]
+pub struct Message {
+    pub from:         String,
+    pub to:           Vec<String>,
+    pub subject:      String,
+    /// "text/plain" or "text/html"
+    pub content_type: &'static str,
+    pub body:         String,
+}
+
+impl Message {
+    /// RFC 5322 headers plus the base64 body, CRLF line endings. Base64 keeps
+    /// long lines and UTF-8 safe without relying on 8BITMIME.
+    fn wire(&self, host: &str) -> String {
+        let now = chrono::Local::now();
+        let mut out = format!(
+            "From: {}\r\nTo: {}\r\nSubject: {}\r\nDate: {}\r\nMessage-ID: <{}.{}@{}>\r\n\
+             MIME-Version: 1.0\r\nContent-Type: {}; charset=utf-8\r\nContent-Transfer-Encoding: base64\r\n\r\n",
+            self.from, self.to.join(", "), encode_header(&self.subject), now.to_rfc2822(),
+            now.timestamp_nanos_opt().unwrap_or(0), std::process::id(), host, self.content_type,
+        );
+        let encoded = B64.encode(self.body.as_bytes());
+        for chunk in encoded.as_bytes().chunks(76) {
+            out.push_str(std::str::from_utf8(chunk).unwrap_or(""));
+            out.push_str("\r\n");
+        }
+        out
+    }
+}
+
+/// RFC 2047 encoded-word for non-ASCII header text.
+fn encode_header(s: &str) -> String {
+    if s.is_ascii() { s.to_string() } else { format!("=?utf-8?B?{}?=", B64.encode(s.as_bytes())) }
+}
+
+/// "Ops <ops@example.com>" → "ops@example.com"
+fn address(s: &str) -> &str {
+    match (s.rfind('<'), s.rfind('>')) {
+        (Some(a), Some(b)) if a < b => &s[a + 1..b],
+        _                          => s.trim(),
+    }
+}
+
+enum Stream {
+    Plain(TcpStream),
+    Tls(Box<rustls::StreamOwned<rustls::ClientConnection, TcpStream>>),
+}
+
+impl Read for Stream {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match self {
+            Stream::Plain(s) => s.read(buf),
+            Stream::Tls(s)   => s.read(buf),
+        }
+    }
+}
+
+impl Write for Stream {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            Stream::Plain(s) => s.write(buf),
+            Stream::Tls(s)   => s.write(buf),
+        }
+    }
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            Stream::Plain(s) => s.flush(),
+            Stream::Tls(s)   => s.flush(),
+        }
+    }
+}
+
+fn wrap_tls(tcp: TcpStream, host: &str) -> Result<Stream> {
+    let name = rustls::pki_types::ServerName::try_from(host.to_string())
+        .map_err(|_| anyhow!("invalid TLS server name {}", host))?;
+    let conn = rustls::ClientConnection::new(http::tls_config(), name)?;
+    Ok(Stream::Tls(Box::new(rustls::StreamOwned::new(conn, tcp))))
+}
+
+/// An SMTP conversation. Replies are read line by line from `buf`.
+struct Session {
+    stream: Stream,
+    buf:    Vec<u8>,
+}
+
+impl Session {
+    fn line(&mut self) -> Result<String> {
+        loop {
+            if let Some(i) = self.buf.iter().position(|&b| b == b'\n') {
+                let line: Vec<u8> = self.buf.drain(..=i).collect();
+                return Ok(String::from_utf8_lossy(&line).trim_end().to_string());
+            }
+            let mut chunk = [0u8; 1024];
+            let n = self.stream.read(&mut chunk)?;
+            if n == 0 { bail!("server closed the connection"); }
+            self.buf.extend_from_slice(&chunk[..n]);
+        }
+    }
+
+    /// Read a (possibly multi-line) reply: its code and the text of every line.
+    fn reply(&mut self) -> Result<(u16, Vec<String>)> {
+        let mut lines = Vec::new();
+        loop {
+            let line = self.line()?;
+            let code = line.get(..3).and_then(|c| c.parse().ok())
+                .ok_or_else(|| anyhow!("malformed SMTP reply: {:?}", line))?;
+            let more = line.as_bytes().get(3) == Some(&b'-');
+            lines.push(line.get(4..).unwrap_or("").to_string());
+            if !more { return Ok((code, lines)); }
+        }
+    }
+
+    /// Send `cmd` and require a reply code in `ok`. `shown` replaces the
+    /// command in error messages (for credentials).
+    fn cmd(&mut self, cmd: &str, ok: &[u16], shown: Option<&str>) -> Result<Vec<String>> {
+        self.stream.write_all(cmd.as_bytes())?;
+        self.stream.write_all(b"\r\n")?;
+        self.stream.flush()?;
+        self.expect(ok, shown.unwrap_or(cmd))
+    }
+
+    fn expect(&mut self, ok: &[u16], what: &str) -> Result<Vec<String>> {
+        let (code, lines) = self.reply()?;
+        if !ok.contains(&code) {
+            bail!("{} → {} {}", what, code, lines.join(" "));
+        }
+        Ok(lines)
+    }
+}
+
+/// Dot-stuffing: double a leading '.' on each line so none reads as the end of
+/// DATA. Base64 lines never start with '.', but headers might.
+fn dot_stuff(text: &str) -> String {
+    let mut data = String::new();
+    for line in text.split_terminator("\r\n") {
+        if line.starts_with('.') { data.push('.'); }
+        data.push_str(line);
+        data.push_str("\r\n");
+    }
+    data
+}
+
+/// Deliver `msg` through the relay in `cfg`: EHLO, optional STARTTLS or implicit
+/// TLS, AUTH PLAIN/LOGIN when a username is set, then one transaction.
+pub fn send(cfg: &SmtpConfig, msg: &Message) -> Result<()> {
+    if msg.to.is_empty() { bail!("no recipients"); }
+    let timeout = Duration::from_secs(cfg.timeout_secs.max(1));
+    let tcp = net::connect(&cfg.host, cfg.port, timeout)?;
+    let loopback = tcp.peer_addr()?.ip().is_loopback();
+
+    let security = cfg.security.as_str();
+    let stream = match security {
+        "tls"              => wrap_tls(tcp, &cfg.host)?,
+        "" | "none" | "starttls" => Stream::Plain(tcp),
+        other              => bail!("unknown smtp.security {:?} (expected none, starttls or tls)", other),
+    };
+    let mut s = Session { stream, buf: Vec::new() };
+    let helo = crate::util::webhook::hostname();
+
+    s.expect(&[220], "greeting")?;
+    let mut caps = s.cmd(&format!("EHLO {}", helo), &[250], None)?;
+    if security == "starttls" {
+        if !caps.iter().any(|c| c.eq_ignore_ascii_case("STARTTLS")) {
+            bail!("{} does not offer STARTTLS", cfg.host);
+        }
+        s.cmd("STARTTLS", &[220], None)?;
+        let Stream::Plain(tcp) = s.stream else { unreachable!("STARTTLS on a TLS stream") };
+        s = Session { stream: wrap_tls(tcp, &cfg.host)?, buf: Vec::new() };
+        caps = s.cmd(&format!("EHLO {}", helo), &[250], None)?;
+    }
+
+    if !cfg.username.is_empty() {
+        if matches!(s.stream, Stream::Plain(_)) && !loopback {
+            bail!("refusing to send SMTP credentials unencrypted to {} (set smtp.security)", cfg.host);
+        }
+        let mechs: Vec<String> = caps.iter()
+            .filter_map(|c| c.to_ascii_uppercase().strip_prefix("AUTH").map(str::to_string))
+            .flat_map(|m| m.split([' ', '=']).map(str::to_string).collect::<Vec<_>>())
+            .collect();
+        if mechs.iter().any(|m| m == "PLAIN") {
+            let token = B64.encode(format!("\0{}\0{}", cfg.username, cfg.password));
+            s.cmd(&format!("AUTH PLAIN {}", token), &[235], Some("AUTH PLAIN"))?;
+        } else if mechs.iter().any(|m| m == "LOGIN") {
+            s.cmd("AUTH LOGIN", &[334], None)?;
+            s.cmd(&B64.encode(&cfg.username), &[334], Some("AUTH LOGIN (username)"))?;
+            s.cmd(&B64.encode(&cfg.password), &[235], Some("AUTH LOGIN (password)"))?;
+        } else {
+            bail!("{} offers no supported AUTH mechanism (PLAIN or LOGIN)", cfg.host);
+        }
+    }
+
+    s.cmd(&format!("MAIL FROM:<{}>", address(&msg.from)), &[250], None)?;
+    for rcpt in &msg.to {
+        s.cmd(&format!("RCPT TO:<{}>", address(rcpt)), &[250, 251], None)?;
+    }
+    s.cmd("DATA", &[354], None)?;
+    s.stream.write_all(dot_stuff(&msg.wire(&helo)).as_bytes())?;
+    s.cmd(".", &[250], Some("end of DATA"))?;
+    let _ = s.cmd("QUIT", &[221], None);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader};
+    use std::net::{IpAddr, TcpListener, UdpSocket};
+
+    /// Accept one connection on `listener` and play a relay that offers `ehlo`
+    /// and accepts everything; returns the client's side of the conversation.
+    fn fake_relay(listener: TcpListener, ehlo: &'static str) -> std::thread::JoinHandle<Vec<String>> {
+        std::thread::spawn(move || {
+            let (mut conn, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(conn.try_clone().unwrap());
+            conn.write_all(b"220 fake ESMTP\r\n").unwrap();
+            let (mut seen, mut in_data, mut login) = (Vec::new(), false, 0);
+            let mut line = String::new();
+            while reader.read_line(&mut line).is_ok_and(|n| n > 0) {
+                let cmd = line.trim_end_matches("\r\n").to_string();
+                line.clear();
+                seen.push(cmd.clone());
+                let reply: &[u8] = if in_data {
+                    if cmd != "." { continue; }
+                    in_data = false;
+                    b"250 queued\r\n"
+                } else if login > 0 {
+                    login -= 1;
+                    if login > 0 { b"334 UGFzc3dvcmQ6\r\n" } else { b"235 ok\r\n" }
+                } else if cmd.starts_with("EHLO") {
+                    ehlo.as_bytes()
+                } else if cmd.starts_with("AUTH PLAIN") {
+                    b"235 ok\r\n"
+                } else if cmd == "AUTH LOGIN" {
+                    login = 2;
+                    b"334 VXNlcm5hbWU6\r\n"
+                } else if cmd == "DATA" {
+                    in_data = true;
+                    b"354 go ahead\r\n"
+                } else if cmd == "QUIT" {
+                    conn.write_all(b"221 bye\r\n").unwrap();
+                    break;
+                } else {
+                    b"250 ok\r\n"
+                };
+                conn.write_all(reply).unwrap();
+            }
+            seen
+        })
+    }
+
+    fn config(host: &str, port: u16) -> SmtpConfig {
+        SmtpConfig {
+            host:         host.to_string(),
+            port,
+            username:     "dtop".to_string(),
+            password:     "secret".to_string(),
+            timeout_secs: 5,
+            ..SmtpConfig::default()
+        }
+    }
+
+    fn message() -> Message {
+        Message {
+            from:         "dtop <dtop@example.com>".to_string(),
+            to:           vec!["Ops <ops@example.com>".to_string(), "oncall@example.com".to_string()],
+            subject:      "Disk alert".to_string(),
+            content_type: "text/plain",
+            body:         "sda is failing".to_string(),
+        }
+    }
+
+    #[test]
+    fn plain_session_to_loopback_relay() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let relay = fake_relay(listener, "250-fake\r\n250-SIZE 1000000\r\n250-AUTH LOGIN PLAIN\r\n250 8BITMIME\r\n");
+        send(&config("127.0.0.1", port), &message()).unwrap();
+
+        let seen = relay.join().unwrap();
+        let token = B64.encode("\0dtop\0secret");
+        assert!(seen[0].starts_with("EHLO "), "{:?}", seen);
+        assert_eq!(seen[1], format!("AUTH PLAIN {}", token));
+        assert_eq!(seen[2..5], ["MAIL FROM:<dtop@example.com>", "RCPT TO:<ops@example.com>", "RCPT TO:<oncall@example.com>"]);
+        assert_eq!(seen[5], "DATA");
+        assert!(seen.contains(&"Subject: Disk alert".to_string()), "{:?}", seen);
+        assert!(seen.contains(&B64.encode("sda is failing")), "{:?}", seen);
+        assert_eq!(seen[seen.len() - 2..], [".", "QUIT"]);
+    }
+
+    #[test]
+    fn multi_line_ehlo_offers_auth_login() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        // Old-style "AUTH=" line, and the mechanism isn't on the final line
+        let relay = fake_relay(listener, "250-fake greets you\r\n250-AUTH=LOGIN\r\n250-PIPELINING\r\n250 HELP\r\n");
+        send(&config("localhost", port), &message()).unwrap();
+
+        let seen = relay.join().unwrap();
+        assert_eq!(seen[1..4], ["AUTH LOGIN".to_string(), B64.encode("dtop"), B64.encode("secret")]);
+        assert!(seen[4].starts_with("MAIL FROM:"), "{:?}", seen);
+    }
+
+    #[test]
+    fn auth_refused_over_plaintext_to_remote_relay() {
+        // Needs an address that isn't loopback: whichever one routes outwards
+        let Some(ip) = UdpSocket::bind("0.0.0.0:0")
+            .and_then(|s| s.connect("192.0.2.1:9").and(s.local_addr()))
+            .ok()
+            .map(|a| a.ip())
+            .filter(|ip: &IpAddr| !ip.is_loopback() && !ip.is_unspecified())
+        else { return };
+        let listener = TcpListener::bind((ip, 0)).unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let relay = fake_relay(listener, "250-fake\r\n250 AUTH PLAIN\r\n");
+
+        let err = send(&config(&ip.to_string(), port), &message()).unwrap_err();
+        assert!(err.to_string().contains("refusing to send SMTP credentials"), "{}", err);
+        let seen = relay.join().unwrap();
+        assert!(!seen.iter().any(|c| c.starts_with("AUTH")), "{:?}", seen);
+    }
+
+    #[test]
+    fn dot_stuffing() {
+        assert_eq!(dot_stuff("Subject: x\r\n.hidden\r\n.\r\nend\r\n"), "Subject: x\r\n..hidden\r\n..\r\nend\r\n");
+        assert_eq!(dot_stuff("no dots\r\n"), "no dots\r\n");
+    }
+}
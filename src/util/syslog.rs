@@ -0,0 +1,190 @@
+use crate::alerts::{Alert, Severity};
+use crate::models::device::BlockDevice;
+use crate::util::webhook;
+use chrono::{Local, SecondsFormat};
+use std::os::unix::net::UnixDatagram;
+use std::path::Path;
+
+pub const SYSLOG_SOCKET:  &str = "/dev/log";
+pub const JOURNAL_SOCKET: &str = "/run/systemd/journal/socket";
+
+/// Private enterprise number for the RFC 5424 structured-data ID. 32473 is
+/// reserved for documentation and examples (RFC 5612), which fits a tool
+/// without its own registration.
+const SD_ID: &str = "dtop@32473";
+
+/// Where daemon output goes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sink {
+    Stderr,
+    Syslog,
+    Journald,
+}
+
+impl Sink {
+    /// Sinks named in `daemon.log`. "auto" picks journald when running under
+    /// systemd with its socket present, stderr otherwise; unknown names are skipped.
+    pub fn parse_list(names: &[String]) -> Vec<Sink> {
+        let mut out = Vec::new();
+        for name in names {
+            let sink = match name.as_str() {
+                "stderr"   => Sink::Stderr,
+                "syslog"   => Sink::Syslog,
+                "journald" | "journal" => Sink::Journald,
+                "auto" => {
+                    let journal = std::env::var_os("JOURNAL_STREAM").is_some() && Path::new(JOURNAL_SOCKET).exists();
+                    if journal { Sink::Journald } else { Sink::Stderr }
+                }
+                _ => continue,
+            };
+            if !out.contains(&sink) { out.push(sink); }
+        }
+        if out.is_empty() { out.push(Sink::Stderr); }
+        out
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Sink::Stderr   => "stderr",
+            Sink::Syslog   => "syslog",
+            Sink::Journald => "journald",
+        }
+    }
+}
+
+/// syslog(3) priority for an alert: crit, warning or info. Resolved alerts log at notice.
+pub fn priority(severity: &Severity, event: &str) -> u8 {
+    if event == "resolved" { return 5; }
+    match severity {
+        Severity::Critical => 2,
+        Severity::Warning  => 4,
+        Severity::Info     => 6,
+    }
+}
+
+/// Syslog facility code for a name ("daemon", "local0" … "local7"); daemon if unknown.
+pub fn facility(name: &str) -> u8 {
+    match name {
+        "user"   => 1,
+        "local0" => 16, "local1" => 17, "local2" => 18, "local3" => 19,
+        "local4" => 20, "local5" => 21, "local6" => 22, "local7" => 23,
+        _        => 3,
+    }
+}
+
+/// Structured fields describing one alert event.
+pub struct Fields<'a> {
+    pub event:  &'a str,
+    pub alert:  &'a Alert,
+    pub serial: Option<String>,
+    pub parent: Option<&'a str>,
+    /// For resolved events: seconds the alert was firing.
+    pub duration: Option<i64>,
+}
+
+impl<'a> Fields<'a> {
+    pub fn new(event: &'a str, alert: &'a Alert, devices: &[BlockDevice], parent: Option<&'a str>) -> Self {
+        let serial = alert.device.as_ref().and_then(|d| {
+            devices.iter().find(|dev| &dev.name == d).and_then(|dev| dev.serial.clone())
+        });
+        Self { event, alert, serial, parent, duration: None }
+    }
+
+    pub fn with_duration(mut self, secs: Option<i64>) -> Self {
+        self.duration = secs;
+        self
+    }
+
+    /// (name without the DTOP_ prefix, value) for every field that is set.
+    fn pairs(&self) -> Vec<(&'static str, String)> {
+        let a = self.alert;
+        let mut out = vec![("EVENT", self.event.to_string()), ("SEVERITY", a.severity.label().to_string())];
+        if let Some(d) = &a.device   { out.push(("DEVICE", d.clone())); }
+        if let Some(s) = &self.serial { out.push(("SERIAL", s.clone())); }
+        if let Some(m) = &a.mount    { out.push(("MOUNT", m.clone())); }
+        out.push(("RULE", a.rule.clone()));
+        if let Some(v) = a.value     { out.push(("VALUE", v.to_string())); }
+        if let Some(p) = self.parent { out.push(("PARENT", p.to_string())); }
+        if let Some(d) = self.duration { out.push(("DURATION", d.to_string())); }
+        out
+    }
+}
+
+/// An RFC 5424 message: `<PRI>1 TIMESTAMP HOST dtop PID EVENT [dtop@32473 …] MSG`.
+pub fn rfc5424(fields: &Fields, facility: u8, message: &str) -> String {
+    let pri = facility as u16 * 8 + priority(&fields.alert.severity, fields.event) as u16;
+    let sd: String = fields.pairs().iter()
+        .map(|(k, v)| format!(" {}=\"{}\"", k.to_lowercase(), sd_escape(v)))
+        .collect();
+    format!(
+        "<{}>1 {} {} dtop {} {} [{}{}] {}",
+        pri, Local::now().to_rfc3339_opts(SecondsFormat::Micros, false), webhook::hostname(),
+        std::process::id(), fields.event, SD_ID, sd, message,
+    )
+}
+
+/// PARAM-VALUE escaping: '"', '\' and ']' get a backslash.
+fn sd_escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"").replace(']', "\\]")
+}
+
+/// A journald native-protocol datagram with MESSAGE, PRIORITY, SYSLOG_IDENTIFIER
+/// and the DTOP_* fields.
+pub fn journal_entry(fields: &Fields, message: &str) -> Vec<u8> {
+    let mut out = Vec::new();
+    let mut put = |key: &str, value: &str| {
+        out.extend_from_slice(key.as_bytes());
+        if value.contains('\n') {
+            // Binary form: name, newline, little-endian length, value
+            out.push(b'\n');
+            out.extend_from_slice(&(value.len() as u64).to_le_bytes());
+            out.extend_from_slice(value.as_bytes());
+        } else {
+            out.push(b'=');
+            out.extend_from_slice(value.as_bytes());
+        }
+        out.push(b'\n');
+    };
+    put("MESSAGE", message);
+    put("PRIORITY", &priority(&fields.alert.severity, fields.event).to_string());
+    put("SYSLOG_IDENTIFIER", "dtop");
+    for (k, v) in fields.pairs() {
+        put(&format!("DTOP_{}", k), &v);
+    }
+    out
+}
+
+/// Writes daemon alert lines to the configured sinks. Socket errors are ignored:
+/// logging must never stop monitoring.
+pub struct Logger {
+    sinks:    Vec<Sink>,
+    facility: u8,
+    socket:   Option<UnixDatagram>,
+}
+
+impl Logger {
+    pub fn new(sinks: Vec<Sink>, facility: u8) -> Self {
+        Self { sinks, facility, socket: UnixDatagram::unbound().ok() }
+    }
+
+    pub fn sinks(&self) -> &[Sink] {
+        &self.sinks
+    }
+
+    /// Log `message` about an alert. Stderr gets it prefixed with the time.
+    pub fn alert(&self, fields: &Fields, message: &str) {
+        for sink in &self.sinks {
+            match sink {
+                Sink::Stderr   => eprintln!("{} {}", Local::now().format("%H:%M:%S"), message),
+                Sink::Syslog   => self.send(SYSLOG_SOCKET, rfc5424(fields, self.facility, message).as_bytes()),
+                Sink::Journald => self.send(JOURNAL_SOCKET, &journal_entry(fields, message)),
+            }
+        }
+    }
+
+    fn send(&self, path: &str, data: &[u8]) {
+        if let Some(sock) = &self.socket {
+            let _ = sock.send_to(data, path);
+        }
+    }
+}
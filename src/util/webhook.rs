@@ -1,34 +1,103 @@
-use crate::alerts::{Alert, Severity};
+use crate::alerts::Alert;
+use crate::config::ChannelConfig;
+use crate::util::correlate::Correlation;
+use crate::util::delivery::{self, Delivery};
+use crate::util::{human, payload};
+use serde_json::json;
+use std::collections::HashMap;
 
-/// Fire an HTTP POST to `url` with a Slack/Discord-compatible JSON payload.
-/// Runs in a detached background thread so it never blocks the UI.
-pub fn notify(alerts: &[Alert], url: &str, notify_warning: bool) {
-    if url.is_empty() { return; }
+/// Used when an endpoint has no `template`: Slack and Discord both accept it.
+pub const DEFAULT_TEMPLATE: &str = r#"{"text": "{{text}}"}"#;
 
-    let relevant: Vec<&Alert> = alerts.iter().filter(|a| {
-        a.severity == Severity::Critical || (notify_warning && a.severity == Severity::Warning)
-    }).collect();
-
-    if relevant.is_empty() { return; }
+/// Queue a POST of `alerts` to a webhook channel in its payload format.
+/// Delivery (with retries) happens on the background delivery thread, so this
+/// never blocks the UI. `durations` holds how long resolved conditions had been firing.
+pub fn post(ch: &ChannelConfig, alerts: &[&Alert], event: &str, corr: &Correlation, durations: &HashMap<String, i64>) {
+    if alerts.is_empty() { return; }
+    for req in payload::build(&ch.url, &ch.webhook, alerts, event, corr, durations) {
+        if req.url.is_empty() { continue; }
+        delivery::enqueue(Delivery::new(&ch.name, &req.url, &ch.webhook, req.body));
+    }
+}
 
-    let text = relevant.iter()
-        .map(|a| format!("[{}] {}{}", a.severity.label(), a.prefix(), a.message))
+/// One line per alert, children indented under their root cause. Resolved
+/// alerts say how long they were firing.
+pub fn text(alerts: &[&Alert], event: &str, corr: &Correlation, durations: &HashMap<String, i64>) -> String {
+    let tag = match event {
+        "reminder"  => "[reminder] ",
+        "escalated" => "[escalated] ",
+        "resolved"  => "[resolved] ",
+        _           => "",
+    };
+    let conds: Vec<String> = alerts.iter().map(|a| a.condition()).collect();
+    alerts.iter()
+        .map(|a| {
+            let mut line = format!("{}[{}] {}{}", tag, a.severity.label(), a.prefix(), a.message);
+            if let Some(d) = durations.get(&a.condition()).filter(|_| event == "resolved") {
+                line.push_str(&format!(" (after {})", human::fmt_duration_short((*d).max(0) as u64)));
+            }
+            match corr.parent_of(a) {
+                Some(p) if conds.iter().any(|c| c == p) => format!("  ↳ {}", line),
+                Some(p) => format!("{} (part of {})", line, p),
+                None    => line,
+            }
+        })
         .collect::<Vec<_>>()
-        .join("\\n");
+        .join("\n")
+}
+
+/// Fill in a payload template for `alerts`. String placeholders are JSON-escaped
+/// (the template supplies the quotes); `{{alerts}}` is a JSON array. Unknown
+/// placeholders are left as they are.
+pub fn render(alerts: &[&Alert], kind: &str, template: &str, corr: &Correlation, durations: &HashMap<String, i64>) -> String {
+    let text = text(alerts, kind, corr, durations);
+    let list: Vec<serde_json::Value> = alerts.iter().map(|a| json!({
+        "severity": a.severity.label(),
+        "device":   a.device,
+        "mount":    a.mount,
+        "rule":     a.rule,
+        "value":    a.value,
+        "message":  a.message,
+        "parent":   corr.parent_of(a),
+        "duration_secs": durations.get(&a.condition()),
+    })).collect();
+    // Longest time firing among resolved alerts, for `{{duration}}`
+    let duration = alerts.iter().filter_map(|a| durations.get(&a.condition())).max().copied().unwrap_or(0);
+    let worst = alerts.iter().map(|a| &a.severity).max().map(|s| s.label()).unwrap_or("INFO");
 
-    // Slack/Discord both accept {"text": "..."} as a minimal payload.
-    let payload = format!("{{\"text\":\"{}\"}}", text.replace('"', "\\\""));
-    let url = url.to_string();
+    let template = if template.is_empty() { DEFAULT_TEMPLATE } else { template };
+    // Single pass, so placeholder-like text inside alert messages is never expanded
+    let mut out  = String::with_capacity(template.len() + text.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        out.push_str(&rest[..start]);
+        let Some(len) = rest[start..].find("}}") else { rest = &rest[start..]; break };
+        let name = &rest[start + 2..start + len];
+        match name.trim() {
+            "alerts"    => out.push_str(&serde_json::Value::Array(list.clone()).to_string()),
+            "text"      => out.push_str(&escape(&text)),
+            "kind"      => out.push_str(&escape(kind)),
+            "severity"  => out.push_str(worst),
+            "count"     => out.push_str(&alerts.len().to_string()),
+            "duration"  => out.push_str(&duration.to_string()),
+            "host"      => out.push_str(&escape(&hostname())),
+            "timestamp" => out.push_str(&chrono::Local::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, false)),
+            _           => out.push_str(&rest[start..start + len + 2]),
+        }
+        rest = &rest[start + len + 2..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// `s` as the inside of a JSON string literal.
+fn escape(s: &str) -> String {
+    let quoted = serde_json::Value::String(s.to_string()).to_string();
+    quoted[1..quoted.len() - 1].to_string()
+}
 
-    std::thread::spawn(move || {
-        let _ = std::process::Command::new("curl")
-            .args([
-                "-s", "--max-time", "10",
-                "-X", "POST",
-                "-H", "Content-Type: application/json",
-                "-d", &payload,
-                &url,
-            ])
-            .output();
-    });
+pub fn hostname() -> String {
+    std::fs::read_to_string("/proc/sys/kernel/hostname")
+        .map(|s| s.trim().to_string())
+        .unwrap_or_else(|_| "unknown".to_string())
 }
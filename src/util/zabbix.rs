@@ -0,0 +1,204 @@
+use crate::alerts::Severity;
+use crate::models::device::BlockDevice;
+use crate::models::smart::{SmartData, SmartStatus};
+use crate::util::health_score::health_score;
+use crate::util::metrics::Snapshot;
+use anyhow::{anyhow, bail, Result};
+use serde_json::{json, Map, Value};
+
+/// Kinds accepted by `--zabbix-discovery`.
+const DISCOVERY_KINDS: &[&str] = &["devices", "filesystems", "arrays"];
+
+/// What a key or discovery reads, so callers collect only that.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    /// Block devices with SMART data.
+    Devices,
+    Filesystems,
+    /// md arrays and ZFS pools.
+    Arrays,
+    /// The active alerts, which need everything.
+    Alerts,
+}
+
+/// The source `discovery(kind, …)` reads.
+pub fn discovery_source(kind: &str) -> Result<Source> {
+    match kind {
+        "devices"     => Ok(Source::Devices),
+        "filesystems" => Ok(Source::Filesystems),
+        "arrays"      => Ok(Source::Arrays),
+        _ => bail!("unknown discovery {:?} (expected {})", kind, DISCOVERY_KINDS.join(", ")),
+    }
+}
+
+/// The source `get(key, …)` reads, from the key's namespace.
+pub fn source(key: &str) -> Result<Source> {
+    let (name, _) = parse_key(key)?;
+    Ok(match name.split('.').next().unwrap_or(name) {
+        "device" | "smart" | "nvme" => Source::Devices,
+        "fs"                        => Source::Filesystems,
+        "array"                     => Source::Arrays,
+        "alerts"                    => Source::Alerts,
+        _ => bail!("unsupported key {:?}", name),
+    })
+}
+
+/// Low-level discovery JSON for `kind`, wrapped in `{"data": […]}` so every
+/// Zabbix version since 2.0 reads it.
+pub fn discovery(kind: &str, s: &Snapshot) -> Result<String> {
+    let rows: Vec<Value> = match kind {
+        "devices" => s.devices.iter().map(|d| macros(&[
+            ("DEVICE", d.name.clone()),
+            ("SERIAL", d.serial.clone().unwrap_or_default()),
+            ("MODEL",  d.model.clone().unwrap_or_default()),
+            ("ALIAS",  d.alias.clone().unwrap_or_else(|| d.name.clone())),
+            ("TYPE",   d.dev_type.label().trim().to_string()),
+        ])).collect(),
+        "filesystems" => s.filesystems.iter().map(|fs| macros(&[
+            ("MOUNT",    fs.mount.clone()),
+            ("FSDEVICE", fs.device.clone()),
+            ("FSTYPE",   fs.fs_type.clone()),
+        ])).collect(),
+        "arrays" => s.raids.iter()
+            .map(|r| macros(&[("ARRAY", r.name.clone()), ("ARRAYTYPE", "md".into()), ("LEVEL", r.level.clone())]))
+            .chain(s.pools.iter()
+                .map(|p| macros(&[("ARRAY", p.name.clone()), ("ARRAYTYPE", "zfs".into()), ("LEVEL", String::new())])))
+            .collect(),
+        _ => bail!("unknown discovery {:?} (expected {})", kind, DISCOVERY_KINDS.join(", ")),
+    };
+    Ok(json!({ "data": rows }).to_string())
+}
+
+fn macros(pairs: &[(&str, String)]) -> Value {
+    let map: Map<String, Value> = pairs.iter()
+        .map(|(k, v)| (format!("{{#{}}}", k), Value::String(v.clone())))
+        .collect();
+    Value::Object(map)
+}
+
+/// Split an item key into its name and parameters:
+/// `smart.attr[sda,5]` → ("smart.attr", ["sda", "5"]). Parameters may be
+/// double-quoted to hold commas or brackets.
+fn parse_key(key: &str) -> Result<(&str, Vec<String>)> {
+    let Some((name, rest)) = key.split_once('[') else { return Ok((key, Vec::new())) };
+    let inner = rest.strip_suffix(']').ok_or_else(|| anyhow!("unterminated parameters in {:?}", key))?;
+    let mut params = Vec::new();
+    let mut cur = String::new();
+    let mut quoted = false;
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => quoted = !quoted,
+            '\\' if quoted => if let Some(n) = chars.next() { cur.push(n) },
+            ',' if !quoted => params.push(std::mem::take(&mut cur).trim().to_string()),
+            _ => cur.push(c),
+        }
+    }
+    if quoted { bail!("unterminated quote in {:?}", key); }
+    params.push(cur.trim().to_string());
+    Ok((name, params))
+}
+
+/// The value of one item key, as printed for a Zabbix UserParameter.
+pub fn get(key: &str, s: &Snapshot) -> Result<String> {
+    let (name, params) = parse_key(key)?;
+    let arg = |i: usize| params.get(i).map(|p| p.as_str()).filter(|p| !p.is_empty());
+    let need = |i: usize, what: &str| arg(i).ok_or_else(|| anyhow!("{}: missing {}", name, what));
+
+    let value = match name {
+        "device.health" => health_score(device(s, need(0, "device")?)?).to_string(),
+        "device.status" => {
+            let status = &smart(device(s, need(0, "device")?)?)?.status;
+            match status {
+                SmartStatus::Unknown => "0",
+                SmartStatus::Passed  => "1",
+                SmartStatus::Warning => "2",
+                SmartStatus::Failed  => "3",
+            }.to_string()
+        }
+        "device.temp" => smart(device(s, need(0, "device")?)?)?.temperature
+            .ok_or_else(|| anyhow!("no temperature reported"))?.to_string(),
+        "device.power_on_hours" => smart(device(s, need(0, "device")?)?)?.power_on_hours
+            .ok_or_else(|| anyhow!("no power-on hours reported"))?.to_string(),
+        "device.capacity" => device(s, need(0, "device")?)?.capacity_bytes.to_string(),
+        "smart.attr" => {
+            let data = smart(device(s, need(0, "device")?)?)?;
+            let id = need(1, "attribute")?;
+            let a = data.attributes.iter()
+                .find(|a| a.id.to_string() == id || a.name.eq_ignore_ascii_case(id))
+                .ok_or_else(|| anyhow!("no SMART attribute {}", id))?;
+            match arg(2).unwrap_or("raw") {
+                "raw"    => a.raw_value.to_string(),
+                "value"  => a.value.to_string(),
+                "worst"  => a.worst.to_string(),
+                "thresh" => a.thresh.to_string(),
+                other    => bail!("unknown field {:?} (expected raw, value, worst or thresh)", other),
+            }
+        }
+        "nvme" => {
+            let n = smart(device(s, need(0, "device")?)?)?.nvme.as_ref()
+                .ok_or_else(|| anyhow!("not an NVMe device"))?;
+            match need(1, "field")? {
+                "critical_warning"  => n.critical_warning.to_string(),
+                "available_spare"   => n.available_spare_pct.to_string(),
+                "percentage_used"   => n.percentage_used.to_string(),
+                "media_errors"      => n.media_errors.to_string(),
+                "unsafe_shutdowns"  => n.unsafe_shutdowns.to_string(),
+                "error_log_entries" => n.error_log_entries.to_string(),
+                "data_read"         => n.bytes_read().to_string(),
+                "data_written"      => n.bytes_written().to_string(),
+                other               => bail!("unknown NVMe field {:?}", other),
+            }
+        }
+        "fs.pct" | "fs.inode_pct" | "fs.used" | "fs.free" | "fs.size" => {
+            let mount = need(0, "mount point")?;
+            let fs = s.filesystems.iter().find(|f| f.mount == mount)
+                .ok_or_else(|| anyhow!("no filesystem mounted at {}", mount))?;
+            match name {
+                "fs.pct"       => format!("{:.2}", fs.use_pct()),
+                "fs.inode_pct" => format!("{:.2}", fs.inode_pct()),
+                "fs.used"      => fs.used_bytes.to_string(),
+                "fs.free"      => fs.avail_bytes.to_string(),
+                _              => fs.total_bytes.to_string(),
+            }
+        }
+        "array.degraded" | "array.state" | "array.rebuild" => {
+            let array = need(0, "array")?;
+            if let Some(r) = s.raids.iter().find(|r| r.name == array) {
+                match name {
+                    "array.degraded" => (r.degraded as u8).to_string(),
+                    "array.state"    => r.state.clone(),
+                    _                => format!("{:.1}", r.rebuild_pct.unwrap_or(100.0)),
+                }
+            } else if let Some(p) = s.pools.iter().find(|p| p.name == array) {
+                match name {
+                    "array.degraded" => (!p.is_healthy() as u8).to_string(),
+                    "array.state"    => p.health.clone(),
+                    _                => "100.0".to_string(),
+                }
+            } else {
+                bail!("no md array or ZFS pool named {}", array);
+            }
+        }
+        "alerts.active" => {
+            let sev = match arg(0).unwrap_or("all") {
+                "all"                  => None,
+                "crit" | "critical"    => Some(Severity::Critical),
+                "warn" | "warning"     => Some(Severity::Warning),
+                "info"                 => Some(Severity::Info),
+                other => bail!("unknown severity {:?} (expected all, critical, warning or info)", other),
+            };
+            s.alerts.iter().filter(|a| sev.as_ref().is_none_or(|sev| &a.severity == sev)).count().to_string()
+        }
+        _ => bail!("unsupported key {:?}", name),
+    };
+    Ok(value)
+}
+
+fn device<'a>(s: &'a Snapshot, name: &str) -> Result<&'a BlockDevice> {
+    s.device(name).ok_or_else(|| anyhow!("no device {}", name))
+}
+
+fn smart(dev: &BlockDevice) -> Result<&SmartData> {
+    dev.smart.as_ref().ok_or_else(|| anyhow!("no SMART data for {}", dev.name))
+}